@@ -0,0 +1,55 @@
+-- Tables that existed before the migrations, IF NOT EXISTS lets this version be
+-- recorded on databases that were created by hand.
+
+CREATE TABLE IF NOT EXISTS persona (
+    id_persona TEXT PRIMARY KEY NOT NULL,
+    nombre TEXT NOT NULL,
+    contrasena TEXT NOT NULL,
+    correo TEXT NOT NULL,
+    telefono INTEGER NOT NULL,
+    identificacion TEXT NOT NULL,
+    nombre_tipo_identificacion TEXT NOT NULL,
+    nombre_rol TEXT NOT NULL DEFAULT 'Usuario'
+);
+
+CREATE TABLE IF NOT EXISTS matricula (
+    id_matricula INTEGER PRIMARY KEY AUTOINCREMENT,
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    monto_usd REAL NOT NULL,
+    fecha_inscripccion TEXT NOT NULL DEFAULT CURRENT_DATE
+);
+
+CREATE INDEX IF NOT EXISTS idx_matricula_id_persona ON matricula (id_persona);
+
+CREATE TABLE IF NOT EXISTS torneo (
+    id_torneo TEXT PRIMARY KEY NOT NULL,
+    nombre TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS persona_torneo (
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    id_torneo TEXT NOT NULL REFERENCES torneo (id_torneo),
+    puesto INTEGER NOT NULL,
+    PRIMARY KEY (id_persona, id_torneo)
+);
+
+CREATE TABLE IF NOT EXISTS entrenamiento (
+    id_entrenamiento TEXT PRIMARY KEY NOT NULL,
+    tiempo_minutos INTEGER NOT NULL,
+    nombre_entrenamiento TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS entrenamiento_persona (
+    id_entrenamiento TEXT NOT NULL REFERENCES entrenamiento (id_entrenamiento),
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    PRIMARY KEY (id_entrenamiento, id_persona)
+);
+
+CREATE TABLE IF NOT EXISTS request_for_approval (
+    request_id TEXT PRIMARY KEY NOT NULL,
+    requester_id TEXT NOT NULL REFERENCES persona (id_persona),
+    command_name TEXT NOT NULL,
+    command_content TEXT NOT NULL,
+    aprover_id TEXT REFERENCES persona (id_persona),
+    completed INTEGER NOT NULL DEFAULT 0
+);
@@ -0,0 +1,12 @@
+CREATE TABLE session (
+    session_id TEXT PRIMARY KEY NOT NULL,
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    nombre_rol TEXT NOT NULL,
+    refresh_token_hash TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    last_used_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    revoked INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_session_id_persona ON session (id_persona);
@@ -0,0 +1,6 @@
+CREATE TABLE password_reset (
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    expires_at INTEGER NOT NULL,
+    used INTEGER NOT NULL DEFAULT 0
+);
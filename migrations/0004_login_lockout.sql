@@ -0,0 +1,12 @@
+ALTER TABLE persona ADD COLUMN failed_login_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE persona ADD COLUMN locked_until INTEGER;
+
+CREATE TABLE login_history (
+    id_login TEXT PRIMARY KEY NOT NULL,
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    ip_address TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_login_history_id_persona ON login_history (id_persona, created_at);
@@ -0,0 +1,19 @@
+CREATE TABLE mfa (
+    id_persona TEXT PRIMARY KEY NOT NULL REFERENCES persona (id_persona),
+    secret TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 0,
+    last_used_step INTEGER
+);
+
+CREATE TABLE mfa_recovery_code (
+    code_hash TEXT NOT NULL,
+    id_persona TEXT NOT NULL REFERENCES persona (id_persona),
+    used INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (id_persona, code_hash)
+);
+
+CREATE TABLE mfa_required_role (
+    nombre_rol TEXT PRIMARY KEY NOT NULL
+);
+
+ALTER TABLE session ADD COLUMN mfa_verified INTEGER NOT NULL DEFAULT 0;
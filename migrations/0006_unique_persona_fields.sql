@@ -0,0 +1,7 @@
+-- Fails when the table already holds duplicates, they have to be merged by hand first
+CREATE UNIQUE INDEX idx_persona_correo ON persona (correo);
+
+CREATE UNIQUE INDEX idx_persona_telefono ON persona (telefono);
+
+CREATE UNIQUE INDEX idx_persona_identificacion
+    ON persona (nombre_tipo_identificacion, identificacion);
@@ -0,0 +1,23 @@
+-- Phones move from an INTEGER to E.164 text. The column is rebuilt because the INTEGER
+-- affinity would turn '+573185920708' back into a number. The existing phones are taken
+-- as Colombian unless they already carry the 57 in front of the 10 digits, the same rule
+-- `PhoneNormalizer::normalize` applies to the new ones.
+--
+-- The country code is hardcoded to 57: the `default_phone_country_code` setting does not
+-- reach the SQL migrations. A deployment with another default has to convert its phones
+-- itself before running this migration.
+DROP INDEX idx_persona_telefono;
+
+ALTER TABLE persona ADD COLUMN telefono_e164 TEXT NOT NULL DEFAULT '';
+
+UPDATE persona SET telefono_e164 = CASE
+    WHEN length(CAST(telefono AS TEXT)) = 12 AND CAST(telefono AS TEXT) LIKE '57%'
+        THEN '+' || CAST(telefono AS TEXT)
+    ELSE '+57' || CAST(telefono AS TEXT)
+END;
+
+ALTER TABLE persona DROP COLUMN telefono;
+
+ALTER TABLE persona RENAME COLUMN telefono_e164 TO telefono;
+
+CREATE UNIQUE INDEX idx_persona_telefono ON persona (telefono);
@@ -0,0 +1,2 @@
+-- Logins by document number look it up without the type, the unique index starts with it
+CREATE INDEX idx_persona_identificacion_numero ON persona (identificacion);
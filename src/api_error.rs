@@ -0,0 +1,265 @@
+use axum::{
+    extract::Request,
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info_span, warn, Instrument};
+use utoipa::ToSchema;
+
+/// Header with the id of the request, taken from the caller when it sends a valid one.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Body of every error response, `code` is stable so clients can match on it while the
+/// message is only meant for people.
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    /// The input the error is about, like the column that collided on a duplicate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Every invalid field of the payload, only on validation errors
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    pub request_id: Option<String>,
+}
+
+/// A rule broken by one field of the payload.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// An error on its way to become a JSON response.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    field: Option<String>,
+    errors: Vec<FieldError>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl ToString) -> Self {
+        Self {
+            status,
+            code,
+            message: message.to_string(),
+            field: None,
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn not_found(code: &'static str, message: impl ToString) -> Self {
+        Self::new(StatusCode::NOT_FOUND, code, message)
+    }
+
+    pub fn conflict(code: &'static str, message: impl ToString) -> Self {
+        Self::new(StatusCode::CONFLICT, code, message)
+    }
+
+    /// Another row already has the value of `field`.
+    pub fn duplicate(field: &str) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            ..Self::conflict(
+                "duplicate",
+                format!("There is already a record with this {field}"),
+            )
+        }
+    }
+
+    /// The request points at a row that does not exist.
+    pub fn invalid_reference() -> Self {
+        Self::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "invalid_reference",
+            "The request references a record that does not exist",
+        )
+    }
+
+    pub fn validation(errors: &[FieldError]) -> Self {
+        Self {
+            errors: errors.to_vec(),
+            ..Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                "Some fields are not valid",
+            )
+        }
+    }
+
+    /// The payload could not be read at all, unlike [`ApiError::validation`] where it was
+    /// read but broke a rule.
+    pub fn malformed(errors: &[FieldError]) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            ..Self::validation(errors)
+        }
+    }
+
+    pub fn payload_too_large() -> Self {
+        Self::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "payload_too_large",
+            "The request body is too large",
+        )
+    }
+
+    pub fn unauthorized(code: &'static str, message: impl ToString) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, code, message)
+    }
+
+    pub fn forbidden(code: &'static str, message: impl ToString) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, message)
+    }
+
+    /// Logs the error and hides it from the caller, it can carry database details.
+    pub fn internal(err: &dyn std::error::Error) -> Self {
+        error!("Internal error: {err}");
+
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "Something went wrong on our side",
+        )
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_client_error() {
+            warn!("Request failed with {}: {}", self.code, self.message);
+        }
+
+        let body = ErrorBody {
+            code: self.code.to_string(),
+            message: self.message,
+            field: self.field,
+            errors: self.errors,
+            request_id: current_request_id(),
+        };
+
+        (self.status, Json(body)).into_response()
+    }
+}
+
+/// Id of the request being served, `None` outside of [`request_id_middleware`].
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Gives every request an id, available to the handlers through [`current_request_id`],
+/// added to its logs and sent back in [`REQUEST_ID_HEADER`].
+pub async fn request_id_middleware(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        uri = %request.uri()
+    );
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LENGTH
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::to_bytes, body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/missing",
+                get(|| async { ApiError::not_found("thing_not_found", "No such thing") }),
+            )
+            .layer(middleware::from_fn(request_id_middleware))
+    }
+
+    async fn call(request_id: Option<&str>) -> (Response, ErrorBody) {
+        let mut request = Request::builder().uri("/missing");
+        if let Some(request_id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, request_id);
+        }
+
+        let response = router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+
+        (
+            Response::from_parts(parts, Body::empty()),
+            serde_json::from_slice(&bytes).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_error_body_carries_the_request_id() {
+        let (response, body) = call(Some("front-1234")).await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "front-1234");
+        assert_eq!(
+            body,
+            ErrorBody {
+                code: "thing_not_found".to_string(),
+                message: "No such thing".to_string(),
+                field: None,
+                errors: Vec::new(),
+                request_id: Some("front-1234".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request_id_is_replaced() {
+        let (response, body) = call(Some("not valid\tid")).await;
+
+        let request_id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(request_id).is_ok());
+        assert_eq!(body.request_id.as_deref(), Some(request_id));
+
+        let (response, _) = call(None).await;
+        assert!(response.headers().contains_key(REQUEST_ID_HEADER));
+    }
+}
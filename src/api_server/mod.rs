@@ -1,15 +1,142 @@
-use std::error::Error;
+use std::{error::Error, future::Future, net::SocketAddr, sync::Arc, time::Duration};
 
-use axum::Router;
-use tower_http::cors::CorsLayer;
-use tracing::info;
+use axum::{
+    extract::{DefaultBodyLimit, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Extension, Router,
+};
+use tokio::{net::TcpListener, sync::Notify};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower_http::{cors::CorsLayer, limit::RequestBodyLimitLayer};
+use tracing::{info, warn};
 
-use crate::global_traits::HttpService;
+use crate::{
+    api_error::{request_id_middleware, ApiError, REQUEST_ID_HEADER},
+    auth_middleware::CSRF_HEADER,
+    client_ip::TrustProxyHeader,
+    global_traits::HttpService,
+    health_service::endpoints::HealthHttpServer,
+    mfa_service::endpoints::MfaHttpServer,
+    openapi::{docs_router, openapi_document},
+    phone::PhoneNormalizer,
+    repositories::Repositories,
+    requests_service::endpoints::RequestHttpServer,
+    route_access::AccessControl,
+    session_service::endpoints::SessionHttpServer,
+    tournament_service::endpoints::TournamentHttpServer,
+    trainings_service::endpoints::TrainingHttpServer,
+    tuition_service::endpoints::TuitionHttpServer,
+    unique_identifier_service::{
+        cache::IdentifierCacheConfig, endpoints::UniqueIdentifierHttpServer,
+        usecases::build_unique_identifier,
+    },
+    user_service::{endpoints::UserHttpServer, notifier::Notifier},
+};
 
-pub async fn start_http_server(
-    port: String,
-    http_services: Vec<Box<dyn HttpService>>,
-) -> Result<(), Box<dyn Error>> {
+/// Every service of the API, built over the same repositories.
+pub async fn build_http_services(
+    repositories: &Repositories,
+    token_key: &str,
+    notifier: Arc<dyn Notifier>,
+    phone_normalizer: PhoneNormalizer,
+) -> Vec<Box<dyn HttpService>> {
+    let unique_identifier = build_unique_identifier(
+        repositories.unique_identifier.clone(),
+        phone_normalizer.clone(),
+        IdentifierCacheConfig::default(),
+    );
+
+    let access_control = AccessControl::new(token_key.to_string(), repositories.session.clone());
+
+    vec![
+        Box::new(
+            UserHttpServer::new(
+                token_key.to_string(),
+                unique_identifier.clone(),
+                repositories.user.clone(),
+                repositories.session.clone(),
+                repositories.mfa.clone(),
+                notifier,
+                repositories.unit_of_work.clone(),
+                phone_normalizer.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            TournamentHttpServer::new(
+                repositories.tournament.clone(),
+                unique_identifier.clone(),
+                repositories.unit_of_work.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            TrainingHttpServer::new(
+                repositories.training.clone(),
+                unique_identifier.clone(),
+                repositories.unit_of_work.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            TuitionHttpServer::new(
+                repositories.tuition.clone(),
+                unique_identifier.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            UniqueIdentifierHttpServer::new(
+                repositories.unique_identifier.clone(),
+                phone_normalizer.clone(),
+                unique_identifier.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            RequestHttpServer::new(
+                repositories.user.clone(),
+                repositories.session.clone(),
+                repositories.tournament.clone(),
+                repositories.request.clone(),
+                repositories.training.clone(),
+                unique_identifier.clone(),
+                token_key.to_string(),
+                repositories.unit_of_work.clone(),
+                phone_normalizer,
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            SessionHttpServer::new(
+                repositories.session.clone(),
+                token_key.to_string(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(
+            MfaHttpServer::new(
+                repositories.mfa.clone(),
+                repositories.user.clone(),
+                access_control.clone(),
+            )
+            .await,
+        ),
+        Box::new(HealthHttpServer::new(repositories.health.clone(), access_control).await),
+    ]
+}
+
+/// Merges the routers of the services into the one the server runs.
+pub fn build_router(http_services: Vec<Box<dyn HttpService>>) -> Router {
     let mut main_router = Router::new();
 
     for http_service in http_services {
@@ -18,16 +145,507 @@ pub async fn start_http_server(
         main_router = main_router.merge(service_router);
     }
 
+    main_router
+}
+
+/// Limits of the HTTP server, taken from the `request_*`, `max_concurrent_requests` and
+/// `shutdown_drain_seconds` settings.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// How long a request may take, counting the wait for a free slot, before a 408
+    pub request_timeout: Duration,
+    /// Largest request body accepted, bigger ones get a 413
+    pub body_limit: usize,
+    /// Requests served at the same time, the next ones wait for a free slot
+    pub max_concurrent_requests: usize,
+    /// How long the requests in flight get to finish once a shutdown signal arrives
+    pub shutdown_drain: Duration,
+    /// Take the client address from the header of the fly.io proxy
+    pub trust_proxy_header: bool,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            body_limit: 1024 * 1024,
+            max_concurrent_requests: 256,
+            shutdown_drain: Duration::from_secs(25),
+            trust_proxy_header: false,
+        }
+    }
+}
+
+/// The merged router with the API docs and the layers the server puts around every route.
+pub fn app_router(
+    http_services: Vec<Box<dyn HttpService>>,
+    cors_allowed_origins: Option<String>,
+    server_config: &ServerConfig,
+) -> Result<Router, Box<dyn Error>> {
+    let cors_layer = cors_layer(cors_allowed_origins)?;
+    let openapi = openapi_document(&http_services);
+
+    let mut router = with_limits(
+        build_router(http_services)
+            .merge(docs_router(openapi))
+            .fallback(route_not_found)
+            .method_not_allowed_fallback(method_not_allowed),
+        server_config,
+    );
+    if server_config.trust_proxy_header {
+        router = router.layer(Extension(TrustProxyHeader));
+    }
+
+    Ok(router
+        .layer(middleware::from_fn(request_id_middleware))
+        .layer(cors_layer))
+}
+
+/// The body limit replaces the default one of the extractors, the concurrency limit is
+/// shared by every route and the wait for it counts against the timeout.
+fn with_limits(router: Router, server_config: &ServerConfig) -> Router {
+    router
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(server_config.body_limit))
+        .layer(middleware::map_response(payload_too_large_as_json))
+        .layer(GlobalConcurrencyLimitLayer::new(
+            server_config.max_concurrent_requests,
+        ))
+        .layer(middleware::from_fn_with_state(
+            server_config.request_timeout,
+            timeout_middleware,
+        ))
+}
+
+/// The body limit answers in plain text when the `Content-Length` is already over it, the
+/// bodies that outgrow it while read are rejected by the extractors.
+async fn payload_too_large_as_json(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|content_type| content_type == "application/json");
+
+    match response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        true => ApiError::payload_too_large().into_response(),
+        false => response,
+    }
+}
+
+async fn route_not_found() -> ApiError {
+    ApiError::not_found("route_not_found", "No route matches the path")
+}
+
+async fn method_not_allowed() -> ApiError {
+    ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "method_not_allowed",
+        "The route does not accept the method",
+    )
+}
+
+async fn timeout_middleware(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => {
+            warn!("Request cut off after {timeout:?}");
+            ApiError::new(
+                StatusCode::REQUEST_TIMEOUT,
+                "request_timeout",
+                "The request took too long",
+            )
+            .into_response()
+        }
+    }
+}
+
+pub async fn start_http_server(
+    port: String,
+    cors_allowed_origins: Option<String>,
+    http_services: Vec<Box<dyn HttpService>>,
+    server_config: ServerConfig,
+) -> Result<(), Box<dyn Error>> {
+    let main_router = app_router(http_services, cors_allowed_origins, &server_config)?;
+
     let ip_addr = format!("0.0.0.0:{port}");
-    let listener = tokio::net::TcpListener::bind(ip_addr).await?;
+    let listener = TcpListener::bind(ip_addr).await?;
 
     info!("Listenig in the port: {port}");
 
-    let cors_layer = CorsLayer::permissive();
+    serve(
+        listener,
+        main_router,
+        server_config.shutdown_drain,
+        shutdown_signal(),
+    )
+    .await
+}
+
+/// Serves until `signal` resolves, then stops accepting connections and waits up to
+/// `drain` for the requests in flight before dropping them.
+async fn serve(
+    listener: TcpListener,
+    router: Router,
+    drain: Duration,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), Box<dyn Error>> {
+    let draining = Arc::new(Notify::new());
+    let drain_started = draining.clone();
 
-    main_router = main_router.layer(cors_layer);
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        signal.await;
+        info!("Shutting down, waiting up to {drain:?} for the requests in flight");
+        drain_started.notify_one();
+    });
 
-    axum::serve(listener, main_router).await?;
+    tokio::select! {
+        result = server => result?,
+        _ = async {
+            draining.notified().await;
+            tokio::time::sleep(drain).await;
+        } => warn!("Drain period over, dropping the requests still in flight"),
+    }
 
     Ok(())
 }
+
+/// Resolves on the first SIGINT (Ctrl+C) or SIGTERM, the one Fly sends to stop a machine.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            warn!("Cannot listen for SIGINT: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("Cannot listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
+/// Only the origins in the comma separated list can call the API from a browser, without
+/// the list no cross origin request is allowed.
+fn cors_layer(cors_allowed_origins: Option<String>) -> Result<CorsLayer, Box<dyn Error>> {
+    let origins = match cors_allowed_origins {
+        Some(cors_allowed_origins) => cors_allowed_origins
+            .split(',')
+            .map(|origin| origin.trim())
+            .filter(|origin| !origin.is_empty())
+            .map(HeaderValue::from_str)
+            .collect::<Result<Vec<_>, _>>()?,
+        None => {
+            warn!("CORS_ALLOWED_ORIGINS is not set, cross origin requests are rejected");
+            Vec::new()
+        }
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_credentials(true)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::DELETE,
+            Method::OPTIONS,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            HeaderName::from_static(CSRF_HEADER),
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers([
+            header::RETRY_AFTER,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::StatusCode, routing::get};
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::oneshot,
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{test_harness::TestApp, user_service::domain::UserRol};
+
+    fn slow_router(delay: Duration) -> Router {
+        Router::new().route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        )
+    }
+
+    async fn get_slow(router: &Router) -> StatusCode {
+        let request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+
+        router.clone().oneshot(request).await.unwrap().status()
+    }
+
+    /// Starts `serve` on a free port, the server shuts down when the sender is used.
+    async fn spawn_server(
+        router: Router,
+        drain: Duration,
+    ) -> (SocketAddr, oneshot::Sender<()>, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (shutdown, signal) = oneshot::channel();
+
+        let server = tokio::spawn(async move {
+            serve(listener, router, drain, async {
+                let _ = signal.await;
+            })
+            .await
+            .unwrap();
+        });
+
+        (addr, shutdown, server)
+    }
+
+    async fn send_slow_request(addr: SocketAddr) -> TcpStream {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        stream
+    }
+
+    #[tokio::test]
+    async fn test_full_router_on_in_memory_repositories() {
+        let app = TestApp::with_repositories(Repositories::in_memory()).await;
+        let admin = app.create_user("esteban", UserRol::Admin).await;
+
+        let response = app
+            .post(
+                "/api/v1/tuitions",
+                Some(&admin.token),
+                json!({ "id_persona": admin.id_persona, "monto_usd": 20.0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        let response = app
+            .get("/api/v1/users/search/esteban/Email", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["items"][0]["matricula_valida"], json!(true));
+    }
+
+    async fn allowed_origin(cors_allowed_origins: Option<&str>, origin: &str) -> Option<String> {
+        let router = slow_router(Duration::ZERO)
+            .layer(cors_layer(cors_allowed_origins.map(str::to_string)).unwrap());
+        let request = Request::builder()
+            .uri("/slow")
+            .header(header::ORIGIN, origin)
+            .body(Body::empty())
+            .unwrap();
+
+        let response = router.oneshot(request).await.unwrap();
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_cors_without_origins_allows_none() {
+        assert_eq!(allowed_origin(None, "https://evil.example").await, None);
+
+        let origins = Some("https://sabana.example, https://admin.example");
+        assert_eq!(
+            allowed_origin(origins, "https://admin.example").await,
+            Some("https://admin.example".to_string())
+        );
+        assert_eq!(allowed_origin(origins, "https://evil.example").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_only_the_routes_older_than_api_v1_have_legacy_aliases() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("esteban", UserRol::Admin).await;
+
+        let response = app.get("/check_email/nadie@unisabana.edu.co", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.headers.contains_key("deprecation"));
+
+        for (method, uri) in [
+            (Method::POST, "/token/refresh"),
+            (Method::POST, "/log_out"),
+            (Method::GET, "/user/sessions"),
+            (Method::POST, "/log_in/mfa"),
+            (Method::GET, "/user/login_history"),
+            (Method::PUT, "/user/password"),
+            (Method::POST, "/password/forgot"),
+            (Method::POST, "/user/mfa/enroll"),
+            (Method::GET, "/mfa/required_roles"),
+            (Method::GET, "/identifier_cache/stats"),
+        ] {
+            let response = app
+                .call(method.clone(), uri, Some(&admin.token), Some(json!({})))
+                .await;
+            assert!(
+                matches!(
+                    response.status,
+                    StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+                ),
+                "{method} {uri} answered {}",
+                response.status
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_body_over_the_limit_is_rejected() {
+        let app = TestApp::new().await;
+        let body_limit = ServerConfig::default().body_limit;
+
+        let response = app
+            .post(
+                "/api/v1/auth/login",
+                None,
+                json!({ "identifier": "a".repeat(body_limit), "contrasena": "clave" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.body["code"], "payload_too_large");
+
+        // A declared length over the limit is turned away before the body is read
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_LENGTH, body_limit + 1)
+            .body(Body::from("a".repeat(body_limit + 1)))
+            .unwrap();
+        let response = app.send(request).await;
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.body["code"], "payload_too_large");
+        assert!(response.body["request_id"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_routes_and_methods_are_error_bodies() {
+        let app = TestApp::new().await;
+
+        let response = app.get("/api/v1/nada", None).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "route_not_found");
+        assert!(response.body["request_id"].is_string());
+
+        let response = app.delete("/api/v1/tournaments", None).await;
+        assert_eq!(response.status, StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.body["code"], "method_not_allowed");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_slow_request_times_out() {
+        let server_config = ServerConfig {
+            request_timeout: Duration::from_millis(50),
+            ..ServerConfig::default()
+        };
+
+        let router = with_limits(slow_router(Duration::from_millis(20)), &server_config);
+        assert_eq!(get_slow(&router).await, StatusCode::OK);
+
+        let router = with_limits(slow_router(Duration::from_secs(1)), &server_config);
+        let request = Request::builder().uri("/slow").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["code"], "request_timeout");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waiting_for_a_free_slot_counts_against_the_timeout() {
+        let server_config = ServerConfig {
+            request_timeout: Duration::from_millis(60),
+            max_concurrent_requests: 1,
+            ..ServerConfig::default()
+        };
+        let router = with_limits(slow_router(Duration::from_millis(40)), &server_config);
+
+        let (first, second) = tokio::join!(get_slow(&router), get_slow(&router));
+        let mut statuses = [first, second];
+        statuses.sort();
+        assert_eq!(statuses, [StatusCode::OK, StatusCode::REQUEST_TIMEOUT]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_waits_for_the_requests_in_flight() {
+        let (addr, shutdown, server) = spawn_server(
+            slow_router(Duration::from_millis(300)),
+            Duration::from_secs(5),
+        )
+        .await;
+
+        let mut stream = send_slow_request(addr).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.send(()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.ends_with("done"), "{response}");
+
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(TcpStream::connect(addr).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_gives_up_after_the_drain_period() {
+        let (addr, shutdown, server) = spawn_server(
+            slow_router(Duration::from_secs(60)),
+            Duration::from_millis(100),
+        )
+        .await;
+
+        let _stream = send_slow_request(addr).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.send(()).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("the server kept waiting after the drain period")
+            .unwrap();
+    }
+}
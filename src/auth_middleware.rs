@@ -1,26 +1,119 @@
+use std::sync::Arc;
+
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
+    http::{HeaderMap, Method},
     middleware::Next,
-    response::IntoResponse,
+    response::Response,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use serde::Deserialize;
+
+use tracing::{error, warn};
+use utoipa::IntoParams;
+
+use crate::{
+    api_error::ApiError,
+    session_service::{domain::TokenPair, repository::SessionRepository},
+    user_service::{
+        domain::UserRol,
+        token_provider::{generate_secret, TokenProvider},
+    },
 };
 
-use tracing::{error, info};
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+/// Only the refresh and the logout are sent the refresh token cookie
+pub const REFRESH_TOKEN_COOKIE_PATH: &str = "/api/v1/token";
+/// Readable by the frontend, which has to send it back in [`CSRF_HEADER`]
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Query of the endpoints that hand out access tokens, `?cookie=true` also sets them
+/// as cookies for the web frontend.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthMode {
+    #[serde(default)]
+    pub cookie: bool,
+}
+
+/// Adds the token cookies and a fresh CSRF token cookie for the double submit check. The
+/// refresh token is taken out of the pair, the page scripts never get to read it.
+pub fn add_auth_cookies(jar: CookieJar, token_pair: &mut TokenPair) -> CookieJar {
+    let access_cookie = Cookie::build((ACCESS_TOKEN_COOKIE, token_pair.access_token.clone()))
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/");
+
+    let csrf_cookie = Cookie::build((CSRF_COOKIE, generate_secret()))
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .path("/");
+
+    let jar = jar.add(access_cookie).add(csrf_cookie);
+
+    match token_pair.refresh_token.take() {
+        Some(refresh_token) => jar.add(
+            Cookie::build((REFRESH_TOKEN_COOKIE, refresh_token))
+                .http_only(true)
+                .secure(true)
+                .same_site(SameSite::Strict)
+                .path(REFRESH_TOKEN_COOKIE_PATH),
+        ),
+        None => jar,
+    }
+}
+
+pub fn remove_auth_cookies(jar: CookieJar) -> CookieJar {
+    jar.remove(Cookie::build(ACCESS_TOKEN_COOKIE).path("/"))
+        .remove(Cookie::build(CSRF_COOKIE).path("/"))
+        .remove(Cookie::build(REFRESH_TOKEN_COOKIE).path(REFRESH_TOKEN_COOKIE_PATH))
+}
+
+/// The double submit check, the header has to repeat the value of the CSRF cookie.
+pub fn has_valid_csrf_token(jar: &CookieJar, headers: &HeaderMap) -> bool {
+    let csrf_cookie = jar.get(CSRF_COOKIE).map(|cookie| cookie.value());
+    let csrf_header = headers.get(CSRF_HEADER).and_then(|h| h.to_str().ok());
 
-use crate::user_service::token_provider::TokenProvider;
+    match (csrf_cookie, csrf_header) {
+        (Some(cookie), Some(header)) => constant_time_eq(cookie.as_bytes(), header.as_bytes()),
+        _ => false,
+    }
+}
 
+/// Identity of the caller, taken from the verified JWT claims.
 #[derive(Debug, Clone)]
-pub struct ClientInfo {
-    pub client_id: String,
+pub struct AuthContext {
+    pub user_id: String,
+    pub role: UserRol,
+    pub session_id: String,
+    /// The session passed the MFA policy of the role
+    pub mfa: bool,
+}
+
+/// State for [`auth_middleware`].
+#[derive(Clone)]
+pub struct AuthState {
+    token_provider: TokenProvider,
+    session_repository: Arc<dyn SessionRepository>,
+}
+
+impl AuthState {
+    pub fn new(token_key: String, session_repository: Arc<dyn SessionRepository>) -> Self {
+        Self {
+            token_provider: TokenProvider::new(token_key),
+            session_repository,
+        }
+    }
 }
 
 pub async fn auth_middleware(
-    State(jwt_secret): State<String>,
+    State(auth_state): State<AuthState>,
     mut request: Request,
     next: Next,
-) -> Result<impl IntoResponse, StatusCode> {
-    let token_provider = TokenProvider::new(jwt_secret);
-
+) -> Result<Response, ApiError> {
     let auth_header = request
         .headers()
         .get("Authorization")
@@ -28,24 +121,138 @@ pub async fn auth_middleware(
 
     let jwt_token_string = match auth_header {
         Some(header_value) if header_value.starts_with("Bearer ") => {
-            &header_value[7..] // Remove "Bearer " prefix
+            header_value[7..].to_string() // Remove "Bearer " prefix
+        }
+        Some(_) => {
+            return Err(ApiError::unauthorized(
+                "invalid_token",
+                "The Authorization header is not a bearer token",
+            ))
         }
-        _ => return Err(StatusCode::UNAUTHORIZED),
+        None => cookie_token(&request)?,
     };
 
-    info!("The token is: |{jwt_token_string}|");
-
-    let claims = token_provider
-        .verify_token(jwt_token_string)
+    let claims = auth_state
+        .token_provider
+        .verify_token(&jwt_token_string)
         .map_err(|err| {
             error!("Error in token verification: {err}");
-            StatusCode::UNAUTHORIZED
-        })?;
+            ApiError::unauthorized("invalid_token", "The access token is not valid")
+        })?
+        .claims;
+
+    let is_revoked = auth_state
+        .session_repository
+        .is_session_revoked(&claims.jti)
+        .await
+        .map_err(|err| ApiError::internal(&err))?;
+
+    if is_revoked {
+        warn!("Token of the revoked session {} was used", claims.jti);
+        return Err(ApiError::unauthorized(
+            "invalid_session",
+            "The session of the access token was revoked",
+        ));
+    }
 
-    let client_id = claims.claims.sub;
-    request.extensions_mut().insert(client_id);
+    request.extensions_mut().insert(AuthContext {
+        user_id: claims.sub,
+        role: claims.nombre_rol,
+        session_id: claims.jti,
+        mfa: claims.mfa,
+    });
 
     let response = next.run(request).await;
 
     Ok(response)
 }
+
+/// Reads the access token from its cookie, the browser sends cookies on its own, so
+/// state changing requests must also prove they can read the CSRF cookie.
+fn cookie_token(request: &Request) -> Result<String, ApiError> {
+    let jar = CookieJar::from_headers(request.headers());
+
+    let access_token = jar
+        .get(ACCESS_TOKEN_COOKIE)
+        .map(|cookie| cookie.value().to_string())
+        .ok_or_else(|| ApiError::unauthorized("missing_token", "No access token was sent"))?;
+
+    let is_safe_method = matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    );
+
+    if !is_safe_method && !has_valid_csrf_token(&jar, request.headers()) {
+        warn!(
+            "Cookie authenticated {} {} without a valid CSRF token",
+            request.method(),
+            request.uri()
+        );
+        return Err(ApiError::forbidden(
+            "invalid_csrf_token",
+            "The CSRF header does not match the CSRF cookie",
+        ));
+    }
+
+    Ok(access_token)
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// State for [`role_middleware`], holds the roles that are allowed to reach a route.
+#[derive(Clone)]
+pub struct RoleGuard {
+    allowed_roles: Arc<[UserRol]>,
+}
+
+impl RoleGuard {
+    pub fn new(allowed_roles: &[UserRol]) -> Self {
+        Self {
+            allowed_roles: allowed_roles.into(),
+        }
+    }
+}
+
+/// Rejects the request with 403 when the caller role is not one of the allowed roles, or
+/// when the session did not pass the MFA policy of the role.
+///
+/// Must run after [`auth_middleware`], it reads the [`AuthContext`] that it puts in the extensions.
+pub async fn role_middleware(
+    State(guard): State<RoleGuard>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let auth_context = request
+        .extensions()
+        .get::<AuthContext>()
+        .ok_or_else(|| ApiError::unauthorized("missing_token", "No access token was sent"))?;
+
+    if !guard.allowed_roles.contains(&auth_context.role) {
+        warn!(
+            "User {} with rol {} tried to access {}",
+            auth_context.user_id,
+            auth_context.role,
+            request.uri()
+        );
+        return Err(ApiError::forbidden(
+            "role_not_allowed",
+            "The role of the user is not allowed on this route",
+        ));
+    }
+
+    if !auth_context.mfa {
+        warn!(
+            "User {} must enroll in MFA to access {}",
+            auth_context.user_id,
+            request.uri()
+        );
+        return Err(ApiError::forbidden(
+            "mfa_required",
+            "The role of the user needs a session that passed MFA",
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,84 @@
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+
+/// Header set by the fly.io proxy with the address of the real client.
+const FLY_CLIENT_IP_HEADER: &str = "Fly-Client-IP";
+
+/// Address of the caller, behind the fly.io proxy the peer address is the proxy one,
+/// so the address it forwards is used instead when [`TrustProxyHeader`] is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientIp(pub String);
+
+/// Request extension added when the server runs behind the fly.io proxy. Without it
+/// [`FLY_CLIENT_IP_HEADER`] is ignored, any client can send the header.
+#[derive(Debug, Clone, Copy)]
+pub struct TrustProxyHeader;
+
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let forwarded_ip = parts
+            .extensions
+            .get::<TrustProxyHeader>()
+            .and_then(|_| parts.headers.get(FLY_CLIENT_IP_HEADER))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok());
+
+        if let Some(ip) = forwarded_ip {
+            return Ok(ClientIp(ip.to_string()));
+        }
+
+        let peer_ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        Ok(ClientIp(peer_ip))
+    }
+}
+
+impl Display for ClientIp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Request;
+
+    use super::*;
+
+    async fn client_ip(trust_proxy_header: bool) -> ClientIp {
+        let mut request = Request::builder()
+            .header(FLY_CLIENT_IP_HEADER, "203.0.113.7")
+            .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))))
+            .body(())
+            .unwrap();
+        if trust_proxy_header {
+            request.extensions_mut().insert(TrustProxyHeader);
+        }
+        let (mut parts, _) = request.into_parts();
+
+        ClientIp::from_request_parts(&mut parts, &()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_proxy_header_only_read_when_trusted() {
+        assert_eq!(client_ip(true).await, ClientIp("203.0.113.7".to_string()));
+        assert_eq!(client_ip(false).await, ClientIp("10.0.0.1".to_string()));
+    }
+}
@@ -0,0 +1,860 @@
+use std::{
+    future::Future,
+    ops::Deref,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use libsql::{params::IntoParams, Builder, Connection, Database, Rows};
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+
+/// Where the data lives, taken from `db_url`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DatabaseMode {
+    /// Turso / libsql server, every query goes over the network
+    Remote { url: String },
+    /// Local file opened with `Builder::new_local`, for development and offline demos
+    Local { path: String },
+    /// Fresh database that lives while the process does, for CI and tests
+    Memory,
+    /// Local file kept in sync with the remote database, reads are served locally
+    EmbeddedReplica { url: String, path: String },
+}
+
+impl DatabaseMode {
+    /// `:memory:` and `file:<path>` are local, anything else is a remote url that becomes
+    /// an embedded replica when a replica path is configured.
+    pub fn from_config(db_url: &str, replica_path: Option<&str>) -> Self {
+        if db_url == ":memory:" {
+            return DatabaseMode::Memory;
+        }
+
+        if let Some(path) = db_url.strip_prefix("file:") {
+            return DatabaseMode::Local {
+                path: path.to_string(),
+            };
+        }
+
+        match replica_path {
+            Some(path) => DatabaseMode::EmbeddedReplica {
+                url: db_url.to_string(),
+                path: path.to_string(),
+            },
+            None => DatabaseMode::Remote {
+                url: db_url.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DatabaseError {
+    #[error("Database error: {0}")]
+    ConnectionError(#[from] libsql::Error),
+    #[error("The database is unreachable after {attempts} attempts: {source}")]
+    Unreachable {
+        attempts: u32,
+        source: libsql::Error,
+    },
+}
+
+impl DatabaseError {
+    fn unreachable(config: &PoolConfig, source: libsql::Error) -> Self {
+        DatabaseError::Unreachable {
+            attempts: config.connect_retries.max(1),
+            source,
+        }
+    }
+}
+
+/// Limits of the connection pool, taken from the `db_pool_*` and `db_connect_*` settings.
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Connections handed out at the same time, callers wait for one to be returned
+    pub size: usize,
+    /// How long a connection attempt or a wait for a free connection may take
+    pub connect_timeout: Duration,
+    /// Attempts made on a transient error before giving up, for the connections and the reads
+    pub connect_retries: u32,
+    /// Wait before the first retry, doubled on every following one
+    pub retry_base_delay: Duration,
+    /// How long a statement on a local file waits for the lock of another writer, like the
+    /// `BEGIN IMMEDIATE` of a unit of work, before it fails with `SQLITE_BUSY`
+    pub busy_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 8,
+            connect_timeout: Duration::from_secs(5),
+            connect_retries: 3,
+            retry_base_delay: Duration::from_millis(200),
+            busy_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// The one database every repository is created from, connections are reused
+/// through a small pool instead of being opened on every call.
+pub struct DatabaseHandle {
+    database: Database,
+    config: PoolConfig,
+    idle: Mutex<Vec<Connection>>,
+    permits: Arc<Semaphore>,
+    /// The file is opened by this process, the remote server handles its own locking
+    local: bool,
+    /// An in memory database is dropped with its last connection, this one keeps it alive
+    _keep_alive: Option<Connection>,
+}
+
+impl DatabaseHandle {
+    /// Opens the database and checks it answers, so startup fails right away with the
+    /// reason instead of on the first request.
+    pub async fn open(
+        mode: DatabaseMode,
+        token: &str,
+        sync_interval: Option<Duration>,
+        config: PoolConfig,
+    ) -> Result<Arc<Self>, DatabaseError> {
+        info!("Opening the database in mode {mode:?}");
+
+        let database = match &mode {
+            DatabaseMode::Remote { url } => {
+                Builder::new_remote(url.clone(), token.to_string())
+                    .build()
+                    .await?
+            }
+            DatabaseMode::Local { path } => Builder::new_local(path).build().await?,
+            DatabaseMode::Memory => {
+                // Plain `:memory:` would give every connection its own empty database
+                let name = format!(
+                    "file:sabana_club_{}?mode=memory&cache=shared",
+                    uuid::Uuid::new_v4().simple()
+                );
+                Builder::new_local(name).build().await?
+            }
+            DatabaseMode::EmbeddedReplica { url, path } => {
+                let mut builder = Builder::new_remote_replica(path, url.clone(), token.to_string())
+                    .read_your_writes(true);
+
+                if let Some(sync_interval) = sync_interval {
+                    builder = builder.sync_interval(sync_interval);
+                }
+
+                let database = builder.build().await?;
+                retry(&config, || async {
+                    timeout(config.connect_timeout, database.sync()).await?
+                })
+                .await
+                .map_err(|source| DatabaseError::unreachable(&config, source))?;
+                database
+            }
+        };
+
+        let keep_alive = match mode {
+            DatabaseMode::Memory => Some(database.connect()?),
+            _ => None,
+        };
+
+        let handle = Arc::new(Self {
+            database,
+            local: !matches!(mode, DatabaseMode::Remote { .. }),
+            permits: Arc::new(Semaphore::new(config.size)),
+            idle: Mutex::new(Vec::with_capacity(config.size)),
+            config,
+            _keep_alive: keep_alive,
+        });
+
+        let conn = handle.connect().await?;
+        retry(&handle.config, || async {
+            timeout(handle.config.connect_timeout, conn.query("SELECT 1", ()))
+                .await?
+                .map(|_| ())
+        })
+        .await
+        .map_err(|source| DatabaseError::unreachable(&handle.config, source))?;
+
+        Ok(handle)
+    }
+
+    /// Takes a connection out of the pool, waiting up to the connect timeout when all
+    /// of them are in use.
+    pub async fn connect(self: &Arc<Self>) -> libsql::Result<PooledConnection> {
+        // Inside a unit of work every repository has to use its connection
+        if let Ok(conn) = TRANSACTION.try_with(|transaction| transaction.conn.clone()) {
+            return Ok(PooledConnection {
+                conn: Some(conn),
+                handle: self.clone(),
+                permit: None,
+            });
+        }
+
+        let permit = tokio::time::timeout(
+            self.config.connect_timeout,
+            self.permits.clone().acquire_owned(),
+        )
+        .await
+        .map_err(|_| {
+            libsql::Error::ConnectionFailed(format!(
+                "no free database connection after {:?}",
+                self.config.connect_timeout
+            ))
+        })?
+        .map_err(|err| libsql::Error::ConnectionFailed(err.to_string()))?;
+
+        let idle = self
+            .idle
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .pop();
+
+        let conn = match idle {
+            Some(conn) => conn,
+            None => {
+                let conn = retry(&self.config, || async { self.database.connect() }).await?;
+                // SQLite only checks the foreign keys of connections that ask for it
+                conn.execute("PRAGMA foreign_keys = ON", ()).await?;
+                if self.local {
+                    // Without it a second writer fails at once instead of waiting its turn
+                    conn.query(
+                        &format!(
+                            "PRAGMA busy_timeout = {}",
+                            self.config.busy_timeout.as_millis()
+                        ),
+                        (),
+                    )
+                    .await?;
+                }
+                conn
+            }
+        };
+
+        Ok(PooledConnection {
+            conn: Some(conn),
+            handle: self.clone(),
+            permit: Some(permit),
+        })
+    }
+}
+
+/// A connection borrowed from the pool, it goes back when dropped.
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    handle: Arc<DatabaseHandle>,
+    /// Unset when the connection belongs to a unit of work, which returns it itself
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledConnection {
+    /// Runs a read only query, retried with backoff on a transient error. Writes go through
+    /// `execute` or `query` and are run once, another attempt could apply them twice.
+    pub async fn read(&self, sql: &str, params: impl IntoParams) -> libsql::Result<Rows> {
+        let params = params.into_params()?;
+
+        // A failed statement inside a unit of work leaves the transaction to its rollback
+        if self.permit.is_none() {
+            return self.query(sql, params).await;
+        }
+
+        retry(&self.handle.config, || async {
+            timeout(
+                self.handle.config.connect_timeout,
+                self.query(sql, params.clone()),
+            )
+            .await?
+        })
+        .await
+    }
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("connection taken before drop")
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        // A connection left inside a transaction would leak it to the next caller
+        if self.permit.is_none() {
+            return;
+        }
+
+        if let Some(conn) = self.conn.take().filter(|conn| conn.is_autocommit()) {
+            self.handle
+                .idle
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(conn);
+        }
+    }
+}
+
+type AfterCommit = Box<dyn FnOnce() + Send>;
+
+/// The connection of the running unit of work and what waits for its commit.
+struct Transaction {
+    conn: Connection,
+    after_commit: Arc<Mutex<Vec<AfterCommit>>>,
+}
+
+tokio::task_local! {
+    static TRANSACTION: Transaction;
+}
+
+/// Runs the action once the running unit of work commits, it is dropped on a rollback.
+/// Outside of a unit of work there is nothing to wait for and it runs right away. For
+/// side effects that others must not see before the data, like clearing a cache.
+pub fn after_commit(action: impl FnOnce() + Send + 'static) {
+    let mut action = Some(Box::new(action) as AfterCommit);
+
+    let _ = TRANSACTION.try_with(|transaction| {
+        transaction
+            .after_commit
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .extend(action.take());
+    });
+
+    if let Some(action) = action {
+        action();
+    }
+}
+
+/// Runs several repository calls as one transaction. Repositories don't need to know
+/// about it, while the work runs `DatabaseHandle::connect` hands out the transaction
+/// connection, so they must not open transactions of their own inside it.
+#[derive(Clone)]
+pub struct UnitOfWork {
+    db: Option<Arc<DatabaseHandle>>,
+}
+
+impl UnitOfWork {
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db: Some(db) }
+    }
+
+    /// Runs the work as is, for services built on repositories without a database
+    #[cfg(any(test, feature = "in-memory"))]
+    pub fn disabled() -> Self {
+        Self { db: None }
+    }
+
+    /// Commits when the work succeeds and rolls back when it fails. Work started inside
+    /// another unit of work joins it and is committed with it. The [`after_commit`]
+    /// actions queued by the work run after the commit.
+    pub async fn run<T, E, Fut>(&self, work: Fut) -> Result<T, E>
+    where
+        Fut: Future<Output = Result<T, E>>,
+        E: From<libsql::Error>,
+    {
+        let Some(db) = &self.db else {
+            return work.await;
+        };
+
+        if TRANSACTION.try_with(|_| ()).is_ok() {
+            return work.await;
+        }
+
+        let conn = db.connect().await?;
+        conn.execute("BEGIN IMMEDIATE", ()).await?;
+
+        let after_commit = Arc::new(Mutex::new(Vec::new()));
+        let transaction = Transaction {
+            conn: Connection::clone(&conn),
+            after_commit: after_commit.clone(),
+        };
+
+        let result = TRANSACTION.scope(transaction, work).await;
+
+        match result {
+            Ok(value) => {
+                conn.execute("COMMIT", ()).await?;
+
+                let actions = std::mem::take(
+                    &mut *after_commit.lock().unwrap_or_else(|err| err.into_inner()),
+                );
+                actions.into_iter().for_each(|action| action());
+
+                Ok(value)
+            }
+            Err(err) => {
+                if let Err(rollback_err) = conn.execute("ROLLBACK", ()).await {
+                    warn!("Error rolling back the unit of work: {rollback_err}");
+                }
+                Err(err)
+            }
+        }
+    }
+}
+
+/// A constraint of the schema broken by a statement. The kind comes from the extended
+/// error code, only the column is read from the message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstraintViolation {
+    /// The column that collided, the last one of a composite key
+    Unique {
+        column: String,
+    },
+    ForeignKey,
+}
+
+impl ConstraintViolation {
+    pub fn from_error(err: &libsql::Error) -> Option<Self> {
+        let message = err.to_string();
+
+        let code = match err {
+            libsql::Error::SqliteFailure(code, _) => *code,
+            libsql::Error::RemoteSqliteFailure(_, extended_code, _) => *extended_code,
+            // The remote error type is private to libsql, its text carries the code name
+            libsql::Error::Hrana(_) => remote_error_code(&message)?,
+            _ => return None,
+        };
+
+        match code {
+            libsql::ffi::SQLITE_CONSTRAINT_UNIQUE | libsql::ffi::SQLITE_CONSTRAINT_PRIMARYKEY => {
+                Self::unique(&message)
+            }
+            libsql::ffi::SQLITE_CONSTRAINT_FOREIGNKEY => Some(ConstraintViolation::ForeignKey),
+            _ => None,
+        }
+    }
+
+    fn unique(message: &str) -> Option<Self> {
+        let (_, columns) = message.split_once("UNIQUE constraint failed: ")?;
+        let columns: String = columns
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | ',' | ' '))
+            .collect();
+        let column = columns.split(',').next_back()?.trim();
+        let column = column.rsplit('.').next().unwrap_or(column);
+
+        Some(ConstraintViolation::Unique {
+            column: column.to_string(),
+        })
+    }
+}
+
+/// The extended code of an error sent by the remote database, which names it like
+/// `code: "SQLITE_CONSTRAINT_UNIQUE"`.
+fn remote_error_code(message: &str) -> Option<i32> {
+    match remote_error_name(message)? {
+        "SQLITE_CONSTRAINT_UNIQUE" => Some(libsql::ffi::SQLITE_CONSTRAINT_UNIQUE),
+        "SQLITE_CONSTRAINT_PRIMARYKEY" => Some(libsql::ffi::SQLITE_CONSTRAINT_PRIMARYKEY),
+        "SQLITE_CONSTRAINT_FOREIGNKEY" => Some(libsql::ffi::SQLITE_CONSTRAINT_FOREIGNKEY),
+        _ => None,
+    }
+}
+
+fn remote_error_name(message: &str) -> Option<&str> {
+    let (_, code) = message.split_once("code: \"")?;
+    let (code, _) = code.split_once('"')?;
+
+    Some(code)
+}
+
+/// Implements `From<libsql::Error>` for a repository error with the `Duplicate { field }`
+/// and `ForeignKeyViolation` variants, any other error is built with `$other`.
+macro_rules! from_libsql_error {
+    ($error:ty, $other:expr) => {
+        impl From<libsql::Error> for $error {
+            fn from(err: libsql::Error) -> Self {
+                use $crate::database::ConstraintViolation;
+
+                match ConstraintViolation::from_error(&err) {
+                    Some(ConstraintViolation::Unique { column }) => {
+                        Self::Duplicate { field: column }
+                    }
+                    Some(ConstraintViolation::ForeignKey) => Self::ForeignKeyViolation,
+                    None => $other(err),
+                }
+            }
+        }
+    };
+}
+pub(crate) use from_libsql_error;
+
+/// Errors worth another attempt: the network or a database busy with another writer.
+fn is_transient(err: &libsql::Error) -> bool {
+    match err {
+        libsql::Error::ConnectionFailed(_)
+        | libsql::Error::Replication(_)
+        | libsql::Error::WriteDelegation(_) => true,
+        libsql::Error::Hrana(remote) => is_transient_remote(&remote.to_string()),
+        libsql::Error::SqliteFailure(code, _) => {
+            *code == libsql::ffi::SQLITE_BUSY || *code == libsql::ffi::SQLITE_LOCKED
+        }
+        _ => false,
+    }
+}
+
+/// The remote errors are only told apart by their text. A closed stream or a failed HTTP
+/// call never reached the database, the errors of a statement carry the code it returned.
+fn is_transient_remote(message: &str) -> bool {
+    match remote_error_name(message) {
+        Some(code) => code.starts_with("SQLITE_BUSY") || code.starts_with("SQLITE_LOCKED"),
+        None => message.starts_with("stream closed") || message.starts_with("http error"),
+    }
+}
+
+async fn retry<T, F, Fut>(config: &PoolConfig, mut operation: F) -> libsql::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = libsql::Result<T>>,
+{
+    let attempts = config.connect_retries.max(1);
+    let mut delay = config.retry_base_delay;
+    let mut attempt = 1;
+
+    loop {
+        match operation().await {
+            Err(err) if is_transient(&err) && attempt < attempts => {
+                warn!(
+                    "Database attempt {attempt} of {attempts} failed, retrying in {delay:?}: {err}"
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs a database future with a deadline, running out of time counts as a failed connection.
+async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> libsql::Result<T> {
+    tokio::time::timeout(duration, future)
+        .await
+        .map_err(|_| libsql::Error::ConnectionFailed(format!("timed out after {duration:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use libsql::params;
+
+    use super::*;
+
+    #[test]
+    fn test_mode_from_config() {
+        assert_eq!(
+            DatabaseMode::from_config(":memory:", None),
+            DatabaseMode::Memory
+        );
+        assert_eq!(
+            DatabaseMode::from_config("file:club.db", Some("replica.db")),
+            DatabaseMode::Local {
+                path: "club.db".to_string()
+            }
+        );
+        assert_eq!(
+            DatabaseMode::from_config("libsql://club.turso.io", Some("replica.db")),
+            DatabaseMode::EmbeddedReplica {
+                url: "libsql://club.turso.io".to_string(),
+                path: "replica.db".to_string()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_database_is_shared_between_connections() {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+
+        let writer = db.connect().await.unwrap();
+        writer
+            .execute("CREATE TABLE test (id INTEGER)", params![])
+            .await
+            .unwrap();
+
+        // Held while the second one is taken, so the pool cannot hand out the same one
+        let reader = db.connect().await.unwrap();
+        let result = reader.query("SELECT COUNT(*) FROM test", params![]).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_pool_waits_for_a_free_connection() {
+        let config = PoolConfig {
+            size: 1,
+            connect_timeout: Duration::from_millis(50),
+            ..PoolConfig::default()
+        };
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, config)
+            .await
+            .unwrap();
+
+        let conn = db.connect().await.unwrap();
+        assert!(db.connect().await.is_err());
+
+        drop(conn);
+        assert!(db.connect().await.is_ok());
+    }
+
+    async fn count_rows(db: &Arc<DatabaseHandle>) -> i64 {
+        let conn = db.connect().await.unwrap();
+        let mut rows = conn.query("SELECT COUNT(*) FROM test", ()).await.unwrap();
+        rows.next().await.unwrap().unwrap().get(0).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_unit_of_work_commits_or_rolls_back_as_a_whole() {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+        db.connect()
+            .await
+            .unwrap()
+            .execute("CREATE TABLE test (id INTEGER)", ())
+            .await
+            .unwrap();
+
+        let unit_of_work = UnitOfWork::new(db.clone());
+        let insert = || async {
+            db.connect()
+                .await?
+                .execute("INSERT INTO test (id) VALUES (1)", ())
+                .await
+        };
+
+        let failed: libsql::Result<()> = unit_of_work
+            .run(async {
+                insert().await?;
+                unit_of_work.run(insert()).await?;
+                Err(libsql::Error::QueryReturnedNoRows)
+            })
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(count_rows(&db).await, 0);
+
+        unit_of_work
+            .run(async {
+                insert().await?;
+                unit_of_work.run(insert()).await
+            })
+            .await
+            .unwrap();
+        assert_eq!(count_rows(&db).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_after_commit_waits_for_the_commit() {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+        let unit_of_work = UnitOfWork::new(db);
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let record = |name: &'static str| {
+            let ran = ran.clone();
+            move || ran.lock().unwrap().push(name)
+        };
+
+        let failed: libsql::Result<()> = unit_of_work
+            .run(async {
+                after_commit(record("rolled back"));
+                Err(libsql::Error::QueryReturnedNoRows)
+            })
+            .await;
+        assert!(failed.is_err());
+
+        unit_of_work
+            .run(async {
+                after_commit(record("committed"));
+                assert!(ran.lock().unwrap().is_empty());
+                libsql::Result::Ok(())
+            })
+            .await
+            .unwrap();
+
+        after_commit(record("outside"));
+        assert_eq!(*ran.lock().unwrap(), vec!["committed", "outside"]);
+    }
+
+    /// The busy handler of SQLite blocks its thread, the other unit of work needs another one.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_units_of_work_on_a_local_file_wait_for_each_other() {
+        let path = std::env::temp_dir().join(format!("sabana_club_{}.db", uuid::Uuid::new_v4()));
+        let db = DatabaseHandle::open(
+            DatabaseMode::Local {
+                path: path.to_string_lossy().into_owned(),
+            },
+            "",
+            None,
+            PoolConfig::default(),
+        )
+        .await
+        .unwrap();
+        db.connect()
+            .await
+            .unwrap()
+            .execute("CREATE TABLE counter (value INTEGER)", ())
+            .await
+            .unwrap();
+
+        let increment = |db: Arc<DatabaseHandle>| async move {
+            UnitOfWork::new(db.clone())
+                .run(async {
+                    let conn = db.connect().await?;
+                    conn.execute("INSERT INTO counter (value) VALUES (1)", ())
+                        .await?;
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    libsql::Result::Ok(())
+                })
+                .await
+        };
+        let (first, second) = tokio::join!(
+            tokio::spawn(increment(db.clone())),
+            tokio::spawn(increment(db.clone()))
+        );
+        first.unwrap().unwrap();
+        second.unwrap().unwrap();
+
+        let mut rows = db
+            .connect()
+            .await
+            .unwrap()
+            .query("SELECT COUNT(*) FROM counter", ())
+            .await
+            .unwrap();
+        let count: i64 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+        assert_eq!(count, 2);
+
+        drop(rows);
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_constraint_violations_are_classified() {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+        let conn = db.connect().await.unwrap();
+        conn.execute_batch(
+            "CREATE TABLE parent (id INTEGER PRIMARY KEY, email TEXT UNIQUE);
+             CREATE TABLE child (
+                 parent_id INTEGER NOT NULL REFERENCES parent (id),
+                 kind TEXT NOT NULL,
+                 number TEXT NOT NULL,
+                 UNIQUE (kind, number)
+             );
+             INSERT INTO parent (id, email) VALUES (1, 'a@b.co');
+             INSERT INTO child (parent_id, kind, number) VALUES (1, 'CC', '10');",
+        )
+        .await
+        .unwrap();
+
+        let violation = |sql: &'static str| {
+            let conn = &conn;
+            async move {
+                let err = conn.execute(sql, ()).await.unwrap_err();
+                ConstraintViolation::from_error(&err)
+            }
+        };
+
+        assert_eq!(
+            violation("INSERT INTO parent (id, email) VALUES (2, 'a@b.co')").await,
+            Some(ConstraintViolation::Unique {
+                column: "email".to_string()
+            })
+        );
+        assert_eq!(
+            violation("INSERT INTO child (parent_id, kind, number) VALUES (1, 'CC', '10')").await,
+            Some(ConstraintViolation::Unique {
+                column: "number".to_string()
+            })
+        );
+        assert_eq!(
+            violation("INSERT INTO child (parent_id, kind, number) VALUES (7, 'TI', '10')").await,
+            Some(ConstraintViolation::ForeignKey)
+        );
+        assert_eq!(violation("SELECT * FROM missing").await, None);
+
+        let remote = |code: &str| {
+            libsql::Error::Hrana(
+                format!(
+                    "stream error: `Error {{ message: \"SQLite error: UNIQUE constraint failed: persona.correo\", code: \"{code}\" }}`"
+                )
+                .into(),
+            )
+        };
+        assert_eq!(
+            ConstraintViolation::from_error(&remote("SQLITE_CONSTRAINT_UNIQUE")),
+            Some(ConstraintViolation::Unique {
+                column: "correo".to_string()
+            })
+        );
+        assert_eq!(
+            ConstraintViolation::from_error(&remote("SQLITE_ERROR")),
+            None
+        );
+        assert_eq!(
+            ConstraintViolation::from_error(&libsql::Error::ConnectionFailed(
+                "UNIQUE constraint failed: persona.correo".to_string()
+            )),
+            None
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_transient_errors_are_retried() {
+        let config = PoolConfig::default();
+
+        let attempts = Mutex::new(0);
+        let result = retry(&config, || async {
+            *attempts.lock().unwrap() += 1;
+            match *attempts.lock().unwrap() {
+                1 => Err(libsql::Error::ConnectionFailed("reset".to_string())),
+                _ => Ok("answer"),
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "answer");
+        assert_eq!(*attempts.lock().unwrap(), 2);
+
+        let attempts = Mutex::new(0);
+        let result: libsql::Result<()> = retry(&config, || async {
+            *attempts.lock().unwrap() += 1;
+            Err(libsql::Error::ConnectionFailed("down".to_string()))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*attempts.lock().unwrap(), config.connect_retries);
+
+        let attempts = Mutex::new(0);
+        let result: libsql::Result<()> = retry(&config, || async {
+            *attempts.lock().unwrap() += 1;
+            Err(libsql::Error::SqliteFailure(
+                libsql::ffi::SQLITE_CONSTRAINT,
+                "constraint".to_string(),
+            ))
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(*attempts.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_only_remote_errors_outside_a_statement_are_transient() {
+        let remote = |message: &str| libsql::Error::Hrana(message.to_string().into());
+
+        assert!(is_transient(&remote("stream closed: `connection reset`")));
+        assert!(is_transient(&remote("http error: `502 Bad Gateway`")));
+        assert!(is_transient(&remote(
+            "stream error: `Error { message: \"database is locked\", code: \"SQLITE_BUSY\" }`"
+        )));
+        assert!(!is_transient(&remote(
+            "stream error: `Error { message: \"UNIQUE constraint failed: persona.correo\", code: \"SQLITE_CONSTRAINT_UNIQUE\" }`"
+        )));
+        assert!(!is_transient(&remote(
+            "cursor error: `error at step 1: no such table: missing`"
+        )));
+        assert!(!is_transient(&remote("api error: `invalid baton`")));
+    }
+}
@@ -0,0 +1,247 @@
+//! Extractors that answer their rejections with an [`ErrorBody`](crate::api_error::ErrorBody)
+//! like every other error of the API, the ones of axum answer in plain text. The handlers take
+//! these instead of `axum::Json`, `axum::extract::Path` and `axum::extract::Query`.
+
+use axum::{
+    extract::{
+        path::ErrorKind,
+        rejection::{
+            BytesRejection, FailedToBufferBody, JsonRejection, PathRejection, QueryRejection,
+        },
+        FromRequest, FromRequestParts, RawPathParams, Request,
+    },
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::api_error::{ApiError, FieldError};
+
+/// Field of the errors that are about the whole body, like a body that is not JSON.
+const BODY_FIELD: &str = "body";
+
+pub struct Json<T>(pub T);
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(request, state).await?;
+
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(PathRejection::FailedToDeserializePathParams(err)) => {
+                // Some errors only know the position or the value of the segment
+                let segments: Vec<(String, String)> =
+                    RawPathParams::from_request_parts(parts, state)
+                        .await
+                        .map(|params| {
+                            params
+                                .iter()
+                                .map(|(name, value)| (name.to_string(), value.to_string()))
+                                .collect()
+                        })
+                        .unwrap_or_default();
+
+                Err(path_error(err.into_kind(), &segments))
+            }
+            Err(rejection) => Err(ApiError::internal(&rejection)),
+        }
+    }
+}
+
+pub struct Query<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Query(value)),
+            Err(QueryRejection::FailedToDeserializeQueryString(err)) => {
+                // serde_urlencoded does not say which parameter failed
+                Err(ApiError::validation(&[FieldError {
+                    field: "query".to_string(),
+                    message: err.body_text(),
+                }]))
+            }
+            Err(rejection) => Err(ApiError::internal(&rejection)),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            JsonRejection::JsonDataError(err) => ApiError::validation(&[body_error(&err)]),
+            JsonRejection::JsonSyntaxError(err) => ApiError::malformed(&[body_error(&err)]),
+            JsonRejection::MissingJsonContentType(err) => ApiError::malformed(&[FieldError {
+                field: BODY_FIELD.to_string(),
+                message: err.body_text(),
+            }]),
+            JsonRejection::BytesRejection(BytesRejection::FailedToBufferBody(
+                FailedToBufferBody::LengthLimitError(_),
+            )) => ApiError::payload_too_large(),
+            rejection => ApiError::malformed(&[FieldError {
+                field: BODY_FIELD.to_string(),
+                message: rejection.body_text(),
+            }]),
+        }
+    }
+}
+
+/// The field serde stopped at, found in the error axum wraps.
+fn body_error(err: &(dyn std::error::Error + 'static)) -> FieldError {
+    let mut source = err.source();
+    while let Some(err) = source {
+        if let Some(err) = err.downcast_ref::<serde_path_to_error::Error<serde_json::Error>>() {
+            return serde_field_error(&err.path().to_string(), &err.inner().to_string());
+        }
+        source = err.source();
+    }
+
+    FieldError {
+        field: BODY_FIELD.to_string(),
+        message: err.to_string(),
+    }
+}
+
+/// serde reports a missing field at the object that lacks it, with the name in the message.
+/// The message is reworded like the ones of the validation of the payloads.
+fn serde_field_error(path: &str, message: &str) -> FieldError {
+    let message = message
+        .rsplit_once(" at line ")
+        .map_or(message, |(message, _)| message);
+    let missing = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.strip_suffix('`'));
+
+    let field = match (path, missing) {
+        (".", Some(missing)) => missing.to_string(),
+        (".", None) => BODY_FIELD.to_string(),
+        (path, Some(missing)) => format!("{path}.{missing}"),
+        (path, None) => path.to_string(),
+    };
+
+    let message = match (missing, message.split_once(", expected ")) {
+        (Some(_), _) => "is required".to_string(),
+        // Like `invalid type: integer `5`, expected a string` or an unknown variant
+        (None, Some((_, expected))) => format!("must be {expected}"),
+        (None, None) => message.to_string(),
+    };
+
+    FieldError { field, message }
+}
+
+/// `segments` are the names and values of the parameters of the path, in order.
+fn path_error(kind: ErrorKind, segments: &[(String, String)]) -> ApiError {
+    let name_at = |index: usize| {
+        segments
+            .get(index)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default()
+    };
+
+    let (field, message) = match kind {
+        ErrorKind::ParseErrorAtKey {
+            key, expected_type, ..
+        } => (key, format!("is not a valid {expected_type}")),
+        ErrorKind::ParseErrorAtIndex {
+            index,
+            expected_type,
+            ..
+        } => (name_at(index), format!("is not a valid {expected_type}")),
+        ErrorKind::ParseError { expected_type, .. } => {
+            (name_at(0), format!("is not a valid {expected_type}"))
+        }
+        ErrorKind::DeserializeError { key, message, .. } => (key, message),
+        ErrorKind::InvalidUtf8InPathParam { key } => (key, "is not valid UTF-8".to_string()),
+        // Like the unknown variants of an enum, serde quotes the value
+        ErrorKind::Message(message) => {
+            let field = segments
+                .iter()
+                .find(|(_, value)| message.contains(&format!("`{value}`")))
+                .map_or_else(|| "path".to_string(), |(name, _)| name.clone());
+
+            (field, message)
+        }
+        kind => return ApiError::internal(&std::io::Error::other(kind.to_string())),
+    };
+
+    ApiError::malformed(&[FieldError { field, message }])
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{header, Method, Request, StatusCode},
+    };
+    use serde_json::json;
+
+    use crate::{test_harness::TestApp, user_service::domain::UserRol};
+
+    #[tokio::test]
+    async fn test_rejections_are_error_bodies() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+
+        let response = app
+            .post("/api/v1/users", None, json!({ "nombre": 5 }))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["code"], "validation_failed");
+        assert_eq!(response.body["errors"][0]["field"], "nombre");
+        assert!(response.body["request_id"].is_string());
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/users")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{\"nombre\":"))
+            .unwrap();
+        let response = app.send(request).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.body["code"], "validation_failed");
+        assert_eq!(response.body["errors"][0]["field"], "nombre");
+
+        let response = app
+            .put(
+                &format!("/api/v1/users/{}/role/Rey", admin.id_persona),
+                Some(&admin.token),
+                json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.body["code"], "validation_failed");
+        assert_eq!(response.body["errors"][0]["field"], "user_role");
+    }
+}
@@ -1,5 +1,9 @@
 use axum::Router;
+use utoipa::openapi::OpenApi;
 
 pub trait HttpService {
     fn get_router(&self) -> Router;
+
+    /// OpenAPI document of the routes in [`HttpService::get_router`].
+    fn openapi(&self) -> OpenApi;
 }
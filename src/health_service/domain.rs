@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct HealthStatus {
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessReport {
+    pub status: String,
+    /// Version of the last migration applied to the database
+    pub schema_version: i64,
+    /// Version of the last migration this binary knows
+    pub expected_schema_version: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    pub version: String,
+    /// `unknown` when the build did not set `GIT_SHA`
+    pub git_sha: String,
+    /// `unknown` when the build did not set `BUILD_TIME`
+    pub build_time: String,
+}
+
+impl VersionInfo {
+    /// The crate version and what the build put in `GIT_SHA` and `BUILD_TIME`.
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: option_env!("GIT_SHA").unwrap_or("unknown").to_string(),
+            build_time: option_env!("BUILD_TIME").unwrap_or("unknown").to_string(),
+        }
+    }
+}
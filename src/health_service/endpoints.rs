@@ -0,0 +1,206 @@
+use std::sync::Arc;
+
+use crate::extract::Json;
+use axum::{extract::State, http::StatusCode};
+use tracing::error;
+use utoipa::OpenApi;
+
+use crate::{
+    api_error::{ApiError, ErrorBody},
+    global_traits::HttpService,
+    migrations,
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
+};
+
+use super::{
+    domain::{HealthStatus, ReadinessReport, VersionInfo},
+    repository::HealthRepository,
+};
+
+/// Probes for the platform that runs the server, none of them needs a token.
+pub struct HealthHttpServer {
+    health_repository: Arc<dyn HealthRepository>,
+    access_control: AccessControl,
+}
+
+impl HealthHttpServer {
+    pub async fn new(
+        health_repository: Arc<dyn HealthRepository>,
+        access_control: AccessControl,
+    ) -> Self {
+        Self {
+            health_repository,
+            access_control,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ServiceState {
+    health_repository: Arc<dyn HealthRepository>,
+}
+
+impl HttpService for HealthHttpServer {
+    fn get_router(&self) -> axum::Router {
+        let state = ServiceState {
+            health_repository: self.health_repository.clone(),
+        };
+
+        self.access_control
+            .build_router("health", routes())
+            .with_state(state.into())
+    }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(HealthApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<Arc<ServiceState>>> {
+    vec![
+        RouteAccess::get("/healthz", Access::Public, liveness),
+        RouteAccess::get("/readyz", Access::Public, readiness),
+        RouteAccess::get("/version", Access::Public, version),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(liveness, readiness, version),
+    tags((name = "health", description = "Liveness, readiness and build of the server"))
+)]
+struct HealthApi;
+
+/// Answers while the process is able to serve requests, it does not look at the database.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses((status = 200, description = "The server is alive", body = HealthStatus))
+)]
+async fn liveness() -> Json<HealthStatus> {
+    Json(HealthStatus {
+        status: "ok".to_string(),
+    })
+}
+
+/// Ready when the database answers and its schema is the one this binary expects.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server can take traffic", body = ReadinessReport),
+        (status = 503, description = "`database_unavailable` or `schema_mismatch`", body = ErrorBody),
+    )
+)]
+async fn readiness(
+    State(state): State<Arc<ServiceState>>,
+) -> Result<Json<ReadinessReport>, ApiError> {
+    let expected_schema_version = migrations::latest_version();
+
+    let schema_version = state
+        .health_repository
+        .schema_version()
+        .await
+        .map_err(|err| {
+            error!("Readiness check failed: {err}");
+            ApiError::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "database_unavailable",
+                "The database cannot be reached",
+            )
+        })?;
+
+    if schema_version != expected_schema_version {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "schema_mismatch",
+            format!(
+                "The database schema is at version {schema_version}, this binary expects {expected_schema_version}"
+            ),
+        ));
+    }
+
+    Ok(Json(ReadinessReport {
+        status: "ready".to_string(),
+        schema_version,
+        expected_schema_version,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "health",
+    responses((status = 200, description = "Version and build of the running binary", body = VersionInfo))
+)]
+async fn version() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        database::{DatabaseHandle, DatabaseMode, PoolConfig},
+        repositories::Repositories,
+        test_harness::TestApp,
+    };
+
+    #[tokio::test]
+    async fn test_probes_need_no_token() {
+        let app = TestApp::new().await;
+
+        let response = app.get("/healthz", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, json!({ "status": "ok" }));
+
+        let response = app.get("/readyz", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["status"], "ready");
+        assert_eq!(
+            response.body["schema_version"],
+            json!(migrations::latest_version())
+        );
+
+        let response = app.get("/version", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["version"], env!("CARGO_PKG_VERSION"));
+        assert!(response.body["git_sha"].is_string());
+        assert!(response.body["build_time"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_not_ready_behind_the_binary_schema() {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+        let conn = db.connect().await.unwrap();
+        migrations::migrate(&conn).await.unwrap();
+        conn.execute(
+            "DELETE FROM schema_migrations WHERE version = ?1",
+            libsql::params![migrations::latest_version()],
+        )
+        .await
+        .unwrap();
+        drop(conn);
+        let app = TestApp::with_repositories(Repositories::libsql(db)).await;
+
+        let response = app.get("/readyz", None).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.body["code"], "schema_mismatch");
+
+        // Liveness does not depend on the database
+        assert_eq!(app.get("/healthz", None).await.status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_repositories_are_ready() {
+        let app = TestApp::with_repositories(Repositories::in_memory()).await;
+
+        assert_eq!(app.get("/readyz", None).await.status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,13 @@
+pub type Result<T> = std::result::Result<T, HealthRepositoryError>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum HealthRepositoryError {
+    #[error("Database error: {0}")]
+    DatabaseError(String),
+}
+
+impl From<libsql::Error> for HealthRepositoryError {
+    fn from(err: libsql::Error) -> Self {
+        Self::DatabaseError(err.to_string())
+    }
+}
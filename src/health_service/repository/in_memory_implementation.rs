@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use crate::migrations;
+
+use super::{err::Result, HealthRepository};
+
+/// The in memory tables have no schema to migrate, they are always up to date.
+#[derive(Clone)]
+pub struct InMemoryHealthRepository;
+
+#[async_trait]
+impl HealthRepository for InMemoryHealthRepository {
+    async fn schema_version(&self) -> Result<i64> {
+        Ok(migrations::latest_version())
+    }
+}
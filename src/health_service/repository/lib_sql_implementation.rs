@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{database::DatabaseHandle, migrations};
+
+use super::{
+    err::{HealthRepositoryError, Result},
+    HealthRepository,
+};
+
+#[derive(Clone)]
+pub struct LibSqlHealthRepository {
+    db: Arc<DatabaseHandle>,
+}
+
+impl LibSqlHealthRepository {
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HealthRepository for LibSqlHealthRepository {
+    async fn schema_version(&self) -> Result<i64> {
+        let conn = self.db.connect().await?;
+
+        migrations::database_version(&conn)
+            .await
+            .map_err(|e| HealthRepositoryError::DatabaseError(e.to_string()))
+    }
+}
@@ -0,0 +1,15 @@
+use async_trait::async_trait;
+
+pub mod err;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
+pub mod lib_sql_implementation;
+
+use err::Result;
+
+#[async_trait]
+pub trait HealthRepository: Send + Sync {
+    /// Version of the last migration applied to the storage, reading it proves the
+    /// storage can be reached.
+    async fn schema_version(&self) -> Result<i64>;
+}
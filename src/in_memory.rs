@@ -0,0 +1,82 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use chrono::NaiveDate;
+
+use crate::{
+    mfa_service::domain::MfaDb,
+    requests_service::domain::RequestForApprovalDb,
+    session_service::domain::SessionDb,
+    tournament_service::domain::{Tournament, UserTournamentRegistration},
+    trainings_service::model::{Training, TrainingRegistration},
+    user_service::domain::{LoginHistoryEntry, UserInfo, UserRol},
+};
+
+/// `persona` row, the user together with the columns the API never returns.
+#[derive(Debug, Clone)]
+pub struct PersonaRow {
+    pub user: UserInfo,
+    pub contrasena: String,
+    pub failed_login_attempts: u32,
+    pub locked_until: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MatriculaRow {
+    pub id_matricula: i64,
+    pub id_persona: String,
+    pub monto_usd: f64,
+    pub fecha_inscripccion: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordResetRow {
+    pub id_persona: String,
+    pub expires_at: i64,
+    pub used: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecoveryCodeRow {
+    pub code_hash: String,
+    pub id_persona: String,
+    pub used: bool,
+}
+
+/// The tables of the schema, keyed by their primary key. Relations are kept as rows
+/// in insertion order like the tables they stand for.
+#[derive(Debug, Default)]
+pub struct Tables {
+    pub persona: HashMap<String, PersonaRow>,
+    pub password_reset: HashMap<String, PasswordResetRow>,
+    pub login_history: Vec<LoginHistoryEntry>,
+    pub matricula: Vec<MatriculaRow>,
+    pub torneo: HashMap<String, Tournament>,
+    pub persona_torneo: Vec<UserTournamentRegistration>,
+    pub entrenamiento: HashMap<String, Training>,
+    pub entrenamiento_persona: Vec<TrainingRegistration>,
+    pub request_for_approval: HashMap<String, RequestForApprovalDb>,
+    pub session: HashMap<String, SessionDb>,
+    pub mfa: HashMap<String, MfaDb>,
+    pub mfa_recovery_code: Vec<RecoveryCodeRow>,
+    pub mfa_required_role: Vec<UserRol>,
+}
+
+/// Stand-in for the database shared by the in memory repositories, so joins like the
+/// tuition state of a user search see the rows written by the other repositories.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStore {
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
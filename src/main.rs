@@ -1,37 +1,36 @@
-use std::{error::Error, sync::Arc};
-
-use api_server::start_http_server;
-use global_traits::HttpService;
-use requests_service::{
-    endpoints::RequestHttpServer,
-    repository::{lib_sql_implementation::LibSqlRequestRepository, RequestRepository},
-};
+use std::{error::Error, sync::Arc, time::Duration};
+
+use api_server::{build_http_services, start_http_server, ServerConfig};
+use database::{DatabaseHandle, DatabaseMode, PoolConfig};
+use phone::PhoneNormalizer;
+use repositories::Repositories;
 use serde::Deserialize;
-use tournament_service::{
-    endpoints::TournamentHttpServer, repository::lib_sql_implementation::TournamentRepositoryImpl,
-};
 use tracing::{error, info};
-use trainings_service::{
-    endpoints::TrainingHttpServer, repository::lib_sql_implementation::TrainingRepositoryImpl,
-};
-use tuition_service::{
-    endpoints::TuitionHttpServer, repository::lib_sql_implementation::TuitionRepositoryImpl,
-};
-use unique_identifier_service::{
-    endpoints::UniqueIdentifierHttpServer,
-    repository::lib_sql_implementation::LibSqlUniqueIdentifierRepo,
-    usecases::build_unique_identifier,
-};
-use user_service::{
-    endpoints::UserHttpServer, repository::libsql_implementation::LibSqlUserRepository,
-};
+use user_service::notifier::LogNotifier;
 
+mod api_error;
 mod api_server;
 mod models;
+mod phone;
+mod repositories;
 
 pub mod auth_middleware;
+mod client_ip;
+mod database;
+mod extract;
 mod global_traits;
+mod health_service;
+#[cfg(any(test, feature = "in-memory"))]
+mod in_memory;
+mod mfa_service;
+mod migrations;
+mod openapi;
+mod pagination;
 mod requests_service;
+mod route_access;
+mod session_service;
+#[cfg(test)]
+mod test_harness;
 mod tournament_service;
 mod trainings_service;
 mod tuition_service;
@@ -40,10 +39,55 @@ pub mod user_service;
 
 #[derive(Debug, Deserialize)]
 struct Config {
+    /// Remote libsql url, `file:<path>` for a local database or `:memory:`
+    #[serde(default)]
     db_url: String,
+    /// Not needed by the local and in memory databases
+    #[serde(default)]
     db_token: String,
+    /// Keeps an embedded replica of the remote database in this file
+    db_replica_path: Option<String>,
+    /// How often the embedded replica pulls changes from the remote database
+    db_sync_interval_seconds: Option<u64>,
+    /// Connections to the database open at the same time
+    db_pool_size: Option<usize>,
+    /// Time allowed to reach the database or to wait for a free connection
+    db_connect_timeout_seconds: Option<u64>,
+    /// Attempts made on a transient database error before giving up, when connecting and on every read
+    db_connect_retries: Option<u32>,
     port: String,
     token_key: String,
+    /// File where the password reset notifications are written, without it the reset tokens
+    /// are not delivered anywhere
+    notifier_outbox: Option<String>,
+    /// Country code given to the phones written without one, 57 (Colombia) when unset
+    default_phone_country_code: Option<String>,
+    /// Comma separated origins allowed to call the API from a browser, none when unset
+    cors_allowed_origins: Option<String>,
+    /// Time a request may take before it is answered with a 408
+    request_timeout_seconds: Option<u64>,
+    /// Largest request body accepted, in bytes
+    request_body_limit_bytes: Option<usize>,
+    /// Requests served at the same time, the rest wait for a free slot
+    max_concurrent_requests: Option<usize>,
+    /// Time the requests in flight get to finish after a SIGTERM or SIGINT
+    shutdown_drain_seconds: Option<u64>,
+    /// Take the client address from the `Fly-Client-IP` header, only safe behind the
+    /// fly.io proxy since any client can send it
+    #[serde(default)]
+    trust_proxy_header: bool,
+    /// Apply the pending migrations when the server starts, otherwise it refuses to
+    /// start until `migrate` is run
+    #[serde(default = "default_migrate_on_startup")]
+    migrate_on_startup: bool,
+    /// Runs on the in memory repositories instead of the database, the data is lost
+    /// when the process stops. Needs a build with the `in-memory` feature.
+    #[serde(default)]
+    demo_mode: bool,
+}
+
+fn default_migrate_on_startup() -> bool {
+    true
 }
 
 #[tokio::main]
@@ -53,84 +97,116 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let config: Config = envy::from_env()?;
 
-    let user_repository = LibSqlUserRepository::new(&config.db_url, &config.db_token)
-        .await
-        .expect("Error creating user repository");
-
-    let unique_identifier_repository =
-        LibSqlUniqueIdentifierRepo::new(&config.db_url, &config.db_token)
-            .await
-            .expect("Error creating unique identifier repository");
-
-    let tournament_repository = TournamentRepositoryImpl::new(&config.db_url, &config.db_token)
-        .await
-        .expect("Error creating tournment repository");
-
-    let training_repository = TrainingRepositoryImpl::new(&config.db_url, &config.db_token)
-        .await
-        .expect("Error creating the training repository");
-
-    let tuition_repository = TuitionRepositoryImpl::new(&config.db_url, &config.db_token)
-        .await
-        .expect("Error creating the tuition repository");
-
-    let request_repository = LibSqlRequestRepository::new(&config.db_url, &config.db_token)
-        .await
-        .expect("Error creating the tuition repository");
-
-    let unique_identifier = build_unique_identifier(unique_identifier_repository.clone());
-
-    let services: Vec<Box<dyn HttpService>> = vec![
-        Box::new(
-            UserHttpServer::new(
-                config.token_key.clone(),
-                unique_identifier.clone(),
-                user_repository.clone(),
-            )
-            .await,
-        ),
-        Box::new(
-            TournamentHttpServer::new(
-                tournament_repository.clone(),
-                unique_identifier.clone(),
-                &config.token_key,
-            )
-            .await,
-        ),
-        Box::new(
-            TrainingHttpServer::new(
-                training_repository.clone(),
-                unique_identifier.clone(),
-                &config.token_key,
-            )
-            .await,
-        ),
-        Box::new(
-            TuitionHttpServer::new(
-                tuition_repository.clone(),
-                unique_identifier.clone(),
-                &config.token_key,
-            )
-            .await,
-        ),
-        Box::new(UniqueIdentifierHttpServer::new(unique_identifier_repository).await),
-        Box::new(
-            RequestHttpServer::new(
-                user_repository.clone(),
-                tournament_repository.clone(),
-                request_repository.clone(),
-                training_repository.clone(),
-                unique_identifier.clone(),
-                config.token_key.to_string(),
-            )
-            .await,
-        ),
-    ];
-
-    match start_http_server(config.port, services).await {
-        Ok(_) => info!("Http server started succesfully"),
-        Err(err) => error!("Error starting http server: {err}"),
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        let db = open_database(&config).await?;
+        migrations::migrate(&*db.connect().await?).await?;
+        info!(
+            "Database schema is at version {}",
+            migrations::latest_version()
+        );
+        return Ok(());
+    }
+
+    let repositories = if config.demo_mode {
+        demo_repositories()?
+    } else {
+        let db = open_database(&config).await?;
+
+        let migration_conn = db.connect().await?;
+        if config.migrate_on_startup {
+            migrations::migrate(&migration_conn).await?;
+        } else {
+            migrations::ensure_up_to_date(&migration_conn).await?;
+        }
+        drop(migration_conn);
+
+        Repositories::libsql(db)
+    };
+
+    let notifier = Arc::new(LogNotifier::new(config.notifier_outbox.clone()));
+
+    let phone_normalizer = config
+        .default_phone_country_code
+        .as_deref()
+        .map_or_else(PhoneNormalizer::default, PhoneNormalizer::new);
+
+    let services =
+        build_http_services(&repositories, &config.token_key, notifier, phone_normalizer).await;
+
+    let server_config = server_config(&config);
+
+    match start_http_server(
+        config.port,
+        config.cors_allowed_origins,
+        services,
+        server_config,
+    )
+    .await
+    {
+        Ok(_) => info!("Http server stopped"),
+        Err(err) => error!("Error running http server: {err}"),
     };
 
     Ok(())
 }
+
+fn server_config(config: &Config) -> ServerConfig {
+    let default_server = ServerConfig::default();
+
+    ServerConfig {
+        request_timeout: config
+            .request_timeout_seconds
+            .map_or(default_server.request_timeout, Duration::from_secs),
+        body_limit: config
+            .request_body_limit_bytes
+            .unwrap_or(default_server.body_limit),
+        max_concurrent_requests: config
+            .max_concurrent_requests
+            .unwrap_or(default_server.max_concurrent_requests),
+        shutdown_drain: config
+            .shutdown_drain_seconds
+            .map_or(default_server.shutdown_drain, Duration::from_secs),
+        trust_proxy_header: config.trust_proxy_header,
+    }
+}
+
+async fn open_database(config: &Config) -> Result<Arc<DatabaseHandle>, Box<dyn Error>> {
+    if config.db_url.is_empty() {
+        return Err("DB_URL is not set, it is only optional in demo mode".into());
+    }
+
+    let default_pool = PoolConfig::default();
+    let pool_config = PoolConfig {
+        size: config.db_pool_size.unwrap_or(default_pool.size),
+        connect_timeout: config
+            .db_connect_timeout_seconds
+            .map_or(default_pool.connect_timeout, Duration::from_secs),
+        connect_retries: config
+            .db_connect_retries
+            .unwrap_or(default_pool.connect_retries),
+        ..default_pool
+    };
+
+    let db = DatabaseHandle::open(
+        DatabaseMode::from_config(&config.db_url, config.db_replica_path.as_deref()),
+        &config.db_token,
+        config.db_sync_interval_seconds.map(Duration::from_secs),
+        pool_config,
+    )
+    .await
+    .inspect_err(|err| error!("Cannot start without the database: {err}"))?;
+
+    Ok(db)
+}
+
+#[cfg(feature = "in-memory")]
+fn demo_repositories() -> Result<Repositories, Box<dyn Error>> {
+    tracing::warn!("Demo mode, the data lives in memory and is lost when the server stops");
+
+    Ok(Repositories::in_memory())
+}
+
+#[cfg(not(feature = "in-memory"))]
+fn demo_repositories() -> Result<Repositories, Box<dyn Error>> {
+    Err("DEMO_MODE needs a build with the in-memory feature".into())
+}
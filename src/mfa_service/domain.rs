@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// TOTP secret of a user, it only counts for the login once `enabled` is set by the
+/// verification step.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MfaDb {
+    pub id_persona: String,
+    pub secret: String,
+    pub enabled: bool,
+    /// Last TOTP time step accepted, older or equal steps are rejected to avoid replays
+    pub last_used_step: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MfaEnrollment {
+    pub secret: String,
+    /// `otpauth://` URI to render as the QR code for the authenticator app
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+/// What the login has to do about the second factor of a user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MfaStatus {
+    /// The user has MFA enabled, the login needs a code
+    Enabled,
+    /// MFA is required for the role but the user has not enrolled yet
+    EnrollmentRequired,
+    NotRequired,
+}
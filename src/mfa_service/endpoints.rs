@@ -0,0 +1,343 @@
+use std::sync::Arc;
+
+use crate::extract::Json;
+use axum::{extract::State, http::StatusCode, Extension};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api_error::ErrorBody,
+    auth_middleware::AuthContext,
+    global_traits::HttpService,
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
+    user_service::{domain::UserRol, repository::UserRepository},
+};
+
+use super::{
+    domain::{MfaEnrollment, RecoveryCodes},
+    err::MfaServiceError,
+    repository::MfaRepository,
+    use_cases::MfaService,
+};
+
+pub struct MfaHttpServer {
+    mfa_service: MfaService,
+    access_control: AccessControl,
+}
+
+impl MfaHttpServer {
+    pub async fn new(
+        mfa_repository: Arc<dyn MfaRepository>,
+        user_repository: Arc<dyn UserRepository>,
+        access_control: AccessControl,
+    ) -> Self {
+        Self {
+            mfa_service: MfaService::new(mfa_repository, user_repository),
+            access_control,
+        }
+    }
+}
+
+impl HttpService for MfaHttpServer {
+    fn get_router(&self) -> axum::Router {
+        self.access_control
+            .build_router("mfa", routes())
+            .with_state(self.mfa_service.clone())
+    }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(MfaApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<MfaService>> {
+    vec![
+        RouteAccess::post("/api/v1/users/me/mfa/enroll", Access::Authenticated, enroll),
+        RouteAccess::post(
+            "/api/v1/users/me/mfa/verify",
+            Access::Authenticated,
+            verify_enrollment,
+        ),
+        RouteAccess::get(
+            "/api/v1/mfa/required_roles",
+            Access::admin(),
+            get_required_roles,
+        ),
+        RouteAccess::put(
+            "/api/v1/mfa/required_roles",
+            Access::admin(),
+            set_required_roles,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(enroll, verify_enrollment, get_required_roles, set_required_roles),
+    tags((name = "mfa", description = "TOTP second factor of the login"))
+)]
+struct MfaApi;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MfaCode {
+    code: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/mfa/enroll",
+    tag = "mfa",
+    responses(
+        (status = 200, description = "New TOTP secret, it counts once it is verified", body = MfaEnrollment),
+        (status = 409, description = "`mfa_already_enabled`", body = ErrorBody),
+    )
+)]
+async fn enroll(
+    State(service): State<MfaService>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<MfaEnrollment>, MfaServiceError> {
+    Ok(Json(service.enroll(&auth_context.user_id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/mfa/verify",
+    tag = "mfa",
+    request_body = MfaCode,
+    responses(
+        (status = 200, description = "MFA is enabled, the recovery codes are only shown once", body = RecoveryCodes),
+        (status = 401, description = "`invalid_mfa_code`", body = ErrorBody),
+        (status = 404, description = "`mfa_not_enrolled`, the caller has not enrolled", body = ErrorBody),
+        (status = 409, description = "`mfa_already_enabled`", body = ErrorBody),
+    )
+)]
+async fn verify_enrollment(
+    State(service): State<MfaService>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(payload): Json<MfaCode>,
+) -> Result<Json<RecoveryCodes>, MfaServiceError> {
+    Ok(Json(
+        service
+            .verify_enrollment(&auth_context.user_id, &payload.code)
+            .await?,
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/mfa/required_roles",
+    tag = "mfa",
+    responses((status = 200, description = "Roles that must log in with MFA", body = Vec<UserRol>))
+)]
+async fn get_required_roles(
+    State(service): State<MfaService>,
+) -> Result<Json<Vec<UserRol>>, MfaServiceError> {
+    Ok(Json(service.get_required_roles().await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/mfa/required_roles",
+    tag = "mfa",
+    request_body = Vec<UserRol>,
+    responses((status = 200, description = "The roles that must log in with MFA were replaced"))
+)]
+async fn set_required_roles(
+    State(service): State<MfaService>,
+    Json(roles): Json<Vec<UserRol>>,
+) -> Result<StatusCode, MfaServiceError> {
+    service.set_required_roles(roles).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use totp_rs::TOTP;
+
+    use super::*;
+    use crate::test_harness::{TestApp, TEST_PASSWORD};
+
+    #[tokio::test]
+    async fn test_enroll_and_log_in_with_mfa() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        assert_eq!(
+            app.post("/api/v1/users/me/mfa/enroll", None, json!({}))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/users/me/mfa/verify",
+                Some(&user.token),
+                json!({ "code": "123456" })
+            )
+            .await
+            .status,
+            StatusCode::NOT_FOUND
+        );
+
+        let response = app
+            .post("/api/v1/users/me/mfa/enroll", Some(&user.token), json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let totp = TOTP::from_url(response.body["provisioning_uri"].as_str().unwrap()).unwrap();
+
+        assert_eq!(
+            app.post(
+                "/api/v1/users/me/mfa/verify",
+                Some(&user.token),
+                json!({ "code": "not-a-code" })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        let response = app
+            .post(
+                "/api/v1/users/me/mfa/verify",
+                Some(&user.token),
+                json!({ "code": totp.generate_current().unwrap() }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let recovery_code = response.body["recovery_codes"][0].as_str().unwrap();
+
+        let response = app
+            .post("/api/v1/users/me/mfa/enroll", Some(&user.token), json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["code"], "mfa_already_enabled");
+
+        let login = app.log_in(&user.correo, TEST_PASSWORD).await;
+        assert_eq!(login.status, StatusCode::OK);
+        assert_eq!(login.body["status"], "mfa_pending");
+
+        // The old login has no MFA step to hand the token to
+        let response = app
+            .post(
+                "/log_in",
+                None,
+                json!({ "identificacion": user.correo, "contrasena": TEST_PASSWORD }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body["code"], "mfa_required");
+
+        let mfa_token = login.body["mfa_token"].as_str().unwrap();
+        assert_eq!(
+            app.post(
+                "/api/v1/auth/login/mfa",
+                None,
+                json!({ "mfa_token": mfa_token, "code": "not-a-code" })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        let response = app
+            .post(
+                "/api/v1/auth/login/mfa",
+                None,
+                json!({ "mfa_token": mfa_token, "code": recovery_code }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body["access_token"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_required_roles() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+
+        assert_eq!(
+            app.get("/api/v1/mfa/required_roles", None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        let response = app
+            .get("/api/v1/mfa/required_roles", Some(&trainer.token))
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(response.body["code"], "role_not_allowed");
+        assert!(response.body["request_id"].is_string());
+        assert_eq!(
+            app.put(
+                "/api/v1/mfa/required_roles",
+                Some(&trainer.token),
+                json!(["Entrenador"])
+            )
+            .await
+            .status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.put(
+                "/api/v1/mfa/required_roles",
+                Some(&admin.token),
+                json!(["Rector"])
+            )
+            .await
+            .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            app.put(
+                "/api/v1/mfa/required_roles",
+                Some(&admin.token),
+                json!(["Entrenador"])
+            )
+            .await
+            .status,
+            StatusCode::OK
+        );
+
+        let response = app
+            .get("/api/v1/mfa/required_roles", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, json!(["Entrenador"]));
+    }
+
+    #[tokio::test]
+    async fn test_requiring_a_role_revokes_its_sessions_without_mfa() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+
+        let response = app
+            .put(
+                "/api/v1/mfa/required_roles",
+                Some(&admin.token),
+                json!(["Entrenador"]),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        assert_eq!(
+            app.get("/api/v1/users/me", Some(&trainer.token))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        let response = app
+            .post(
+                "/api/v1/token/refresh",
+                None,
+                json!({ "refresh_token": trainer.refresh_token }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+        // The sessions of the other roles are left alone
+        assert_eq!(
+            app.get("/api/v1/users/me", Some(&admin.token)).await.status,
+            StatusCode::OK
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::{api_error::ApiError, user_service::repository::err::UserRepositoryError};
+
+use super::repository::err::MfaRepositoryError;
+
+pub type Result<T> = std::result::Result<T, MfaServiceError>;
+
+#[derive(Error, Debug)]
+pub enum MfaServiceError {
+    #[error("MFA repository error: {0}")]
+    MfaRepositoryError(#[from] MfaRepositoryError),
+    #[error("User repository error: {0}")]
+    UserRepositoryError(#[from] UserRepositoryError),
+    #[error("TOTP error: {0}")]
+    TotpError(String),
+    #[error("MFA is already enabled for the user")]
+    AlreadyEnabled,
+    #[error("There is no MFA enrollment for the user")]
+    NotEnrolled,
+    #[error("The MFA code is not valid")]
+    InvalidCode,
+}
+
+impl IntoResponse for MfaServiceError {
+    fn into_response(self) -> Response {
+        match &self {
+            MfaServiceError::InvalidCode => ApiError::unauthorized("invalid_mfa_code", &self),
+            MfaServiceError::NotEnrolled => ApiError::not_found("mfa_not_enrolled", &self),
+            MfaServiceError::AlreadyEnabled => ApiError::conflict("mfa_already_enabled", &self),
+            _ => ApiError::internal(&self),
+        }
+        .into_response()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod domain;
+pub mod endpoints;
+pub mod err;
+pub mod repository;
+pub mod use_cases;
@@ -0,0 +1,23 @@
+use std::result;
+
+use thiserror::Error;
+
+use crate::database::from_libsql_error;
+
+pub type Result<T> = result::Result<T, MfaRepositoryError>;
+
+#[derive(Error, Debug)]
+pub enum MfaRepositoryError {
+    #[error("Database connection error: {0}")]
+    ConnectionError(libsql::Error),
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
+    #[error("Error deserializing into a struct from the database: {0}")]
+    DeserializationError(#[from] serde::de::value::Error),
+    #[error("serde json error: {0}")]
+    SerdeJsonError(#[from] serde_json::Error),
+}
+
+from_libsql_error!(MfaRepositoryError, MfaRepositoryError::ConnectionError);
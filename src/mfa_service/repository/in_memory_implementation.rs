@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+
+use crate::{
+    in_memory::{InMemoryStore, RecoveryCodeRow},
+    mfa_service::domain::MfaDb,
+    user_service::domain::UserRol,
+};
+
+use super::{err::Result, MfaRepository};
+
+#[derive(Clone)]
+pub struct InMemoryMfaRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryMfaRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl MfaRepository for InMemoryMfaRepository {
+    async fn get_mfa(&self, user_id: &str) -> Result<Option<MfaDb>> {
+        Ok(self.store.tables().mfa.get(user_id).cloned())
+    }
+
+    async fn save_pending_secret(&self, user_id: &str, secret: &str) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        if let Some(MfaDb { enabled: true, .. }) = tables.mfa.get(user_id) {
+            return Ok(());
+        }
+
+        tables.mfa.insert(
+            user_id.to_string(),
+            MfaDb {
+                id_persona: user_id.to_string(),
+                secret: secret.to_string(),
+                enabled: false,
+                last_used_step: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn enable_mfa(&self, user_id: &str, recovery_code_hashes: Vec<String>) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        if let Some(mfa) = tables.mfa.get_mut(user_id) {
+            mfa.enabled = true;
+        }
+
+        tables
+            .mfa_recovery_code
+            .retain(|code| code.id_persona != user_id);
+        tables
+            .mfa_recovery_code
+            .extend(
+                recovery_code_hashes
+                    .into_iter()
+                    .map(|code_hash| RecoveryCodeRow {
+                        code_hash,
+                        id_persona: user_id.to_string(),
+                        used: false,
+                    }),
+            );
+
+        Ok(())
+    }
+
+    async fn use_time_step(&self, user_id: &str, time_step: i64) -> Result<bool> {
+        let mut tables = self.store.tables();
+
+        match tables.mfa.get_mut(user_id) {
+            Some(mfa) if mfa.last_used_step.is_none_or(|last| last < time_step) => {
+                mfa.last_used_step = Some(time_step);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn consume_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool> {
+        let mut tables = self.store.tables();
+
+        match tables
+            .mfa_recovery_code
+            .iter_mut()
+            .find(|code| code.id_persona == user_id && code.code_hash == code_hash && !code.used)
+        {
+            Some(code) => {
+                code.used = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn get_required_roles(&self) -> Result<Vec<UserRol>> {
+        Ok(self.store.tables().mfa_required_role.clone())
+    }
+
+    async fn set_required_roles(&self, roles: Vec<UserRol>) -> Result<()> {
+        let mut tables = self.store.tables();
+        let tables = &mut *tables;
+
+        for session in tables.session.values_mut() {
+            let newly_required = roles.contains(&session.nombre_rol)
+                && !tables.mfa_required_role.contains(&session.nombre_rol);
+            let has_mfa = tables
+                .mfa
+                .get(&session.id_persona)
+                .is_some_and(|mfa| mfa.enabled);
+
+            if newly_required && !has_mfa {
+                session.revoked = true;
+            }
+        }
+        tables.mfa_required_role = roles;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use crate::database::{DatabaseHandle, PooledConnection};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use libsql::{de, params};
+use serde_json::json;
+
+use crate::{mfa_service::domain::MfaDb, user_service::domain::UserRol};
+
+use super::{err::Result, MfaRepository};
+
+#[derive(Clone)]
+pub struct LibSqlMfaRepository {
+    db: Arc<DatabaseHandle>,
+}
+
+impl LibSqlMfaRepository {
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
+    }
+
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        Ok(self.db.connect().await?)
+    }
+}
+
+#[async_trait]
+impl MfaRepository for LibSqlMfaRepository {
+    async fn get_mfa(&self, user_id: &str) -> Result<Option<MfaDb>> {
+        let conn = self.get_connection().await?;
+
+        let mut rows = conn
+            .read(
+                "SELECT id_persona, secret, enabled, last_used_step FROM mfa WHERE id_persona = ?1",
+                params![user_id],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(de::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save_pending_secret(&self, user_id: &str, secret: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        // The enabled check keeps a pending enrollment from replacing an active secret
+        conn.execute(
+            "INSERT INTO mfa (id_persona, secret, enabled, last_used_step)
+            VALUES (?1, ?2, 0, NULL)
+            ON CONFLICT (id_persona) DO UPDATE SET secret = excluded.secret, last_used_step = NULL
+            WHERE mfa.enabled = 0",
+            params![user_id, secret],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn enable_mfa(&self, user_id: &str, recovery_code_hashes: Vec<String>) -> Result<()> {
+        let conn = self.get_connection().await?;
+        let transaction = conn.transaction().await?;
+
+        transaction
+            .execute(
+                "UPDATE mfa SET enabled = 1 WHERE id_persona = ?1",
+                params![user_id],
+            )
+            .await?;
+
+        transaction
+            .execute(
+                "DELETE FROM mfa_recovery_code WHERE id_persona = ?1",
+                params![user_id],
+            )
+            .await?;
+
+        for code_hash in recovery_code_hashes {
+            transaction
+                .execute(
+                    "INSERT INTO mfa_recovery_code (code_hash, id_persona, used) VALUES (?1, ?2, 0)",
+                    params![code_hash, user_id],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    async fn use_time_step(&self, user_id: &str, time_step: i64) -> Result<bool> {
+        let conn = self.get_connection().await?;
+
+        let updated = conn
+            .execute(
+                "UPDATE mfa SET last_used_step = ?2
+                WHERE id_persona = ?1 AND (last_used_step IS NULL OR last_used_step < ?2)",
+                params![user_id, time_step],
+            )
+            .await?;
+
+        Ok(updated > 0)
+    }
+
+    async fn consume_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool> {
+        let conn = self.get_connection().await?;
+
+        let updated = conn
+            .execute(
+                "UPDATE mfa_recovery_code SET used = 1
+                WHERE id_persona = ?1 AND code_hash = ?2 AND used = 0",
+                params![user_id, code_hash],
+            )
+            .await?;
+
+        Ok(updated > 0)
+    }
+
+    async fn get_required_roles(&self) -> Result<Vec<UserRol>> {
+        let conn = self.get_connection().await?;
+
+        let mut rows = conn
+            .read("SELECT nombre_rol FROM mfa_required_role", params![])
+            .await?;
+
+        let mut roles = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            let string_role = row.get::<String>(0)?;
+            roles.push(serde_json::from_str(&json!(string_role).to_string())?);
+        }
+
+        Ok(roles)
+    }
+
+    async fn set_required_roles(&self, roles: Vec<UserRol>) -> Result<()> {
+        let conn = self.get_connection().await?;
+        let transaction = conn.transaction().await?;
+
+        for role in &roles {
+            transaction
+                .execute(
+                    "UPDATE session SET revoked = 1
+                     WHERE revoked = 0 AND nombre_rol = ?1
+                        AND nombre_rol NOT IN (SELECT nombre_rol FROM mfa_required_role)
+                        AND id_persona NOT IN (SELECT id_persona FROM mfa WHERE enabled = 1)",
+                    params![role.to_string()],
+                )
+                .await?;
+        }
+
+        transaction
+            .execute("DELETE FROM mfa_required_role", params![])
+            .await?;
+
+        for role in roles {
+            transaction
+                .execute(
+                    "INSERT INTO mfa_required_role (nombre_rol) VALUES (?1)",
+                    params![role.to_string()],
+                )
+                .await?;
+        }
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+use mockall::automock;
+
+pub mod err;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
+pub mod lib_sql_implementation;
+
+use crate::user_service::domain::UserRol;
+
+use super::domain::MfaDb;
+use err::Result;
+
+#[automock]
+#[async_trait]
+pub trait MfaRepository: Send + Sync {
+    async fn get_mfa(&self, user_id: &str) -> Result<Option<MfaDb>>;
+
+    /// Stores a new secret that is not enabled yet, replacing a previous pending one.
+    async fn save_pending_secret(&self, user_id: &str, secret: &str) -> Result<()>;
+
+    /// Enables the stored secret and replaces the recovery codes of the user.
+    async fn enable_mfa(&self, user_id: &str, recovery_code_hashes: Vec<String>) -> Result<()>;
+
+    /// Stores the time step of an accepted code, returns false when an equal or newer
+    /// step was already used.
+    async fn use_time_step(&self, user_id: &str, time_step: i64) -> Result<bool>;
+
+    /// Marks the recovery code as used, returns false when it does not exist or was used.
+    async fn consume_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool>;
+
+    async fn get_required_roles(&self) -> Result<Vec<UserRol>>;
+
+    /// Also revokes the sessions of the roles that become required, their tokens never
+    /// passed the second factor. The users that have MFA enabled keep theirs.
+    async fn set_required_roles(&self, roles: Vec<UserRol>) -> Result<()>;
+}
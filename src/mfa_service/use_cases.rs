@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, Rng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::user_service::{
+    domain::UserRol, repository::UserRepository, token_provider::hash_secret,
+};
+
+use super::domain::{MfaDb, MfaEnrollment, MfaStatus, RecoveryCodes};
+use super::err::{MfaServiceError, Result};
+use super::repository::MfaRepository;
+
+const TOTP_ISSUER: &str = "Sabana Club";
+const TOTP_DIGITS: usize = 6;
+const TOTP_STEP_SECONDS: u64 = 30;
+/// Codes of the previous and the next time step are accepted for clock drift
+const TOTP_ALLOWED_DRIFT_STEPS: i64 = 1;
+const RECOVERY_CODES: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+#[derive(Clone)]
+pub struct MfaService {
+    mfa_repository: Arc<dyn MfaRepository>,
+    user_repository: Arc<dyn UserRepository>,
+}
+
+impl MfaService {
+    pub fn new(
+        mfa_repository: Arc<dyn MfaRepository>,
+        user_repository: Arc<dyn UserRepository>,
+    ) -> Self {
+        Self {
+            mfa_repository,
+            user_repository,
+        }
+    }
+
+    /// Creates a new pending secret, it is not asked at login until it is verified.
+    pub async fn enroll(&self, user_id: &str) -> Result<MfaEnrollment> {
+        if let Some(MfaDb { enabled: true, .. }) = self.mfa_repository.get_mfa(user_id).await? {
+            return Err(MfaServiceError::AlreadyEnabled);
+        }
+
+        let mut secret = [0u8; 20];
+        rand::thread_rng().fill_bytes(&mut secret);
+        let encoded_secret = Secret::Raw(secret.to_vec()).to_encoded().to_string();
+
+        let user = self.user_repository.get_user_by_id(user_id).await?;
+        let totp = Self::totp(&encoded_secret, user.correo)?;
+
+        self.mfa_repository
+            .save_pending_secret(user_id, &encoded_secret)
+            .await?;
+
+        Ok(MfaEnrollment {
+            secret: encoded_secret,
+            provisioning_uri: totp.get_url(),
+        })
+    }
+
+    /// Enables MFA once the user proves the authenticator app has the secret, the
+    /// recovery codes are only shown in this response.
+    pub async fn verify_enrollment(&self, user_id: &str, code: &str) -> Result<RecoveryCodes> {
+        let mfa = self
+            .mfa_repository
+            .get_mfa(user_id)
+            .await?
+            .ok_or(MfaServiceError::NotEnrolled)?;
+
+        if mfa.enabled {
+            return Err(MfaServiceError::AlreadyEnabled);
+        }
+
+        if !self.check_totp(&mfa, code).await? {
+            return Err(MfaServiceError::InvalidCode);
+        }
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODES)
+            .map(|_| {
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(RECOVERY_CODE_LENGTH)
+                    .map(char::from)
+                    .collect()
+            })
+            .collect();
+
+        self.mfa_repository
+            .enable_mfa(
+                user_id,
+                recovery_codes
+                    .iter()
+                    .map(|code| hash_secret(code))
+                    .collect(),
+            )
+            .await?;
+
+        Ok(RecoveryCodes { recovery_codes })
+    }
+
+    pub async fn status(&self, user_id: &str, nombre_rol: &UserRol) -> Result<MfaStatus> {
+        if let Some(MfaDb { enabled: true, .. }) = self.mfa_repository.get_mfa(user_id).await? {
+            return Ok(MfaStatus::Enabled);
+        }
+
+        let required_roles = self.mfa_repository.get_required_roles().await?;
+
+        if required_roles.contains(nombre_rol) {
+            Ok(MfaStatus::EnrollmentRequired)
+        } else {
+            Ok(MfaStatus::NotRequired)
+        }
+    }
+
+    /// Checks the second factor of the login, either a TOTP code or an unused recovery code.
+    pub async fn verify_login_code(&self, user_id: &str, code: &str) -> Result<bool> {
+        let mfa = match self.mfa_repository.get_mfa(user_id).await? {
+            Some(mfa) if mfa.enabled => mfa,
+            _ => return Err(MfaServiceError::NotEnrolled),
+        };
+
+        let code = code.trim();
+
+        if code.len() == TOTP_DIGITS && code.chars().all(|c| c.is_ascii_digit()) {
+            return self.check_totp(&mfa, code).await;
+        }
+
+        Ok(self
+            .mfa_repository
+            .consume_recovery_code(user_id, &hash_secret(code))
+            .await?)
+    }
+
+    pub async fn get_required_roles(&self) -> Result<Vec<UserRol>> {
+        Ok(self.mfa_repository.get_required_roles().await?)
+    }
+
+    pub async fn set_required_roles(&self, roles: Vec<UserRol>) -> Result<()> {
+        Ok(self.mfa_repository.set_required_roles(roles).await?)
+    }
+
+    async fn check_totp(&self, mfa: &MfaDb, code: &str) -> Result<bool> {
+        let totp = Self::totp(&mfa.secret, mfa.id_persona.clone())?;
+        let current_step = Utc::now().timestamp() / TOTP_STEP_SECONDS as i64;
+
+        let matching_step = (current_step - TOTP_ALLOWED_DRIFT_STEPS
+            ..=current_step + TOTP_ALLOWED_DRIFT_STEPS)
+            .find(|step| totp.check(code, (*step as u64) * TOTP_STEP_SECONDS));
+
+        match matching_step {
+            Some(step) => Ok(self
+                .mfa_repository
+                .use_time_step(&mfa.id_persona, step)
+                .await?),
+            None => Ok(false),
+        }
+    }
+
+    fn totp(encoded_secret: &str, account_name: String) -> Result<TOTP> {
+        let secret = Secret::Encoded(encoded_secret.to_string())
+            .to_bytes()
+            .map_err(|err| MfaServiceError::TotpError(format!("{err:?}")))?;
+
+        TOTP::new(
+            Algorithm::SHA1,
+            TOTP_DIGITS,
+            0,
+            TOTP_STEP_SECONDS,
+            secret,
+            Some(TOTP_ISSUER.to_string()),
+            account_name,
+        )
+        .map_err(|err| MfaServiceError::TotpError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::mfa_service::repository::MockMfaRepository;
+    use crate::user_service::repository::MockUserRepository;
+
+    const USER_ID: &str = "5f405541-d1df-454a-b2fc-56004ba380cc";
+    const SECRET: &str = "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP";
+
+    fn enabled_mfa() -> MfaDb {
+        MfaDb {
+            id_persona: USER_ID.to_string(),
+            secret: SECRET.to_string(),
+            enabled: true,
+            last_used_step: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_login_totp_code() {
+        let current_step = Utc::now().timestamp() / TOTP_STEP_SECONDS as i64;
+        let code = MfaService::totp(SECRET, USER_ID.to_string())
+            .unwrap()
+            .generate(current_step as u64 * TOTP_STEP_SECONDS);
+
+        let mut mfa_repo = MockMfaRepository::new();
+        mfa_repo
+            .expect_get_mfa()
+            .returning(|_| Ok(Some(enabled_mfa())));
+        mfa_repo
+            .expect_use_time_step()
+            .with(eq(USER_ID), eq(current_step))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mfa_repo
+            .expect_consume_recovery_code()
+            .returning(|_, _| Ok(false));
+
+        let mfa_service = MfaService::new(Arc::new(mfa_repo), Arc::new(MockUserRepository::new()));
+
+        assert!(mfa_service.verify_login_code(USER_ID, &code).await.unwrap());
+        assert!(!mfa_service
+            .verify_login_code(USER_ID, "not-a-recovery-code")
+            .await
+            .unwrap());
+    }
+}
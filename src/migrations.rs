@@ -0,0 +1,275 @@
+use libsql::{params, Connection};
+use thiserror::Error;
+use tracing::info;
+
+/// A schema change compiled into the binary, versions are applied in ascending order
+/// and never edited once released.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "sessions",
+        sql: include_str!("../migrations/0002_sessions.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "password_reset",
+        sql: include_str!("../migrations/0003_password_reset.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "login_lockout",
+        sql: include_str!("../migrations/0004_login_lockout.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "mfa",
+        sql: include_str!("../migrations/0005_mfa.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "unique_persona_fields",
+        sql: include_str!("../migrations/0006_unique_persona_fields.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "phone_e164",
+        sql: include_str!("../migrations/0007_phone_e164.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "persona_identificacion_index",
+        sql: include_str!("../migrations/0008_persona_identificacion_index.sql"),
+    },
+];
+
+pub type Result<T> = std::result::Result<T, MigrationError>;
+
+#[derive(Error, Debug)]
+pub enum MigrationError {
+    #[error("Database error: {0}")]
+    ConnectionError(#[from] libsql::Error),
+    #[error("The database schema is at version {database_version} but this binary only knows up to {binary_version}, refusing to start")]
+    DatabaseAhead {
+        database_version: i64,
+        binary_version: i64,
+    },
+    #[error("The database schema is at version {database_version}, pending migrations up to {binary_version}, run the migrate command")]
+    PendingMigrations {
+        database_version: i64,
+        binary_version: i64,
+    },
+    #[error("Migration {version} ({name}) failed: {source}")]
+    MigrationFailed {
+        version: i64,
+        name: &'static str,
+        source: libsql::Error,
+    },
+}
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies the pending migrations, each one in its own transaction together with the
+/// row that records it.
+pub async fn migrate(conn: &Connection) -> Result<()> {
+    let database_version = check_not_ahead(conn).await?;
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > database_version)
+    {
+        info!(
+            "Applying migration {} ({})",
+            migration.version, migration.name
+        );
+
+        let failed = |source| MigrationError::MigrationFailed {
+            version: migration.version,
+            name: migration.name,
+            source,
+        };
+
+        let transaction = conn.transaction().await.map_err(failed)?;
+        transaction
+            .execute_batch(migration.sql)
+            .await
+            .map_err(failed)?;
+        transaction
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+                params![migration.version, migration.name],
+            )
+            .await
+            .map_err(failed)?;
+        transaction.commit().await.map_err(failed)?;
+    }
+
+    Ok(())
+}
+
+/// Startup check for when the migrations are not applied by the server itself.
+pub async fn ensure_up_to_date(conn: &Connection) -> Result<()> {
+    let database_version = check_not_ahead(conn).await?;
+
+    if database_version < latest_version() {
+        return Err(MigrationError::PendingMigrations {
+            database_version,
+            binary_version: latest_version(),
+        });
+    }
+
+    Ok(())
+}
+
+async fn check_not_ahead(conn: &Connection) -> Result<i64> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY NOT NULL,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+        )",
+        params![],
+    )
+    .await?;
+
+    let database_version = database_version(conn).await?;
+
+    if database_version > latest_version() {
+        return Err(MigrationError::DatabaseAhead {
+            database_version,
+            binary_version: latest_version(),
+        });
+    }
+
+    Ok(database_version)
+}
+
+/// Version of the last migration applied to the database, without touching the schema.
+pub async fn database_version(conn: &Connection) -> Result<i64> {
+    let mut rows = conn
+        .query(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            params![],
+        )
+        .await?;
+
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<i64>(0)?),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phone::PhoneNormalizer;
+
+    async fn memory_connection() -> Connection {
+        libsql::Builder::new_local(":memory:")
+            .build()
+            .await
+            .unwrap()
+            .connect()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_migrate_is_idempotent() {
+        let conn = memory_connection().await;
+
+        migrate(&conn).await.unwrap();
+        migrate(&conn).await.unwrap();
+        ensure_up_to_date(&conn).await.unwrap();
+
+        let mut rows = conn
+            .query("SELECT COUNT(*) FROM schema_migrations", params![])
+            .await
+            .unwrap();
+        let applied: i64 = rows.next().await.unwrap().unwrap().get(0).unwrap();
+
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+    }
+
+    #[tokio::test]
+    async fn test_database_ahead_of_binary() {
+        let conn = memory_connection().await;
+        migrate(&conn).await.unwrap();
+
+        conn.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES (?1, 'future')",
+            params![latest_version() + 1],
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            migrate(&conn).await,
+            Err(MigrationError::DatabaseAhead { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_phones_are_converted_to_e164() {
+        let conn = memory_connection().await;
+        check_not_ahead(&conn).await.unwrap();
+        for migration in MIGRATIONS.iter().filter(|migration| migration.version < 7) {
+            conn.execute_batch(migration.sql).await.unwrap();
+            conn.execute(
+                "INSERT INTO schema_migrations (version, name) VALUES (?1, ?2)",
+                params![migration.version, migration.name],
+            )
+            .await
+            .unwrap();
+        }
+
+        for (id_persona, telefono) in [("a", 3185920708u64), ("b", 573001234567)] {
+            conn.execute(
+                "INSERT INTO persona (id_persona, nombre, contrasena, correo, telefono, identificacion, nombre_tipo_identificacion)
+                 VALUES (?1, 'Nombre', 'hash', ?1 || '@unisabana.edu.co', ?2, ?1, 'CC')",
+                params![id_persona, telefono],
+            )
+            .await
+            .unwrap();
+        }
+
+        migrate(&conn).await.unwrap();
+
+        let mut rows = conn
+            .query(
+                "SELECT telefono, typeof(telefono) FROM persona ORDER BY id_persona",
+                params![],
+            )
+            .await
+            .unwrap();
+        let mut phones = Vec::new();
+        while let Some(row) = rows.next().await.unwrap() {
+            phones.push((row.get::<String>(0).unwrap(), row.get::<String>(1).unwrap()));
+        }
+
+        assert_eq!(
+            phones,
+            [
+                ("+573185920708".to_string(), "text".to_string()),
+                ("+573001234567".to_string(), "text".to_string())
+            ]
+        );
+
+        // The phones written from now on are normalized by the same rule
+        let normalizer = PhoneNormalizer::default();
+        for (phone, (migrated, _)) in ["3185920708", "573001234567"].iter().zip(&phones) {
+            assert_eq!(normalizer.normalize(phone).as_ref(), Some(migrated));
+        }
+    }
+}
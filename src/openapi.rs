@@ -0,0 +1,149 @@
+//! OpenAPI document of the whole API, merged from the documents of the services, and the
+//! routes that serve it with the Swagger UI.
+
+use axum::Router;
+use utoipa::{
+    openapi::{
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+        OpenApi as OpenApiDocument,
+    },
+    Modify, OpenApi,
+};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::{
+    api_error::{ErrorBody, FieldError},
+    auth_middleware::{ACCESS_TOKEN_COOKIE, CSRF_HEADER},
+    global_traits::HttpService,
+};
+
+pub const BEARER_SCHEME: &str = "bearer_token";
+pub const COOKIE_SCHEME: &str = "access_token_cookie";
+pub const CSRF_SCHEME: &str = "csrf_token";
+
+pub const OPENAPI_PATH: &str = "/openapi.json";
+pub const DOCS_PATH: &str = "/docs";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Sabana Club API",
+        description = "Every error response with a body is an `ErrorBody`, match on its `code`."
+    ),
+    components(schemas(ErrorBody, FieldError)),
+    modifiers(&SecuritySchemes)
+)]
+struct ApiDoc;
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut OpenApiDocument) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            BEARER_SCHEME,
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .description(Some(
+                        "Access token from `/api/v1/auth/login` or `/api/v1/token/refresh`",
+                    ))
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            COOKIE_SCHEME,
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                ACCESS_TOKEN_COOKIE,
+                "Set by the login and the refresh when they are called with `?cookie=true`",
+            ))),
+        );
+        components.add_security_scheme(
+            CSRF_SCHEME,
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                CSRF_HEADER,
+                "Value of the `csrf_token` cookie, needed by the cookie auth outside of GET",
+            ))),
+        );
+    }
+}
+
+/// The document of every service merged with the security schemes and the error schemas.
+pub fn openapi_document(http_services: &[Box<dyn HttpService>]) -> OpenApiDocument {
+    let mut openapi = ApiDoc::openapi();
+
+    for http_service in http_services {
+        openapi.merge(http_service.openapi());
+    }
+
+    openapi
+}
+
+/// Serves the document in [`OPENAPI_PATH`] and the Swagger UI to browse it in [`DOCS_PATH`].
+pub fn docs_router(openapi: OpenApiDocument) -> Router {
+    SwaggerUi::new(DOCS_PATH).url(OPENAPI_PATH, openapi).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{test_harness::TestApp, user_service::domain::UserRol};
+
+    #[tokio::test]
+    async fn test_openapi_document_is_served() {
+        let app = TestApp::new().await;
+
+        let response = app.get(OPENAPI_PATH, None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let document = response.body;
+
+        let security_schemes = &document["components"]["securitySchemes"];
+        for scheme in [BEARER_SCHEME, COOKIE_SCHEME, CSRF_SCHEME] {
+            assert!(security_schemes[scheme].is_object(), "{scheme}");
+        }
+        let schemas = &document["components"]["schemas"];
+        for schema in [
+            "ErrorBody",
+            "RequestContent",
+            "UserCreationInfo",
+            "TrainingInfo",
+        ] {
+            assert!(schemas[schema].is_object(), "{schema}");
+        }
+
+        let delete_tournament = &document["paths"]["/api/v1/tournaments/{tournament_id}"]["delete"];
+        assert_eq!(
+            delete_tournament["security"],
+            json!([
+                { "bearer_token": [] },
+                { "access_token_cookie": [], "csrf_token": [] }
+            ])
+        );
+        assert!(delete_tournament["responses"]["401"].is_object());
+        assert!(delete_tournament["responses"]["403"].is_object());
+        assert_eq!(
+            delete_tournament["responses"]["404"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/ErrorBody" })
+        );
+        // The documented 404 is the one the route answers
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let response = app
+            .delete("/api/v1/tournaments/no-existe", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "tournament_not_found");
+        assert!(response.body["message"].is_string());
+
+        let all_tournaments = &document["paths"]["/api/v1/tournaments"]["get"];
+        assert!(all_tournaments.get("security").is_none());
+        assert_eq!(all_tournaments["description"], "Access: public");
+
+        let response = app.get(&format!("{DOCS_PATH}/"), None).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+}
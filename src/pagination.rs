@@ -0,0 +1,536 @@
+//! Pagination, sorting and filtering of the list endpoints. The query string is parsed once
+//! into [`ListParams`] and every repository list method takes it, the libsql ones turn it
+//! into SQL and the in-memory ones apply it with [`paginate`].
+
+#[cfg(any(test, feature = "in-memory"))]
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{request::Parts, StatusCode},
+};
+use serde::{Deserialize, Serialize};
+#[cfg(any(test, feature = "in-memory"))]
+use serde_json::Value as JsonValue;
+use utoipa::{
+    openapi::{
+        path::{Parameter, ParameterBuilder, ParameterIn},
+        schema::{ObjectBuilder, Type},
+        Required,
+    },
+    IntoParams, ToSchema,
+};
+
+use crate::api_error::{ApiError, FieldError};
+use crate::database::PooledConnection;
+
+pub const DEFAULT_LIMIT: u32 = 20;
+pub const MAX_LIMIT: u32 = 100;
+
+/// SQLite takes the `OFFSET` as a signed 64 bit integer.
+const MAX_OFFSET: u64 = i64::MAX as u64;
+
+/// A resource that can be listed, the fields it can be sorted and filtered by are the
+/// columns of its table with the same name.
+pub trait Listable {
+    /// The first one is the default sort.
+    const SORT_FIELDS: &'static [&'static str];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)];
+    /// Unique field that breaks the ties of the sort, so the pages never overlap.
+    const ID_FIELD: &'static str;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    Text,
+    Bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Text(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// What part of a list to return. The field names come from the [`Listable`] lists, so
+/// they are safe to put in a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListParams {
+    pub offset: u64,
+    pub limit: u32,
+    pub sort: &'static str,
+    pub order: SortOrder,
+    pub filters: Vec<(&'static str, FilterValue)>,
+    pub id_field: &'static str,
+}
+
+impl ListParams {
+    /// The first page with the default size and sort.
+    pub fn new<T: Listable>() -> Self {
+        Self {
+            offset: 0,
+            limit: DEFAULT_LIMIT,
+            sort: T::SORT_FIELDS[0],
+            order: SortOrder::Asc,
+            filters: Vec::new(),
+            id_field: T::ID_FIELD,
+        }
+    }
+
+    /// Every row in one page, for the deprecated routes that always returned whole tables.
+    pub fn unbounded<T: Listable>() -> Self {
+        Self {
+            limit: u32::MAX,
+            ..Self::new::<T>()
+        }
+    }
+
+    pub fn with_limit(self, limit: u32) -> Self {
+        Self { limit, ..self }
+    }
+
+    /// Only the rows whose `field` is `value`, the field has to be a filter of the resource.
+    pub fn with_filter(mut self, field: &'static str, value: FilterValue) -> Self {
+        self.filters.push((field, value));
+        self
+    }
+
+    /// Parses `page` or `cursor`, `limit`, `sort`, `order` and the filters of `T`, with an
+    /// error for every parameter that is not valid.
+    pub fn from_query<T: Listable>(
+        query: &[(String, String)],
+    ) -> std::result::Result<Self, Vec<FieldError>> {
+        let mut params = Self::new::<T>();
+        let mut errors = Vec::new();
+        let mut error = |field: &str, message: &str| {
+            errors.push(FieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            })
+        };
+        let mut page = None;
+        let mut cursor = None;
+
+        for (key, value) in query {
+            match key.as_str() {
+                "page" => match value.parse::<u64>() {
+                    Ok(number) if (1..=MAX_OFFSET).contains(&number) => page = Some(number),
+                    _ => error("page", "Must be a number starting at 1"),
+                },
+                "cursor" => match decode_cursor(value) {
+                    Some(offset) => cursor = Some(offset),
+                    None => error("cursor", "Must be a `next_cursor` given by the API"),
+                },
+                "limit" => match value.parse::<u32>() {
+                    Ok(limit) if (1..=MAX_LIMIT).contains(&limit) => params.limit = limit,
+                    _ => error("limit", &format!("Must be between 1 and {MAX_LIMIT}")),
+                },
+                "sort" => match T::SORT_FIELDS.iter().find(|field| **field == value) {
+                    Some(field) => params.sort = field,
+                    None => error(
+                        "sort",
+                        &format!("Must be one of: {}", T::SORT_FIELDS.join(", ")),
+                    ),
+                },
+                "order" => match value.as_str() {
+                    "asc" => params.order = SortOrder::Asc,
+                    "desc" => params.order = SortOrder::Desc,
+                    _ => error("order", "Must be `asc` or `desc`"),
+                },
+                _ => match T::FILTER_FIELDS.iter().find(|(field, _)| field == key) {
+                    Some((field, FilterKind::Text)) => params
+                        .filters
+                        .push((field, FilterValue::Text(value.clone()))),
+                    Some((field, FilterKind::Bool)) => match value.parse::<bool>() {
+                        Ok(value) => params.filters.push((field, FilterValue::Bool(value))),
+                        Err(_) => error(key, "Must be `true` or `false`"),
+                    },
+                    None => error(key, "Unknown query parameter"),
+                },
+            }
+        }
+
+        match (page, cursor) {
+            (Some(_), Some(_)) => error("cursor", "Can not be used together with `page`"),
+            (Some(page), None) => match (page - 1).checked_mul(u64::from(params.limit)) {
+                Some(offset) if offset <= MAX_OFFSET => params.offset = offset,
+                _ => error("page", "Is past the last page that can be asked for"),
+            },
+            (None, Some(offset)) => params.offset = offset,
+            (None, None) => {}
+        }
+
+        if errors.is_empty() {
+            Ok(params)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Conditions of the filters joined by `AND`, with a `?` for each value of
+    /// [`ListParams::filter_values`]. `prefix` qualifies the columns, like `p.`.
+    pub fn sql_conditions(&self, prefix: &str) -> String {
+        if self.filters.is_empty() {
+            return "TRUE".to_string();
+        }
+
+        self.filters
+            .iter()
+            .map(|(field, _)| format!("{prefix}{field} = ?"))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    pub fn filter_values(&self) -> Vec<libsql::Value> {
+        self.filters
+            .iter()
+            .map(|(_, value)| match value {
+                FilterValue::Text(text) => libsql::Value::Text(text.clone()),
+                FilterValue::Bool(boolean) => libsql::Value::Integer(i64::from(*boolean)),
+            })
+            .collect()
+    }
+
+    /// `ORDER BY`, `LIMIT` and `OFFSET` of the page.
+    pub fn sql_page(&self, prefix: &str) -> String {
+        let order = match self.order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+
+        format!(
+            "ORDER BY {prefix}{sort} {order}, {prefix}{id} {order} LIMIT {limit} OFFSET {offset}",
+            sort = self.sort,
+            id = self.id_field,
+            limit = self.limit,
+            offset = self.offset,
+        )
+    }
+}
+
+/// Runs a `SELECT COUNT(*)`, the total of a [`Page`], retried like the other reads.
+pub async fn count_rows(
+    conn: &PooledConnection,
+    sql: &str,
+    values: Vec<libsql::Value>,
+) -> libsql::Result<u64> {
+    let mut rows = conn.read(sql, values).await?;
+
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<u64>(0)?),
+        None => Ok(0),
+    }
+}
+
+/// One page of a list, `next_cursor` is null on the last one.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Rows that pass the filters, in every page
+    pub total: u64,
+    /// Value of `cursor` that returns the next page. It encodes the offset of that
+    /// page, so rows created or deleted between the requests shift the pages.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn new(items: Vec<T>, total: u64, params: &ListParams) -> Self {
+        let next_offset = params.offset + items.len() as u64;
+        let next_cursor =
+            (!items.is_empty() && next_offset < total).then(|| encode_cursor(next_offset));
+
+        Self {
+            items,
+            total,
+            next_cursor,
+        }
+    }
+}
+
+/// The cursor is the offset of the next page in hex, not a position keyed on the
+/// sort column, clients should still treat it as opaque.
+fn encode_cursor(offset: u64) -> String {
+    format!("{offset:x}")
+}
+
+fn decode_cursor(cursor: &str) -> Option<u64> {
+    u64::from_str_radix(cursor, 16)
+        .ok()
+        .filter(|offset| *offset <= MAX_OFFSET)
+}
+
+/// Applies the params to rows kept in memory, comparing the fields of their JSON form the
+/// way SQLite compares the columns.
+#[cfg(any(test, feature = "in-memory"))]
+pub fn paginate<T: Serialize>(rows: Vec<T>, params: &ListParams) -> Page<T> {
+    let mut rows: Vec<(JsonValue, T)> = rows
+        .into_iter()
+        .map(|row| (serde_json::to_value(&row).unwrap_or_default(), row))
+        .filter(|(json, _)| {
+            params.filters.iter().all(|(field, value)| match value {
+                FilterValue::Text(text) => json[field].as_str() == Some(text.as_str()),
+                FilterValue::Bool(boolean) => json[field].as_bool() == Some(*boolean),
+            })
+        })
+        .collect();
+
+    rows.sort_by(|(a, _), (b, _)| {
+        let ordering = compare_json(&a[params.sort], &b[params.sort])
+            .then_with(|| compare_json(&a[params.id_field], &b[params.id_field]));
+
+        match params.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    });
+
+    let total = rows.len() as u64;
+    let items = rows
+        .into_iter()
+        .map(|(_, row)| row)
+        .skip(params.offset.try_into().unwrap_or(usize::MAX))
+        .take(params.limit as usize)
+        .collect();
+
+    Page::new(items, total, params)
+}
+
+/// Nulls first, then booleans, numbers and strings by their bytes, like SQLite.
+#[cfg(any(test, feature = "in-memory"))]
+fn compare_json(a: &JsonValue, b: &JsonValue) -> Ordering {
+    fn rank(value: &JsonValue) -> u8 {
+        match value {
+            JsonValue::Null => 0,
+            JsonValue::Bool(_) | JsonValue::Number(_) => 1,
+            JsonValue::String(_) => 2,
+            _ => 3,
+        }
+    }
+
+    let number = |value: &JsonValue| match value {
+        JsonValue::Bool(boolean) => f64::from(u8::from(*boolean)),
+        value => value.as_f64().unwrap_or_default(),
+    };
+
+    match (a, b) {
+        (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+        (a, b) if rank(a) == 1 && rank(b) == 1 => number(a).total_cmp(&number(b)),
+        (a, b) => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// The [`ListParams`] of `T` from the query string, a 422 with every invalid parameter
+/// otherwise.
+pub struct ListQuery<T>(pub ListParams, pub PhantomData<fn() -> T>);
+
+impl<S, T> FromRequestParts<S> for ListQuery<T>
+where
+    S: Send + Sync,
+    T: Listable,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+            .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, "invalid_query", err))?;
+
+        ListParams::from_query::<T>(&query)
+            .map(|params| ListQuery(params, PhantomData))
+            .map_err(|errors| ApiError::validation(&errors))
+    }
+}
+
+impl<T: Listable> IntoParams for ListQuery<T> {
+    fn into_params(_parameter_in_provider: impl Fn() -> Option<ParameterIn>) -> Vec<Parameter> {
+        let parameter = |name: &str, description: String, schema: ObjectBuilder| {
+            ParameterBuilder::new()
+                .name(name)
+                .parameter_in(ParameterIn::Query)
+                .required(Required::False)
+                .description(Some(description))
+                .schema(Some(schema))
+                .build()
+        };
+
+        let mut parameters = vec![
+            parameter(
+                "page",
+                "Page number, starting at 1".to_string(),
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .minimum(Some(1)),
+            ),
+            parameter(
+                "cursor",
+                "`next_cursor` of the previous page, instead of `page`. It is an offset, \
+                 so rows created or deleted in between shift the pages"
+                    .to_string(),
+                ObjectBuilder::new().schema_type(Type::String),
+            ),
+            parameter(
+                "limit",
+                format!("Items per page, {DEFAULT_LIMIT} by default"),
+                ObjectBuilder::new()
+                    .schema_type(Type::Integer)
+                    .minimum(Some(1))
+                    .maximum(Some(MAX_LIMIT)),
+            ),
+            parameter(
+                "sort",
+                format!("Field to sort by, `{}` by default", T::SORT_FIELDS[0]),
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .enum_values(Some(T::SORT_FIELDS.iter().copied())),
+            ),
+            parameter(
+                "order",
+                "`asc` by default".to_string(),
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .enum_values(Some(["asc", "desc"])),
+            ),
+        ];
+
+        parameters.extend(T::FILTER_FIELDS.iter().map(|(field, kind)| {
+            let schema_type = match kind {
+                FilterKind::Text => Type::String,
+                FilterKind::Bool => Type::Boolean,
+            };
+
+            parameter(
+                field,
+                format!("Only the items whose `{field}` is this value"),
+                ObjectBuilder::new().schema_type(schema_type),
+            )
+        }));
+
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        id: &'static str,
+        nombre: Option<&'static str>,
+        activo: bool,
+    }
+
+    impl Listable for Row {
+        const SORT_FIELDS: &'static [&'static str] = &["nombre", "id"];
+        const FILTER_FIELDS: &'static [(&'static str, FilterKind)] =
+            &[("activo", FilterKind::Bool)];
+        const ID_FIELD: &'static str = "id";
+    }
+
+    fn query(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_params_from_query() {
+        let params = ListParams::from_query::<Row>(&query(&[
+            ("page", "3"),
+            ("limit", "10"),
+            ("sort", "id"),
+            ("order", "desc"),
+            ("activo", "true"),
+        ]))
+        .unwrap();
+        assert_eq!(params.offset, 20);
+        assert_eq!(params.limit, 10);
+        assert_eq!(params.sort, "id");
+        assert_eq!(params.order, SortOrder::Desc);
+        assert_eq!(params.filters, vec![("activo", FilterValue::Bool(true))]);
+
+        let params =
+            ListParams::from_query::<Row>(&query(&[("cursor", &encode_cursor(7))])).unwrap();
+        assert_eq!(params.offset, 7);
+        assert_eq!(
+            params,
+            ListParams {
+                offset: 7,
+                ..ListParams::new::<Row>()
+            }
+        );
+
+        let errors = ListParams::from_query::<Row>(&query(&[
+            ("page", "0"),
+            ("limit", "101"),
+            ("sort", "contrasena"),
+            ("order", "up"),
+            ("activo", "si"),
+            ("otro", "x"),
+        ]))
+        .unwrap_err();
+        let fields: Vec<_> = errors.iter().map(|error| error.field.as_str()).collect();
+        assert_eq!(fields, ["page", "limit", "sort", "order", "activo", "otro"]);
+
+        let errors =
+            ListParams::from_query::<Row>(&query(&[("page", "1"), ("cursor", "a")])).unwrap_err();
+        assert_eq!(errors[0].field, "cursor");
+    }
+
+    #[test]
+    fn test_paginate_sorts_like_sqlite() {
+        let rows = || {
+            vec![
+                Row {
+                    id: "3",
+                    nombre: Some("b"),
+                    activo: true,
+                },
+                Row {
+                    id: "1",
+                    nombre: None,
+                    activo: false,
+                },
+                Row {
+                    id: "2",
+                    nombre: Some("b"),
+                    activo: true,
+                },
+                Row {
+                    id: "4",
+                    nombre: Some("a"),
+                    activo: true,
+                },
+            ]
+        };
+        let ids = |page: &Page<Row>| page.items.iter().map(|row| row.id).collect::<Vec<_>>();
+
+        let page = paginate(rows(), &ListParams::new::<Row>().with_limit(3));
+        assert_eq!(ids(&page), ["1", "4", "2"]);
+        assert_eq!(page.total, 4);
+        assert_eq!(page.next_cursor, Some(encode_cursor(3)));
+
+        let params = ListParams {
+            offset: 3,
+            ..ListParams::new::<Row>().with_limit(3)
+        };
+        let page = paginate(rows(), &params);
+        assert_eq!(ids(&page), ["3"]);
+        assert_eq!(page.next_cursor, None);
+
+        let params = ListParams {
+            order: SortOrder::Desc,
+            ..ListParams::new::<Row>().with_filter("activo", FilterValue::Bool(true))
+        };
+        let page = paginate(rows(), &params);
+        assert_eq!(ids(&page), ["3", "2", "4"]);
+        assert_eq!(page.total, 3);
+    }
+}
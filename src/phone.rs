@@ -0,0 +1,193 @@
+//! Phones are stored in E.164, `+` and the country code followed by the number, so the
+//! same phone written in different ways points to the same `persona`.
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+/// Colombia, used for the phones written without a country code.
+pub const DEFAULT_COUNTRY_CODE: &str = "57";
+
+/// Digits of a Colombian phone without the country code. None of them starts with 57, so a
+/// number of this length after the country code already carries it.
+const NATIONAL_DIGITS: usize = 10;
+
+/// E.164 numbers have at most 15 digits counting the country code.
+const MAX_E164_DIGITS: usize = 15;
+const MIN_E164_DIGITS: usize = 8;
+
+/// Characters people use to group the digits of a phone.
+const SEPARATORS: [char; 5] = [' ', '-', '.', '(', ')'];
+
+#[derive(Debug, Clone)]
+pub struct PhoneNormalizer {
+    default_country_code: String,
+}
+
+impl PhoneNormalizer {
+    /// Takes the country code with or without the `+`.
+    pub fn new(default_country_code: &str) -> Self {
+        Self {
+            default_country_code: default_country_code.trim_start_matches('+').to_string(),
+        }
+    }
+
+    /// The E.164 form of the phone, `None` when it can not be a phone. A phone that starts
+    /// with `+` or `00` already has its country code, so does one made of the default code
+    /// and a national number, any other one gets the default. Migration 0007 converted the
+    /// stored phones with the same rule.
+    pub fn normalize(&self, phone: &str) -> Option<String> {
+        let phone = phone.trim();
+        let (international, number) = match phone.strip_prefix('+') {
+            Some(number) => (true, number),
+            None => match phone.strip_prefix("00") {
+                Some(number) => (true, number),
+                None => (false, phone),
+            },
+        };
+
+        let digits = strip_separators(number);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let has_default_code = digits.len() == self.default_country_code.len() + NATIONAL_DIGITS
+            && digits.starts_with(&self.default_country_code);
+
+        let e164 = if international || has_default_code {
+            format!("+{digits}")
+        } else {
+            format!("+{}{digits}", self.default_country_code)
+        };
+
+        is_e164(&e164).then_some(e164)
+    }
+}
+
+impl Default for PhoneNormalizer {
+    fn default() -> Self {
+        Self::new(DEFAULT_COUNTRY_CODE)
+    }
+}
+
+/// Whether the phone is already in the form it is stored with.
+pub fn is_e164(phone: &str) -> bool {
+    phone.strip_prefix('+').is_some_and(|digits| {
+        (MIN_E164_DIGITS..=MAX_E164_DIGITS).contains(&digits.len())
+            && !digits.starts_with('0')
+            && digits.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// The phone without the characters used to group its digits, for searches by a part
+/// of the number.
+pub fn strip_separators(phone: &str) -> String {
+    phone.chars().filter(|c| !SEPARATORS.contains(c)).collect()
+}
+
+/// Phones used to be numbers, clients and the stored requests can still send them so.
+pub fn deserialize_phone<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Phone {
+        Text(String),
+        Number(u64),
+    }
+
+    let phone = Phone::deserialize(deserializer)
+        .map_err(|_| de::Error::custom("must be a phone number, as text or as a number"))?;
+
+    Ok(match phone {
+        Phone::Text(phone) => phone,
+        Phone::Number(phone) => phone.to_string(),
+    })
+}
+
+/// The phone as a number for the clients of the legacy routes, the digits of the E.164 form
+/// without the `+`. Sent back, they are normalized to the same phone.
+pub fn serialize_legacy_phone<S>(phone: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match phone.trim_start_matches('+').parse::<u64>() {
+        Ok(number) => serializer.serialize_u64(number),
+        Err(_) => serializer.serialize_str(phone),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ways_of_writing_a_phone_give_the_same_number() {
+        let normalizer = PhoneNormalizer::default();
+
+        for phone in [
+            "3185920708",
+            "318 592 0708",
+            "318-592-0708",
+            "(318) 592 0708",
+            "+573185920708",
+            "+57 318 592 0708",
+            "00573185920708",
+            "573185920708",
+            "57 318 592 0708",
+        ] {
+            assert_eq!(
+                normalizer.normalize(phone).as_deref(),
+                Some("+573185920708"),
+                "{phone}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_country_code_is_configurable() {
+        let normalizer = PhoneNormalizer::new("+1");
+
+        assert_eq!(
+            normalizer.normalize("415 555 0100").as_deref(),
+            Some("+14155550100")
+        );
+        assert_eq!(
+            normalizer.normalize("+573185920708").as_deref(),
+            Some("+573185920708")
+        );
+    }
+
+    #[test]
+    fn test_what_is_not_a_phone() {
+        let normalizer = PhoneNormalizer::default();
+
+        for phone in [
+            "",
+            "abc",
+            "318 592 07O8",
+            "+",
+            "12",
+            "+0573185920708",
+            "+57318592070812345",
+        ] {
+            assert_eq!(normalizer.normalize(phone), None, "{phone}");
+        }
+    }
+
+    #[test]
+    fn test_phone_numbers_are_still_accepted() {
+        #[derive(Deserialize)]
+        struct Payload {
+            #[serde(deserialize_with = "deserialize_phone")]
+            telefono: String,
+        }
+
+        for body in [
+            r#"{"telefono": 3185920708}"#,
+            r#"{"telefono": "3185920708"}"#,
+        ] {
+            let payload: Payload = serde_json::from_str(body).unwrap();
+            assert_eq!(payload.telefono, "3185920708");
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use crate::{
+    database::{DatabaseHandle, UnitOfWork},
+    health_service::repository::{
+        lib_sql_implementation::LibSqlHealthRepository, HealthRepository,
+    },
+    mfa_service::repository::{lib_sql_implementation::LibSqlMfaRepository, MfaRepository},
+    requests_service::repository::{
+        lib_sql_implementation::LibSqlRequestRepository, RequestRepository,
+    },
+    session_service::repository::{
+        lib_sql_implementation::LibSqlSessionRepository, SessionRepository,
+    },
+    tournament_service::repository::{
+        lib_sql_implementation::TournamentRepositoryImpl, TournamentRepository,
+    },
+    trainings_service::repository::{
+        lib_sql_implementation::TrainingRepositoryImpl, TrainingRepository,
+    },
+    tuition_service::repository::{
+        lib_sql_implementation::TuitionRepositoryImpl, TuitionRepository,
+    },
+    unique_identifier_service::repository::{
+        lib_sql_implementation::LibSqlUniqueIdentifierRepo, UniqueIdentifierRepository,
+    },
+    user_service::repository::{libsql_implementation::LibSqlUserRepository, UserRepository},
+};
+
+/// Every repository of the API, all of them backed by the same storage.
+#[derive(Clone)]
+pub struct Repositories {
+    pub user: Arc<dyn UserRepository>,
+    pub unique_identifier: Arc<dyn UniqueIdentifierRepository>,
+    pub tournament: Arc<dyn TournamentRepository>,
+    pub training: Arc<dyn TrainingRepository>,
+    pub tuition: Arc<dyn TuitionRepository>,
+    pub request: Arc<dyn RequestRepository>,
+    pub session: Arc<dyn SessionRepository>,
+    pub mfa: Arc<dyn MfaRepository>,
+    pub health: Arc<dyn HealthRepository>,
+    pub unit_of_work: UnitOfWork,
+}
+
+impl Repositories {
+    pub fn libsql(db: Arc<DatabaseHandle>) -> Self {
+        Self {
+            user: Arc::new(LibSqlUserRepository::new(db.clone())),
+            unique_identifier: Arc::new(LibSqlUniqueIdentifierRepo::new(db.clone())),
+            tournament: Arc::new(TournamentRepositoryImpl::new(db.clone())),
+            training: Arc::new(TrainingRepositoryImpl::new(db.clone())),
+            tuition: Arc::new(TuitionRepositoryImpl::new(db.clone())),
+            request: Arc::new(LibSqlRequestRepository::new(db.clone())),
+            session: Arc::new(LibSqlSessionRepository::new(db.clone())),
+            mfa: Arc::new(LibSqlMfaRepository::new(db.clone())),
+            health: Arc::new(LibSqlHealthRepository::new(db.clone())),
+            unit_of_work: UnitOfWork::new(db),
+        }
+    }
+
+    /// HashMap backed repositories that start empty, nothing outlives the process.
+    #[cfg(any(test, feature = "in-memory"))]
+    pub fn in_memory() -> Self {
+        use crate::{
+            health_service::repository::in_memory_implementation::InMemoryHealthRepository,
+            in_memory::InMemoryStore,
+            mfa_service::repository::in_memory_implementation::InMemoryMfaRepository,
+            requests_service::repository::in_memory_implementation::InMemoryRequestRepository,
+            session_service::repository::in_memory_implementation::InMemorySessionRepository,
+            tournament_service::repository::in_memory_implementation::InMemoryTournamentRepository,
+            trainings_service::repository::in_memory_implementation::InMemoryTrainingRepository,
+            tuition_service::repository::in_memory_implementation::InMemoryTuitionRepository,
+            unique_identifier_service::repository::in_memory_implementation::InMemoryUniqueIdentifierRepo,
+            user_service::repository::in_memory_implementation::InMemoryUserRepository,
+        };
+
+        let store = InMemoryStore::new();
+
+        Self {
+            user: Arc::new(InMemoryUserRepository::new(store.clone())),
+            unique_identifier: Arc::new(InMemoryUniqueIdentifierRepo::new(store.clone())),
+            tournament: Arc::new(InMemoryTournamentRepository::new(store.clone())),
+            training: Arc::new(InMemoryTrainingRepository::new(store.clone())),
+            tuition: Arc::new(InMemoryTuitionRepository::new(store.clone())),
+            request: Arc::new(InMemoryRequestRepository::new(store.clone())),
+            session: Arc::new(InMemorySessionRepository::new(store.clone())),
+            mfa: Arc::new(InMemoryMfaRepository::new(store)),
+            health: Arc::new(InMemoryHealthRepository),
+            // The tables have no transactions, a failed unit of work keeps its earlier writes
+            unit_of_work: UnitOfWork::disabled(),
+        }
+    }
+}
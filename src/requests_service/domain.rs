@@ -1,17 +1,15 @@
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
 
 use crate::{
+    pagination::{FilterKind, Listable},
     tournament_service::{err::TournamentServiceError, use_cases::TournamentService},
     trainings_service::{err::TrainingServiceError, use_cases::TrainingService},
-    user_service::{
-        domain::{UserCreationInfo, UserUpdating},
-        err::UserServiceError,
-        use_cases::UserService,
-    },
+    user_service::{domain::UserUpdating, err::UserServiceError, use_cases::UserService},
 };
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct RequestForApproval {
     pub requester_id: String,
     pub request_id: String,
@@ -21,6 +19,17 @@ pub struct RequestForApproval {
     pub completed: bool,
 }
 
+impl Listable for RequestForApproval {
+    const SORT_FIELDS: &'static [&'static str] =
+        &["command_name", "requester_id", "completed", "request_id"];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = &[
+        ("command_name", FilterKind::Text),
+        ("requester_id", FilterKind::Text),
+        ("completed", FilterKind::Bool),
+    ];
+    const ID_FIELD: &'static str = "request_id";
+}
+
 impl TryFrom<RequestForApprovalDb> for RequestForApproval {
     type Error = serde_json::Error;
 
@@ -40,7 +49,7 @@ impl TryFrom<RequestForApprovalDb> for RequestForApproval {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RequestForApprovalDb {
     pub requester_id: String,
     pub request_id: String,
@@ -50,13 +59,7 @@ pub struct RequestForApprovalDb {
     pub completed: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct RequestCreation {
-    pub command_name: String,
-    pub command_content: RequestContent,
-}
-
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 #[serde(tag = "type")]
 pub enum RequestContent {
     UpdateUser {
@@ -120,9 +123,9 @@ impl CommandExecutor {
 #[derive(Error, Debug)]
 pub enum CommandError {
     #[error("Error executing commad in the user service")]
-    UserServiceError(#[from] UserServiceError),
+    User(#[from] UserServiceError),
     #[error("Error executing commad in the tournament service")]
-    TournamentServiceError(#[from] TournamentServiceError),
+    Tournament(#[from] TournamentServiceError),
     #[error("Error executing commad in the trainnig service")]
-    TrainingServiceError(#[from] TrainingServiceError),
+    Training(#[from] TrainingServiceError),
 }
@@ -1,22 +1,20 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    middleware,
-    routing::{delete, get, post},
-    Extension, Json, Router,
-};
-use tracing::{error, info};
+use crate::extract::{Json, Path};
+use axum::{extract::State, http::StatusCode, Extension};
+use utoipa::OpenApi;
 
 use crate::{
-    auth_middleware::auth_middleware,
+    api_error::ErrorBody,
+    auth_middleware::AuthContext,
+    database::UnitOfWork,
     global_traits::HttpService,
+    pagination::{FilterValue, ListParams, ListQuery, Page},
+    phone::PhoneNormalizer,
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
+    session_service::{repository::SessionRepository, use_cases::SessionService},
     tournament_service::{repository::TournamentRepository, use_cases::TournamentService},
-    trainings_service::{
-        repository::{lib_sql_implementation::TrainingRepositoryImpl, TrainingRepository},
-        use_cases::TrainingService,
-    },
+    trainings_service::{repository::TrainingRepository, use_cases::TrainingService},
     unique_identifier_service::usecases::UniqueIdentifier,
     user_service::{
         repository::UserRepository, token_provider::TokenProvider, use_cases::UserService,
@@ -24,31 +22,47 @@ use crate::{
 };
 
 use super::{
-    domain::{CommandExecutor, RequestContent, RequestCreation, RequestForApproval},
+    domain::{CommandExecutor, RequestContent, RequestForApproval},
+    err::RequestServiceError,
     repository::RequestRepository,
     usecases::RequestService,
 };
 
 pub struct RequestHttpServer {
     user_repository: Arc<dyn UserRepository>,
+    session_repository: Arc<dyn SessionRepository>,
     tournament_repository: Arc<dyn TournamentRepository>,
     unique_identifier: Arc<dyn UniqueIdentifier>,
     training_repository: Arc<dyn TrainingRepository>,
     request_repository: Arc<dyn RequestRepository>,
     token_key: String,
+    unit_of_work: UnitOfWork,
+    phone_normalizer: PhoneNormalizer,
+    access_control: AccessControl,
 }
 
 impl RequestHttpServer {
+    // The executed commands reach into the user, tournament and training services, so the
+    // server takes all of their dependencies
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         user_repository: Arc<dyn UserRepository>,
+        session_repository: Arc<dyn SessionRepository>,
         tournament_repository: Arc<dyn TournamentRepository>,
         request_repository: Arc<dyn RequestRepository>,
         training_repository: Arc<dyn TrainingRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
         token_key: String,
+        unit_of_work: UnitOfWork,
+        phone_normalizer: PhoneNormalizer,
+        access_control: AccessControl,
     ) -> Self {
         Self {
+            access_control,
+            unit_of_work,
+            phone_normalizer,
             user_repository,
+            session_repository,
             tournament_repository,
             unique_identifier,
             token_key,
@@ -61,21 +75,25 @@ impl RequestHttpServer {
 impl HttpService for RequestHttpServer {
     fn get_router(&self) -> axum::Router {
         let token_provider = TokenProvider::new(self.token_key.clone());
+        let session_service = SessionService::new(self.session_repository.clone(), token_provider);
 
         let user_service = UserService::new(
             self.user_repository.clone(),
             self.unique_identifier.clone(),
-            token_provider,
+            session_service,
+            self.phone_normalizer.clone(),
         );
 
         let tournament_service = TournamentService::new(
             self.tournament_repository.clone(),
             self.unique_identifier.clone(),
+            self.unit_of_work.clone(),
         );
 
         let training_service = TrainingService::new(
             self.training_repository.clone(),
             self.unique_identifier.clone(),
+            self.unit_of_work.clone(),
         );
 
         let command_executor = CommandExecutor {
@@ -84,105 +102,411 @@ impl HttpService for RequestHttpServer {
             training_service,
         };
 
-        let request_service =
-            RequestService::new(command_executor, self.request_repository.clone());
-
-        Router::new()
-            .route("/request/name/{name}", get(get_requests_by_name))
-            .route("/request/id/{request_id}", get(get_request_by_id))
-            .route("/request", post(create_request))
-            .route("/request/execute/{request_id}", post(execute_request))
-            .route("/request/all", get(get_all_requests))
-            .route("/request/{request_id}", delete(delete_request))
-            .layer(middleware::from_fn_with_state(
-                self.token_key.clone(),
-                auth_middleware,
-            ))
+        let request_service = RequestService::new(
+            command_executor,
+            self.request_repository.clone(),
+            self.unit_of_work.clone(),
+        );
+
+        self.access_control
+            .build_router("request", routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("request", legacy_routes()),
+            )
             .with_state(request_service)
     }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(RequestApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<RequestService>> {
+    vec![
+        RouteAccess::get("/api/v1/requests", Access::admin(), list_requests),
+        RouteAccess::post("/api/v1/requests", Access::Authenticated, create_request),
+        RouteAccess::get(
+            "/api/v1/requests/{request_id}",
+            Access::Authenticated,
+            get_request_by_id,
+        ),
+        RouteAccess::delete(
+            "/api/v1/requests/{request_id}",
+            Access::admin(),
+            delete_request,
+        ),
+        RouteAccess::post(
+            "/api/v1/requests/{request_id}/execute",
+            Access::admin(),
+            execute_request,
+        ),
+    ]
+}
+
+fn legacy_routes() -> Vec<RouteAccess<RequestService>> {
+    vec![
+        RouteAccess::get(
+            "/request/name/{name}",
+            Access::admin(),
+            get_requests_by_name,
+        ),
+        RouteAccess::get(
+            "/request/id/{request_id}",
+            Access::Authenticated,
+            get_request_by_id,
+        ),
+        RouteAccess::post("/request", Access::Authenticated, create_request),
+        RouteAccess::post(
+            "/request/execute/{request_id}",
+            Access::admin(),
+            execute_request,
+        ),
+        RouteAccess::get("/request/all", Access::admin(), get_all_requests),
+        RouteAccess::delete(
+            "/request/{request_id}",
+            Access::admin(),
+            legacy_delete_request,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_requests,
+        create_request,
+        get_request_by_id,
+        delete_request,
+        execute_request,
+    ),
+    tags((name = "request", description = "Changes asked by the users that an admin has to approve"))
+)]
+struct RequestApi;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/requests",
+    tag = "request",
+    params(ListQuery<RequestForApproval>),
+    responses(
+        (status = 200, description = "Page of the requests", body = Page<RequestForApproval>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_requests(
+    State(request_service): State<RequestService>,
+    ListQuery(params, _): ListQuery<RequestForApproval>,
+) -> Result<Json<Page<RequestForApproval>>, RequestServiceError> {
+    Ok(Json(request_service.list_requests(&params).await?))
 }
 
 async fn get_all_requests(
     State(request_service): State<RequestService>,
-) -> Result<Json<Vec<RequestForApproval>>, StatusCode> {
-    match request_service.get_all_requests().await {
-        Err(err) => {
-            error!("Error getting request by name: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(requests) => Ok(Json(requests)),
-    }
+) -> Result<Json<Vec<RequestForApproval>>, RequestServiceError> {
+    let requests = request_service
+        .list_requests(&ListParams::unbounded::<RequestForApproval>())
+        .await?;
+
+    Ok(Json(requests.items))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/requests/{request_id}",
+    tag = "request",
+    params(("request_id" = String, Path)),
+    responses(
+        (status = 204, description = "The request was deleted"),
+        (status = 404, description = "`request_not_found`", body = ErrorBody),
+    )
+)]
 async fn delete_request(
     State(request_service): State<RequestService>,
     Path(request_id): Path<String>,
-) -> StatusCode {
-    match request_service.delete_request(request_id).await {
-        Err(err) => {
-            error!("Error executing request: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+) -> Result<StatusCode, RequestServiceError> {
+    request_service.delete_request(request_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
+async fn legacy_delete_request(
+    State(request_service): State<RequestService>,
+    Path(request_id): Path<String>,
+) -> Result<StatusCode, RequestServiceError> {
+    request_service.delete_request(request_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/requests/{request_id}/execute",
+    tag = "request",
+    params(("request_id" = String, Path)),
+    responses(
+        (status = 200, description = "The command of the request was run and the request approved"),
+        (status = 404, description = "`request_not_found`, or the error of the service that runs the command", body = ErrorBody),
+        (status = 409, description = "`request_already_completed`, or the error of the service that runs the command", body = ErrorBody),
+        (status = 422, description = "Error of the service that runs the command", body = ErrorBody),
+    )
+)]
 async fn execute_request(
     State(request_service): State<RequestService>,
-    Extension(aprover_id): Extension<String>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(request_id): Path<String>,
-) -> StatusCode {
-    match request_service
-        .execute_request(request_id, &aprover_id)
-        .await
-    {
-        Err(err) => {
-            error!("Error executing request: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+) -> Result<StatusCode, RequestServiceError> {
+    request_service
+        .execute_request(request_id, &auth_context.user_id)
+        .await?;
+
+    Ok(StatusCode::OK)
 }
 
 async fn get_requests_by_name(
     State(request_service): State<RequestService>,
     Path(request_name): Path<String>,
-) -> Result<Json<Vec<RequestForApproval>>, StatusCode> {
-    match request_service.get_requests_by_name(request_name).await {
-        Err(err) => {
-            error!("Error getting request by name: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(requests) => Ok(Json(requests)),
-    }
+) -> Result<Json<Vec<RequestForApproval>>, RequestServiceError> {
+    let params = ListParams::unbounded::<RequestForApproval>()
+        .with_filter("command_name", FilterValue::Text(request_name));
+
+    Ok(Json(request_service.list_requests(&params).await?.items))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/requests/{request_id}",
+    tag = "request",
+    params(("request_id" = String, Path)),
+    responses(
+        (status = 200, description = "The request", body = RequestForApproval),
+        (status = 404, description = "`request_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_request_by_id(
     State(request_service): State<RequestService>,
     Path(request_id): Path<String>,
-) -> Result<Json<RequestForApproval>, StatusCode> {
-    match request_service.get_request_by_id(request_id).await {
-        Err(err) => {
-            error!("Error getting request by name: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(requests) => Ok(Json(requests)),
-    }
+) -> Result<Json<RequestForApproval>, RequestServiceError> {
+    Ok(Json(request_service.get_request_by_id(request_id).await?))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/requests",
+    tag = "request",
+    request_body = RequestContent,
+    responses(
+        (status = 200, description = "The request is waiting for approval"),
+        (status = 422, description = "`invalid_reference`", body = ErrorBody),
+    )
+)]
 async fn create_request(
     State(request_service): State<RequestService>,
-    Extension(user_id): Extension<String>,
+    Extension(auth_context): Extension<AuthContext>,
     Json(request_creation): Json<RequestContent>,
-) -> StatusCode {
-    match request_service
-        .create_request(request_creation, user_id)
-        .await
-    {
-        Ok(_) => StatusCode::OK,
-        Err(err) => {
-            error!("Error creating request: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
+) -> Result<StatusCode, RequestServiceError> {
+    request_service
+        .create_request(request_creation, auth_context.user_id)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        test_harness::{TestApp, TestUser},
+        user_service::domain::UserRol,
+    };
+
+    fn update_user_request(user: &TestUser, nombre: &str) -> Value {
+        json!({
+            "type": "UpdateUser",
+            "user_id": user.id_persona,
+            "user_updation": {
+                "nombre": nombre,
+                "correo": user.correo,
+                "telefono": user.telefono,
+                "identificacion": user.identificacion,
+                "nombre_tipo_identificacion": "CC"
+            }
+        })
+    }
+
+    async fn create_request(app: &TestApp, user: &TestUser, nombre: &str) -> String {
+        let response = app
+            .post(
+                "/api/v1/requests",
+                Some(&user.token),
+                update_user_request(user, nombre),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        let params = ListParams::new::<RequestForApproval>()
+            .with_filter("command_name", FilterValue::Text("update_user".to_string()));
+        let requests = app
+            .repositories
+            .request
+            .list_requests(&params)
+            .await
+            .unwrap();
+        requests.items[0].request_id.clone()
+    }
+
+    #[tokio::test]
+    async fn test_create_and_get_requests() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        assert_eq!(
+            app.post(
+                "/api/v1/requests",
+                None,
+                update_user_request(&user, "Nuevo")
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/requests",
+                Some(&user.token),
+                json!({ "type": "Unknown" })
+            )
+            .await
+            .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let request_id = create_request(&app, &user, "Nuevo").await;
+
+        let uri = format!("/api/v1/requests/{request_id}");
+        assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+        let response = app.get(&uri, Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["requester_id"], json!(user.id_persona));
+        assert_eq!(response.body["completed"], json!(false));
+        let response = app
+            .get("/api/v1/requests/no-existe", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "request_not_found");
+
+        for uri in [
+            "/api/v1/requests",
+            "/api/v1/requests?command_name=update_user",
+        ] {
+            assert_eq!(app.get(uri, None).await.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                app.get(uri, Some(&user.token)).await.status,
+                StatusCode::FORBIDDEN,
+                "GET {uri}"
+            );
+            let response = app.get(uri, Some(&admin.token)).await;
+            assert_eq!(response.status, StatusCode::OK, "GET {uri}");
+            assert_eq!(response.body["items"][0]["request_id"], json!(request_id));
         }
+
+        let response = app
+            .get(
+                "/api/v1/requests?command_name=delete_training",
+                Some(&admin.token),
+            )
+            .await;
+        assert_eq!(response.body["items"], json!([]));
+
+        let response = app
+            .get(
+                "/api/v1/requests?completed=false&nombre=x",
+                Some(&admin.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["errors"][0]["field"], "nombre");
+    }
+
+    #[tokio::test]
+    async fn test_execute_request() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let request_id = create_request(&app, &user, "Nuevo").await;
+
+        let uri = format!("/api/v1/requests/{request_id}/execute");
+        assert_eq!(
+            app.post(&uri, None, json!({})).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post(&uri, Some(&user.token), json!({})).await.status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(&uri, Some(&admin.token), json!({})).await.status,
+            StatusCode::OK
+        );
+        let response = app.post(&uri, Some(&admin.token), json!({})).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["code"], "request_already_completed");
+        let response = app
+            .post(
+                "/api/v1/requests/no-existe/execute",
+                Some(&admin.token),
+                json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "request_not_found");
+
+        let response = app.get("/api/v1/users/me", Some(&user.token)).await;
+        assert_eq!(response.body["nombre"], "Nuevo");
+
+        let response = app
+            .get(&format!("/api/v1/requests/{request_id}"), Some(&user.token))
+            .await;
+        assert_eq!(response.body["completed"], json!(true));
+        assert_eq!(response.body["aprover_id"], json!(admin.id_persona));
+    }
+
+    #[tokio::test]
+    async fn test_delete_request() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let request_id = create_request(&app, &user, "Nuevo").await;
+
+        let uri = format!("/api/v1/requests/{request_id}");
+        assert_eq!(
+            app.delete(&uri, None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&user.token)).await.status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&admin.token)).await.status,
+            StatusCode::NO_CONTENT
+        );
+
+        let response = app.get("/api/v1/requests", Some(&admin.token)).await;
+        assert_eq!(response.body["items"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_request() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+
+        let response = app
+            .delete("/api/v1/requests/no-existe", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "request_not_found");
     }
 }
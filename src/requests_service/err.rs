@@ -1,6 +1,7 @@
+use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
-use crate::user_service::err::UserServiceError;
+use crate::api_error::ApiError;
 
 use super::{domain::CommandError, repository::err::RequestRepositoryError};
 
@@ -9,9 +10,42 @@ pub type Result<T> = std::result::Result<T, RequestServiceError>;
 #[derive(Debug, Error)]
 pub enum RequestServiceError {
     #[error("Error executing a command in the user service: {0}")]
-    CommandExecutionError(#[from] CommandError),
+    CommandExecution(#[from] CommandError),
     #[error("request repository error: {0}")]
-    RequestRepositoryError(#[from] RequestRepositoryError),
+    RequestRepository(#[from] RequestRepositoryError),
     #[error("serialization error: {0}")]
-    SerializationError(#[from] serde_json::Error),
+    Serialization(#[from] serde_json::Error),
+    #[error("Transaction error: {0}")]
+    Transaction(#[from] libsql::Error),
+    #[error("The request {0} was already executed")]
+    AlreadyCompleted(String),
+}
+
+impl IntoResponse for RequestServiceError {
+    fn into_response(self) -> Response {
+        match self {
+            // A failed command answers like the service that runs it
+            RequestServiceError::CommandExecution(CommandError::User(err)) => err.into_response(),
+            RequestServiceError::CommandExecution(CommandError::Tournament(err)) => {
+                err.into_response()
+            }
+            RequestServiceError::CommandExecution(CommandError::Training(err)) => {
+                err.into_response()
+            }
+            RequestServiceError::RequestRepository(RequestRepositoryError::CommandDontExist) => {
+                ApiError::not_found("request_not_found", "The request does not exist")
+                    .into_response()
+            }
+            RequestServiceError::RequestRepository(RequestRepositoryError::Duplicate { field }) => {
+                ApiError::duplicate(&field).into_response()
+            }
+            RequestServiceError::RequestRepository(RequestRepositoryError::ForeignKeyViolation) => {
+                ApiError::invalid_reference().into_response()
+            }
+            RequestServiceError::AlreadyCompleted(_) => {
+                ApiError::conflict("request_already_completed", &self).into_response()
+            }
+            err => ApiError::internal(&err).into_response(),
+        }
+    }
 }
@@ -2,16 +2,20 @@ use std::result;
 
 use thiserror::Error;
 
+use crate::database::from_libsql_error;
+
 pub type Result<T> = result::Result<T, RequestRepositoryError>;
 
 #[derive(Error, Debug)]
 pub enum RequestRepositoryError {
     #[error("Internal database error: {0}")]
-    InternalDbError(#[from] Box<dyn std::error::Error>),
-    #[error("User not found")]
-    UserNotFound,
+    InternalDbError(#[from] Box<dyn std::error::Error + Send + Sync>),
     #[error("Database connection error: {0}")]
-    ConnectionError(#[from] libsql::Error),
+    ConnectionError(libsql::Error),
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
     #[error("Error deserializing into a struct form the database: {0}")]
     DeserializationError(#[from] serde::de::value::Error),
     #[error("chrono date operation error: {0}")]
@@ -21,3 +25,8 @@ pub enum RequestRepositoryError {
     #[error("serialization error in serde_json: {0}")]
     DeserializationErrorSerdeJson(#[from] serde_json::Error),
 }
+
+from_libsql_error!(
+    RequestRepositoryError,
+    RequestRepositoryError::ConnectionError
+);
@@ -0,0 +1,77 @@
+use async_trait::async_trait;
+
+use crate::{
+    in_memory::InMemoryStore,
+    pagination::{paginate, ListParams, Page},
+    requests_service::domain::{RequestForApproval, RequestForApprovalDb},
+};
+
+use super::{
+    err::{RequestRepositoryError, Result},
+    RequestRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemoryRequestRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryRequestRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl RequestRepository for InMemoryRequestRepository {
+    async fn get_commands_by_id(&self, command_id: &str) -> Result<RequestForApproval> {
+        let request = self
+            .store
+            .tables()
+            .request_for_approval
+            .get(command_id)
+            .cloned()
+            .ok_or(RequestRepositoryError::CommandDontExist)?;
+
+        Ok(RequestForApproval::try_from(request)?)
+    }
+
+    async fn create_command(&self, request: RequestForApprovalDb) -> Result<()> {
+        self.store
+            .tables()
+            .request_for_approval
+            .insert(request.request_id.clone(), request);
+
+        Ok(())
+    }
+
+    async fn delete_request(&self, request_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .request_for_approval
+            .remove(request_id)
+            .map(|_| ())
+            .ok_or(RequestRepositoryError::CommandDontExist)
+    }
+
+    async fn list_requests(&self, params: &ListParams) -> Result<Page<RequestForApproval>> {
+        let tables = self.store.tables();
+
+        let mut requests = Vec::new();
+
+        for request in tables.request_for_approval.values() {
+            requests.push(RequestForApproval::try_from(request.clone())?);
+        }
+
+        Ok(paginate(requests, params))
+    }
+
+    async fn aprove_request(&self, request_id: &str, approver_id: &str) -> Result<()> {
+        if let Some(request) = self.store.tables().request_for_approval.get_mut(request_id) {
+            request.completed = true;
+            request.aprover_id = Some(approver_id.to_string());
+        }
+
+        Ok(())
+    }
+}
@@ -1,31 +1,28 @@
-use std::{result, sync::Arc};
+use crate::database::{DatabaseHandle, PooledConnection};
+use crate::pagination::{count_rows, ListParams, Page};
+use std::sync::Arc;
 
-use crate::requests_service::domain::{RequestContent, RequestForApproval, RequestForApprovalDb};
+use crate::requests_service::domain::{RequestForApproval, RequestForApprovalDb};
 
 use super::{
     err::{RequestRepositoryError, Result},
     RequestRepository,
 };
 use async_trait::async_trait;
-use libsql::{de, params, Connection, Database};
+use libsql::{de, params};
 
 #[derive(Clone)]
 pub struct LibSqlRequestRepository {
-    db: Arc<Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl LibSqlRequestRepository {
-    pub async fn new(url: &str, token: &str) -> result::Result<Arc<dyn RequestRepository>, String> {
-        let db = libsql::Builder::new_remote(url.to_string(), token.to_string())
-            .build()
-            .await
-            .map_err(|err| format!("Error creating new remote database for libsql: {err}"))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<Connection> {
-        Ok(self.db.connect()?)
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        Ok(self.db.connect().await?)
     }
 }
 
@@ -43,30 +40,10 @@ impl RequestRepository for LibSqlRequestRepository {
         Ok(())
     }
 
-    async fn get_commands_by_name(&self, command_name: &str) -> Result<Vec<RequestForApproval>> {
-        let conn = self.get_connection().await?;
-
-        let mut rows = conn.query(
-            "SELECT r.requester_id, r.request_id, r.command_name, r.command_content, r.aprover_id, r.completed
-            FROM request_for_approval AS r WHERE r.command_name = ?1",
-            params![command_name],
-        ).await?;
-
-        let mut requests = Vec::new();
-
-        while let Some(row) = rows.next().await? {
-            let request: RequestForApprovalDb = de::from_row(&row)?;
-
-            requests.push(RequestForApproval::try_from(request)?);
-        }
-
-        Ok(requests)
-    }
-
     async fn get_commands_by_id(&self, command_id: &str) -> Result<RequestForApproval> {
         let conn = self.get_connection().await?;
 
-        let mut row = conn.query(
+        let mut row = conn.read(
             "SELECT r.requester_id, r.request_id, r.command_name, r.command_content, r.aprover_id, r.completed
             FROM request_for_approval AS r WHERE r.request_id = ?1",
             params![command_id],
@@ -103,23 +80,39 @@ impl RequestRepository for LibSqlRequestRepository {
     async fn delete_request(&self, request_id: &str) -> Result<()> {
         let conn = self.get_connection().await?;
 
-        conn.execute(
-            "DELETE FROM request_for_approval WHERE request_id = ?1",
-            params![request_id],
-        )
-        .await?;
+        let deleted = conn
+            .execute(
+                "DELETE FROM request_for_approval WHERE request_id = ?1",
+                params![request_id],
+            )
+            .await?;
+
+        if deleted == 0 {
+            return Err(RequestRepositoryError::CommandDontExist);
+        }
 
         Ok(())
     }
 
-    async fn get_all_commands(&self) -> Result<Vec<RequestForApproval>> {
+    async fn list_requests(&self, params: &ListParams) -> Result<Page<RequestForApproval>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("");
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM request_for_approval WHERE {conditions}"),
+            params.filter_values(),
+        )
+        .await?;
 
         let mut rows = conn
-            .query(
-                "SELECT requester_id, request_id, command_name, command_content, aprover_id, completed
-FROM request_for_approval",
-                params![],
+            .read(
+                &format!(
+                    "SELECT requester_id, request_id, command_name, command_content, aprover_id, completed
+                    FROM request_for_approval WHERE {conditions} {}",
+                    params.sql_page("")
+                ),
+                params.filter_values(),
             )
             .await?;
 
@@ -133,7 +126,7 @@ FROM request_for_approval",
             requests.push(request);
         }
 
-        Ok(requests)
+        Ok(Page::new(requests, total, params))
     }
 }
 
@@ -154,7 +147,7 @@ mod tests {
                 user_updation: UserUpdating {
                     nombre: "Esteban".to_string(),
                     correo: "estebanmff@gmail.com".to_string(),
-                    telefono: 3185920708,
+                    telefono: "+573185920708".to_string(),
                     identificacion: "1014739191".to_string(),
                     nombre_tipo_identificacion: "CC".to_string(),
                 },
@@ -208,7 +201,8 @@ mod tests {
         {
             assert_eq!(user_updation.nombre, "Esteban");
             assert_eq!(user_updation.correo, "estebanmff@gmail.com");
-            assert_eq!(user_updation.telefono, 3185920708);
+            // Requests stored before the phones were strings
+            assert_eq!(user_updation.telefono, "3185920708");
             assert_eq!(user_updation.identificacion, "1014739191");
             assert_eq!(user_updation.nombre_tipo_identificacion, "CC");
             assert_eq!(user_id, "5f405541-d1df-454a-b2fc-56004ba380cc");
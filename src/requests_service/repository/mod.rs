@@ -1,22 +1,23 @@
 use async_trait::async_trait;
 
 pub mod err;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod lib_sql_implementation;
 
 use super::domain::{RequestForApproval, RequestForApprovalDb};
+use crate::pagination::{ListParams, Page};
 use err::Result;
 
 #[async_trait]
 pub trait RequestRepository: Send + Sync {
-    async fn get_commands_by_name(&self, command_name: &str) -> Result<Vec<RequestForApproval>>;
-
     async fn get_commands_by_id(&self, command_id: &str) -> Result<RequestForApproval>;
 
     async fn create_command(&self, request: RequestForApprovalDb) -> Result<()>;
 
     async fn delete_request(&self, request_id: &str) -> Result<()>;
 
-    async fn get_all_commands(&self) -> Result<Vec<RequestForApproval>>;
+    async fn list_requests(&self, params: &ListParams) -> Result<Page<RequestForApproval>>;
 
     async fn aprove_request(&self, request_id: &str, approver_id: &str) -> Result<()>;
 }
@@ -2,44 +2,38 @@ use std::sync::Arc;
 
 use uuid::Uuid;
 
+use crate::database::UnitOfWork;
+use crate::pagination::{ListParams, Page};
+
 use super::{
-    domain::{
-        CommandExecutor, RequestContent, RequestCreation, RequestForApproval, RequestForApprovalDb,
-    },
+    domain::{CommandExecutor, RequestContent, RequestForApproval, RequestForApprovalDb},
     repository::RequestRepository,
 };
 
-use super::err::Result;
+use super::err::{RequestServiceError, Result};
 
 #[derive(Clone)]
 pub struct RequestService {
     command_executor: CommandExecutor,
     request_repository: Arc<dyn RequestRepository>,
+    unit_of_work: UnitOfWork,
 }
 
 impl RequestService {
     pub fn new(
         command_executor: CommandExecutor,
         request_repository: Arc<dyn RequestRepository>,
+        unit_of_work: UnitOfWork,
     ) -> Self {
         Self {
             command_executor,
             request_repository,
+            unit_of_work,
         }
     }
 
-    pub async fn get_requests_by_name(
-        &self,
-        request_name: String,
-    ) -> Result<Vec<RequestForApproval>> {
-        Ok(self
-            .request_repository
-            .get_commands_by_name(&request_name)
-            .await?)
-    }
-
-    pub async fn get_all_requests(&self) -> Result<Vec<RequestForApproval>> {
-        Ok(self.request_repository.get_all_commands().await?)
+    pub async fn list_requests(&self, params: &ListParams) -> Result<Page<RequestForApproval>> {
+        Ok(self.request_repository.list_requests(params).await?)
     }
 
     pub async fn delete_request(&self, request_id: String) -> Result<()> {
@@ -55,17 +49,28 @@ impl RequestService {
             .await?)
     }
 
+    /// Runs the command and marks the request approved, neither happens without the other.
+    /// A request is executed once, the status is read inside the unit of work so two
+    /// approvals at the same time can not both run the command.
     pub async fn execute_request(&self, request_id: String, aprover_id: &str) -> Result<()> {
-        let request = self.get_request_by_id(request_id).await?;
-        self.command_executor
-            .execute_command(request.command_content)
-            .await?;
-
-        self.request_repository
-            .aprove_request(&request.request_id, aprover_id)
-            .await?;
-
-        Ok(())
+        self.unit_of_work
+            .run(async {
+                let request = self.get_request_by_id(request_id).await?;
+                if request.completed {
+                    return Err(RequestServiceError::AlreadyCompleted(request.request_id));
+                }
+
+                self.command_executor
+                    .execute_command(request.command_content)
+                    .await?;
+
+                self.request_repository
+                    .aprove_request(&request.request_id, aprover_id)
+                    .await?;
+
+                Ok(())
+            })
+            .await
     }
 
     pub async fn create_request(
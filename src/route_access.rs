@@ -0,0 +1,464 @@
+use std::{fmt::Display, sync::Arc};
+
+use axum::{
+    handler::Handler,
+    http::{HeaderName, HeaderValue, Method},
+    middleware,
+    response::Response as HttpResponse,
+    routing::{delete, get, post, put, MethodRouter},
+    Router,
+};
+use tracing::{info, warn};
+use utoipa::openapi::{
+    path::Operation,
+    response::{Response, ResponseBuilder},
+    security::SecurityRequirement,
+    Content, OpenApi, Ref,
+};
+
+use crate::{
+    auth_middleware::{auth_middleware, role_middleware, AuthState, RoleGuard},
+    openapi::{BEARER_SCHEME, COOKIE_SCHEME, CSRF_SCHEME},
+    session_service::repository::SessionRepository,
+    user_service::domain::UserRol,
+};
+
+/// When the routes without a version were deprecated, as an RFC 9745 `Deprecation` date.
+const LEGACY_DEPRECATION: &str = "@1792195200";
+/// When the routes without a version stop answering, as an RFC 8594 `Sunset` date.
+const LEGACY_SUNSET: &str = "Wed, 30 Jun 2027 23:59:59 GMT";
+
+/// Who is allowed to call a route.
+#[derive(Debug, Clone)]
+pub enum Access {
+    Public,
+    Authenticated,
+    Roles(Vec<UserRol>),
+}
+
+impl Access {
+    pub fn admin() -> Self {
+        Access::Roles(vec![UserRol::Admin])
+    }
+
+    pub fn staff() -> Self {
+        Access::Roles(vec![UserRol::Admin, UserRol::Entrenador])
+    }
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Access::Public => write!(f, "public"),
+            Access::Authenticated => write!(f, "authenticated"),
+            Access::Roles(roles) => {
+                let roles: Vec<String> = roles.iter().map(|role| role.to_string()).collect();
+                write!(f, "roles [{}]", roles.join(", "))
+            }
+        }
+    }
+}
+
+/// One row of the route access table of an `HttpService`.
+pub struct RouteAccess<S> {
+    method: Method,
+    path: &'static str,
+    access: Access,
+    method_router: MethodRouter<S>,
+}
+
+impl<S> RouteAccess<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    pub fn get<H, T>(path: &'static str, access: Access, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::GET, path, access, get(handler))
+    }
+
+    pub fn post<H, T>(path: &'static str, access: Access, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::POST, path, access, post(handler))
+    }
+
+    pub fn put<H, T>(path: &'static str, access: Access, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::PUT, path, access, put(handler))
+    }
+
+    pub fn delete<H, T>(path: &'static str, access: Access, handler: H) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        Self::new(Method::DELETE, path, access, delete(handler))
+    }
+
+    fn new(
+        method: Method,
+        path: &'static str,
+        access: Access,
+        method_router: MethodRouter<S>,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            access,
+            method_router,
+        }
+    }
+}
+
+/// Everything needed to enforce an [`Access`] level on a route.
+#[derive(Clone)]
+pub struct AccessControl {
+    auth_state: AuthState,
+}
+
+impl AccessControl {
+    pub fn new(token_key: String, session_repository: Arc<dyn SessionRepository>) -> Self {
+        Self {
+            auth_state: AuthState::new(token_key, session_repository),
+        }
+    }
+
+    /// Builds a router from the route access table, every route gets the layers of its
+    /// access level, so there is no way to add a route without deciding who can call it.
+    pub fn build_router<S>(&self, service_name: &str, routes: Vec<RouteAccess<S>>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let mut router = Router::new();
+
+        for route in routes {
+            info!(
+                "[{service_name}] {} {} -> {}",
+                route.method, route.path, route.access
+            );
+
+            let method_router = self.protect(route.method_router, &route.access);
+            router = router.route(route.path, method_router);
+        }
+
+        router
+    }
+
+    /// Builds the router of the paths from before `/api/v1`, kept for the clients
+    /// that have not migrated yet. Their answers carry the `Deprecation` and `Sunset` headers
+    /// and they are left out of the OpenAPI document.
+    pub fn build_legacy_router<S>(
+        &self,
+        service_name: &str,
+        routes: Vec<RouteAccess<S>>,
+    ) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let mut router = Router::new();
+
+        for route in routes {
+            info!(
+                "[{service_name}] {} {} -> {} (deprecated)",
+                route.method, route.path, route.access
+            );
+
+            let method_router = self
+                .protect(route.method_router, &route.access)
+                .layer(middleware::map_response(add_deprecation_headers));
+            router = router.route(route.path, method_router);
+        }
+
+        router
+    }
+
+    fn protect<S>(&self, method_router: MethodRouter<S>, access: &Access) -> MethodRouter<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let auth_layer = middleware::from_fn_with_state(self.auth_state.clone(), auth_middleware);
+
+        match access {
+            Access::Public => method_router,
+            Access::Authenticated => method_router.route_layer(auth_layer),
+            Access::Roles(roles) => {
+                let role_guard = RoleGuard::new(roles);
+
+                // The last layer added runs first, so auth has to be added after the role check
+                method_router
+                    .route_layer(middleware::from_fn_with_state(role_guard, role_middleware))
+                    .route_layer(auth_layer)
+            }
+        }
+    }
+}
+
+async fn add_deprecation_headers(mut response: HttpResponse) -> HttpResponse {
+    let headers = response.headers_mut();
+    headers.insert(
+        HeaderName::from_static("deprecation"),
+        HeaderValue::from_static(LEGACY_DEPRECATION),
+    );
+    headers.insert(
+        HeaderName::from_static("sunset"),
+        HeaderValue::from_static(LEGACY_SUNSET),
+    );
+
+    response
+}
+
+/// Adds to the operations of the document who can call them, taken from the route access
+/// table so the document cannot disagree with the layers the routes really get.
+pub fn document_routes<S>(mut openapi: OpenApi, routes: &[RouteAccess<S>]) -> OpenApi {
+    for route in routes {
+        let Some(operation) = operation_mut(&mut openapi, &route.method, route.path) else {
+            if cfg!(debug_assertions) {
+                panic!("{} {} has no #[utoipa::path]", route.method, route.path);
+            }
+            warn!(
+                "{} {} is missing in the OpenAPI document",
+                route.method, route.path
+            );
+            continue;
+        };
+
+        document_access(operation, &route.method, &route.access);
+    }
+
+    openapi
+}
+
+fn operation_mut<'a>(
+    openapi: &'a mut OpenApi,
+    method: &Method,
+    path: &str,
+) -> Option<&'a mut Operation> {
+    let path_item = openapi.paths.paths.get_mut(path)?;
+
+    match *method {
+        Method::GET => path_item.get.as_mut(),
+        Method::POST => path_item.post.as_mut(),
+        Method::PUT => path_item.put.as_mut(),
+        Method::DELETE => path_item.delete.as_mut(),
+        _ => None,
+    }
+}
+
+fn document_access(operation: &mut Operation, method: &Method, access: &Access) {
+    let access_note = format!("Access: {access}");
+    operation.description = Some(match operation.description.take() {
+        Some(description) => format!("{description}\n\n{access_note}"),
+        None => access_note,
+    });
+
+    if let Access::Public = access {
+        return;
+    }
+
+    // The cookie only authenticates the state changing requests that also send the CSRF token
+    let mut cookie_requirement = SecurityRequirement::new(COOKIE_SCHEME, Vec::<String>::new());
+    if !matches!(*method, Method::GET) {
+        cookie_requirement = cookie_requirement.add(CSRF_SCHEME, Vec::<String>::new());
+    }
+    operation.security = Some(vec![
+        SecurityRequirement::new(BEARER_SCHEME, Vec::<String>::new()),
+        cookie_requirement,
+    ]);
+
+    let responses = &mut operation.responses.responses;
+    responses.entry("401".to_string()).or_insert_with(|| {
+        error_response(
+            "`missing_token`, `invalid_token`, or `invalid_session` when the session was revoked",
+        )
+        .into()
+    });
+
+    let forbidden = match access {
+        Access::Roles(_) => "`role_not_allowed`, or `mfa_required` when the session did not pass the MFA policy of the role, or `invalid_csrf_token`",
+        _ if matches!(*method, Method::GET) => return,
+        _ => "`invalid_csrf_token`, authenticated with the cookie but without a valid CSRF token",
+    };
+    responses
+        .entry("403".to_string())
+        .or_insert_with(|| error_response(forbidden).into());
+}
+
+fn error_response(description: &str) -> Response {
+    ResponseBuilder::new()
+        .description(description)
+        .content(
+            "application/json",
+            Content::new(Some(Ref::from_schema_name("ErrorBody"))),
+        )
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, http::Request, http::StatusCode};
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::{
+        auth_middleware::{ACCESS_TOKEN_COOKIE, CSRF_COOKIE, CSRF_HEADER},
+        session_service::repository::MockSessionRepository,
+        user_service::token_provider::TokenProvider,
+    };
+
+    const TOKEN_KEY: &str = "gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=";
+    const REVOKED_SESSION: &str = "revoked-session";
+
+    fn router() -> Router {
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_is_session_revoked()
+            .returning(|session_id| Ok(session_id == REVOKED_SESSION));
+
+        let access_control = AccessControl::new(TOKEN_KEY.to_string(), Arc::new(mock_repo));
+
+        let routes = vec![
+            RouteAccess::get("/public", Access::Public, || async { "public" }),
+            RouteAccess::get("/authenticated", Access::Authenticated, || async {
+                "authenticated"
+            }),
+            RouteAccess::get("/admin", Access::admin(), || async { "admin" }),
+            RouteAccess::post("/authenticated", Access::Authenticated, || async {
+                "authenticated"
+            }),
+        ];
+
+        access_control.build_router("test", routes)
+    }
+
+    fn token(user_rol: UserRol, session_id: &str, mfa: bool) -> String {
+        TokenProvider::new(TOKEN_KEY.to_string())
+            .generate_token(
+                "5f405541-d1df-454a-b2fc-56004ba380cc".to_string(),
+                user_rol,
+                session_id.to_string(),
+                mfa,
+            )
+            .unwrap()
+    }
+
+    async fn call(uri: &str, session: Option<(UserRol, &str)>) -> StatusCode {
+        call_with_token(
+            uri,
+            session.map(|(user_rol, session_id)| token(user_rol, session_id, true)),
+        )
+        .await
+    }
+
+    async fn call_with_token(uri: &str, token: Option<String>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+
+        if let Some(token) = token {
+            request = request.header("Authorization", format!("Bearer {token}"));
+        }
+
+        router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_public_route_without_token() {
+        let status = call("/public", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_route_without_token() {
+        let status = call("/authenticated", None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_role_route_without_token() {
+        let status = call("/admin", None).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_role_route_with_wrong_rol() {
+        let status = call("/admin", Some((UserRol::Entrenador, "session"))).await;
+
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_role_route_with_allowed_rol() {
+        let status = call("/admin", Some((UserRol::Admin, "session"))).await;
+
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_token() {
+        let status = call("/authenticated", Some((UserRol::Admin, REVOKED_SESSION))).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_role_route_without_required_mfa() {
+        let token = token(UserRol::Admin, "session", false);
+
+        assert_eq!(
+            call_with_token("/admin", Some(token.clone())).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call_with_token("/authenticated", Some(token)).await,
+            StatusCode::OK
+        );
+    }
+
+    async fn call_with_cookie(method: &str, csrf_header: Option<&str>) -> StatusCode {
+        let token = token(UserRol::Usuario, "session", true);
+        let mut request = Request::builder()
+            .method(method)
+            .uri("/authenticated")
+            .header(
+                "Cookie",
+                format!("{ACCESS_TOKEN_COOKIE}={token}; {CSRF_COOKIE}=csrf-secret"),
+            );
+
+        if let Some(csrf_header) = csrf_header {
+            request = request.header(CSRF_HEADER, csrf_header);
+        }
+
+        router()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_cookie_auth_requires_csrf_for_state_changes() {
+        assert_eq!(call_with_cookie("GET", None).await, StatusCode::OK);
+        assert_eq!(call_with_cookie("POST", None).await, StatusCode::FORBIDDEN);
+        assert_eq!(
+            call_with_cookie("POST", Some("other-secret")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            call_with_cookie("POST", Some("csrf-secret")).await,
+            StatusCode::OK
+        );
+    }
+}
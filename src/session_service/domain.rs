@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::user_service::domain::UserRol;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+pub struct TokenPair {
+    pub access_token: String,
+    /// Left out of the body in cookie mode, it is only sent in an HttpOnly cookie
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+}
+
+/// Session as it is shown to its owner, without the refresh token hash.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Session {
+    pub session_id: String,
+    pub created_at: i64,
+    pub last_used_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionDb {
+    pub session_id: String,
+    pub id_persona: String,
+    pub nombre_rol: UserRol,
+    pub refresh_token_hash: String,
+    pub created_at: i64,
+    pub last_used_at: i64,
+    pub expires_at: i64,
+    pub revoked: bool,
+    /// The session was opened passing the MFA policy of the role
+    pub mfa_verified: bool,
+}
@@ -0,0 +1,478 @@
+use std::sync::Arc;
+
+use crate::extract::{Json, Path, Query};
+use axum::{
+    extract::{rejection::JsonRejection, State},
+    http::{HeaderMap, StatusCode},
+    Extension,
+};
+use axum_extra::extract::cookie::CookieJar;
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    api_error::{ApiError, ErrorBody},
+    auth_middleware::{
+        add_auth_cookies, has_valid_csrf_token, remove_auth_cookies, AuthContext, AuthMode,
+        REFRESH_TOKEN_COOKIE,
+    },
+    global_traits::HttpService,
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
+    user_service::token_provider::TokenProvider,
+};
+
+use super::{
+    domain::{Session, TokenPair},
+    err::SessionServiceError,
+    repository::SessionRepository,
+    use_cases::SessionService,
+};
+
+pub struct SessionHttpServer {
+    session_service: SessionService,
+    access_control: AccessControl,
+}
+
+impl SessionHttpServer {
+    pub async fn new(
+        session_repository: Arc<dyn SessionRepository>,
+        token_key: String,
+        access_control: AccessControl,
+    ) -> Self {
+        let session_service =
+            SessionService::new(session_repository, TokenProvider::new(token_key));
+        Self {
+            session_service,
+            access_control,
+        }
+    }
+}
+
+impl HttpService for SessionHttpServer {
+    fn get_router(&self) -> axum::Router {
+        self.access_control
+            .build_router("session", routes())
+            .with_state(self.session_service.clone())
+    }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(SessionApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<SessionService>> {
+    vec![
+        RouteAccess::post("/api/v1/token/refresh", Access::Public, refresh_token),
+        RouteAccess::post("/api/v1/token/logout", Access::Public, log_out),
+        RouteAccess::get(
+            "/api/v1/users/me/sessions",
+            Access::Authenticated,
+            get_sessions,
+        ),
+        RouteAccess::delete(
+            "/api/v1/users/me/sessions/{session_id}",
+            Access::Authenticated,
+            revoke_session,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(refresh_token, log_out, get_sessions, revoke_session),
+    tags((name = "session", description = "Refresh tokens and the sessions they belong to"))
+)]
+struct SessionApi;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct RefreshInfo {
+    /// Left out by the web frontend, the refresh token cookie is read instead
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+/// A refresh token sent in the body or, in cookie mode, in its cookie.
+struct RequestRefreshToken {
+    refresh_token: String,
+    from_cookie: bool,
+}
+
+/// The browser sends the refresh token cookie on its own, so reading it needs the same
+/// CSRF check as the cookie authenticated routes.
+fn request_refresh_token(
+    jar: &CookieJar,
+    headers: &HeaderMap,
+    payload: Result<axum::Json<RefreshInfo>, JsonRejection>,
+) -> Result<RequestRefreshToken, ApiError> {
+    let body_token = match payload {
+        Ok(axum::Json(payload)) => payload.refresh_token,
+        Err(JsonRejection::MissingJsonContentType(_)) => None,
+        Err(rejection) => return Err(ApiError::from(rejection)),
+    };
+
+    if let Some(refresh_token) = body_token {
+        return Ok(RequestRefreshToken {
+            refresh_token,
+            from_cookie: false,
+        });
+    }
+
+    let Some(cookie) = jar.get(REFRESH_TOKEN_COOKIE) else {
+        return Err(ApiError::unauthorized(
+            "missing_token",
+            "No refresh token was sent",
+        ));
+    };
+
+    if !has_valid_csrf_token(jar, headers) {
+        return Err(ApiError::forbidden(
+            "invalid_csrf_token",
+            "The CSRF header does not match the CSRF cookie",
+        ));
+    }
+
+    Ok(RequestRefreshToken {
+        refresh_token: cookie.value().to_string(),
+        from_cookie: true,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/token/refresh",
+    tag = "session",
+    params(AuthMode),
+    request_body = RefreshInfo,
+    responses(
+        (status = 200, description = "New token pair, the refresh token given is spent. In cookie mode the refresh token is only in its cookie", body = TokenPair),
+        (status = 401, description = "`missing_token`, or `invalid_session` when the refresh token is invalid, expired, revoked or was already used", body = ErrorBody),
+        (status = 403, description = "`invalid_csrf_token`, the refresh token cookie was sent without a valid CSRF token", body = ErrorBody),
+    )
+)]
+async fn refresh_token(
+    State(service): State<SessionService>,
+    Query(auth_mode): Query<AuthMode>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    payload: Result<axum::Json<RefreshInfo>, JsonRejection>,
+) -> Result<(CookieJar, Json<TokenPair>), ApiError> {
+    let request_token = request_refresh_token(&jar, &headers, payload)?;
+
+    let mut token_pair = service
+        .refresh_session(&request_token.refresh_token)
+        .await
+        .inspect_err(|err| error!("Refresh token rejected: {err}"))?;
+
+    let jar = match auth_mode.cookie || request_token.from_cookie {
+        true => add_auth_cookies(jar, &mut token_pair),
+        false => jar,
+    };
+
+    Ok((jar, Json(token_pair)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/token/logout",
+    tag = "session",
+    request_body = RefreshInfo,
+    responses(
+        (status = 200, description = "The session was ended and the auth cookies removed"),
+        (status = 401, description = "`missing_token`, or `invalid_session` when the refresh token is not valid", body = ErrorBody),
+        (status = 403, description = "`invalid_csrf_token`, the refresh token cookie was sent without a valid CSRF token", body = ErrorBody),
+    )
+)]
+async fn log_out(
+    State(service): State<SessionService>,
+    jar: CookieJar,
+    headers: HeaderMap,
+    payload: Result<axum::Json<RefreshInfo>, JsonRejection>,
+) -> (CookieJar, Result<StatusCode, ApiError>) {
+    let result = match request_refresh_token(&jar, &headers, payload) {
+        Ok(request_token) => service
+            .end_session(&request_token.refresh_token)
+            .await
+            .inspect_err(|err| error!("Log out with an invalid refresh token: {err}"))
+            .map(|_| StatusCode::OK)
+            .map_err(ApiError::from),
+        Err(err) => Err(err),
+    };
+
+    (remove_auth_cookies(jar), result)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/sessions",
+    tag = "session",
+    responses((status = 200, description = "Active sessions of the caller", body = Vec<Session>))
+)]
+async fn get_sessions(
+    State(service): State<SessionService>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<Session>>, SessionServiceError> {
+    Ok(Json(
+        service.get_active_sessions(&auth_context.user_id).await?,
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/me/sessions/{session_id}",
+    tag = "session",
+    params(("session_id" = String, Path)),
+    responses(
+        (status = 200, description = "The session was revoked"),
+        (status = 404, description = "`session_not_found`, the caller has no such session", body = ErrorBody),
+    )
+)]
+async fn revoke_session(
+    State(service): State<SessionService>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, SessionServiceError> {
+    service
+        .revoke_session(&auth_context.user_id, &session_id)
+        .await
+        .map_err(|err| match err {
+            err if err.is_invalid_session() => SessionServiceError::SessionNotFound,
+            err => err,
+        })?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use axum::{
+        body::Body,
+        http::{header, Method, Request},
+    };
+
+    use super::*;
+    use crate::{
+        auth_middleware::{CSRF_COOKIE, CSRF_HEADER},
+        test_harness::{TestApp, TestResponse, TEST_PASSWORD},
+        user_service::domain::UserRol,
+    };
+
+    #[tokio::test]
+    async fn test_refresh_token() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let response = app
+            .post(
+                "/api/v1/token/refresh",
+                None,
+                json!({ "refresh_token": user.refresh_token }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let access_token = response.body["access_token"].as_str().unwrap();
+        assert_eq!(
+            app.get("/api/v1/users/me", Some(access_token)).await.status,
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            app.post(
+                "/api/v1/token/refresh",
+                None,
+                json!({ "refresh_token": "not-a-token" })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/token/refresh", None, json!({}))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    fn response_cookies(response: &TestResponse) -> Vec<String> {
+        response
+            .headers
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .map(|value| value.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    fn cookie_value(set_cookies: &[String], name: &str) -> String {
+        set_cookies
+            .iter()
+            .find_map(|cookie| cookie.strip_prefix(&format!("{name}=")))
+            .and_then(|cookie| cookie.split(';').next())
+            .unwrap()
+            .to_string()
+    }
+
+    fn cookie_request(
+        uri: &str,
+        refresh_token: &str,
+        csrf: &str,
+        csrf_header: &str,
+    ) -> Request<Body> {
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header(
+                header::COOKIE,
+                format!("{REFRESH_TOKEN_COOKIE}={refresh_token}; {CSRF_COOKIE}={csrf}"),
+            )
+            .header(CSRF_HEADER, csrf_header)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_in_cookie_mode() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let response = app
+            .post(
+                "/api/v1/auth/login?cookie=true",
+                None,
+                json!({ "identificacion": user.correo, "contrasena": TEST_PASSWORD }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.get("refresh_token").is_none());
+
+        let set_cookies = response_cookies(&response);
+        let refresh_cookie = set_cookies
+            .iter()
+            .find(|cookie| cookie.starts_with(&format!("{REFRESH_TOKEN_COOKIE}=")))
+            .unwrap();
+        for attribute in [
+            "HttpOnly",
+            "Secure",
+            "SameSite=Strict",
+            "Path=/api/v1/token",
+        ] {
+            assert!(refresh_cookie.contains(attribute), "{refresh_cookie}");
+        }
+        let refresh_token = cookie_value(&set_cookies, REFRESH_TOKEN_COOKIE);
+        let csrf = cookie_value(&set_cookies, CSRF_COOKIE);
+
+        let response = app
+            .send(cookie_request(
+                "/api/v1/token/refresh",
+                &refresh_token,
+                &csrf,
+                "other",
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+        let response = app
+            .send(cookie_request(
+                "/api/v1/token/refresh",
+                &refresh_token,
+                &csrf,
+                &csrf,
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.body.get("refresh_token").is_none());
+        let set_cookies = response_cookies(&response);
+        let new_refresh_token = cookie_value(&set_cookies, REFRESH_TOKEN_COOKIE);
+        let csrf = cookie_value(&set_cookies, CSRF_COOKIE);
+        assert_ne!(new_refresh_token, refresh_token);
+
+        let response = app
+            .send(cookie_request(
+                "/api/v1/token/logout",
+                &new_refresh_token,
+                &csrf,
+                &csrf,
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            app.post(
+                "/api/v1/token/refresh",
+                None,
+                json!({ "refresh_token": new_refresh_token })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_out() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let log_out = json!({ "refresh_token": user.refresh_token });
+        assert_eq!(
+            app.post("/api/v1/token/logout", None, log_out.clone())
+                .await
+                .status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.get("/api/v1/users/me", Some(&user.token)).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/token/logout", None, log_out).await.status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/token/logout",
+                None,
+                json!({ "refresh_token": "not-a-token" })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_and_revoke_sessions() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let other = app.create_user("otro", UserRol::Usuario).await;
+
+        let response = app.get("/api/v1/users/me/sessions", None).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body["code"], "missing_token");
+        let response = app
+            .get("/api/v1/users/me/sessions", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body.as_array().unwrap().len(), 1);
+        let session_id = response.body[0]["session_id"].as_str().unwrap();
+
+        let uri = format!("/api/v1/users/me/sessions/{session_id}");
+        assert_eq!(
+            app.delete(&uri, None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        let response = app.delete(&uri, Some(&other.token)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "session_not_found");
+        assert_eq!(
+            app.delete(&uri, Some(&user.token)).await.status,
+            StatusCode::OK
+        );
+        let response = app
+            .get("/api/v1/users/me/sessions", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body["code"], "invalid_session");
+    }
+}
@@ -0,0 +1,58 @@
+use axum::response::{IntoResponse, Response};
+use thiserror::Error;
+
+use crate::{api_error::ApiError, user_service::token_provider::TokenProviderError};
+
+use super::repository::err::SessionRepositoryError;
+
+pub type Result<T> = std::result::Result<T, SessionServiceError>;
+
+#[derive(Error, Debug)]
+pub enum SessionServiceError {
+    #[error("Session repository error: {0}")]
+    SessionRepositoryError(#[from] SessionRepositoryError),
+    #[error("Token provider error: {0}")]
+    TokenProviderError(#[from] TokenProviderError),
+    #[error("The refresh token is malformed")]
+    InvalidRefreshToken,
+    #[error("The session was revoked")]
+    SessionRevoked,
+    #[error("The session expired")]
+    SessionExpired,
+    #[error("Refresh token reuse detected, session {0} was revoked")]
+    RefreshTokenReuse(String),
+    #[error("Session not found for the user")]
+    SessionNotFound,
+}
+
+impl SessionServiceError {
+    /// The refresh token can not be used anymore, or never could.
+    pub fn is_invalid_session(&self) -> bool {
+        matches!(
+            self,
+            SessionServiceError::InvalidRefreshToken
+                | SessionServiceError::SessionRevoked
+                | SessionServiceError::SessionExpired
+                | SessionServiceError::RefreshTokenReuse(_)
+                | SessionServiceError::SessionRepositoryError(
+                    SessionRepositoryError::SessionNotFound
+                )
+        )
+    }
+}
+
+impl From<SessionServiceError> for ApiError {
+    fn from(err: SessionServiceError) -> Self {
+        match &err {
+            SessionServiceError::SessionNotFound => ApiError::not_found("session_not_found", &err),
+            err if err.is_invalid_session() => ApiError::unauthorized("invalid_session", err),
+            _ => ApiError::internal(&err),
+        }
+    }
+}
+
+impl IntoResponse for SessionServiceError {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
@@ -0,0 +1,26 @@
+use std::result;
+
+use thiserror::Error;
+
+use crate::database::from_libsql_error;
+
+pub type Result<T> = result::Result<T, SessionRepositoryError>;
+
+#[derive(Error, Debug)]
+pub enum SessionRepositoryError {
+    #[error("Database connection error: {0}")]
+    ConnectionError(libsql::Error),
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
+    #[error("Error deserializing into a struct from the database: {0}")]
+    DeserializationError(#[from] serde::de::value::Error),
+    #[error("Session not found")]
+    SessionNotFound,
+}
+
+from_libsql_error!(
+    SessionRepositoryError,
+    SessionRepositoryError::ConnectionError
+);
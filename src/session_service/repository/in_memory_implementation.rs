@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+
+use crate::{
+    in_memory::InMemoryStore,
+    session_service::domain::{Session, SessionDb},
+};
+
+use super::{
+    err::{Result, SessionRepositoryError},
+    SessionRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemorySessionRepository {
+    store: InMemoryStore,
+}
+
+impl InMemorySessionRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl SessionRepository for InMemorySessionRepository {
+    async fn create_session(&self, session: SessionDb) -> Result<()> {
+        self.store
+            .tables()
+            .session
+            .insert(session.session_id.clone(), session);
+
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<SessionDb> {
+        self.store
+            .tables()
+            .session
+            .get(session_id)
+            .cloned()
+            .ok_or(SessionRepositoryError::SessionNotFound)
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        new_hash: &str,
+        last_used_at: i64,
+        expires_at: i64,
+    ) -> Result<bool> {
+        let mut tables = self.store.tables();
+
+        match tables.session.get_mut(session_id) {
+            Some(session) if !session.revoked && session.refresh_token_hash == old_hash => {
+                session.refresh_token_hash = new_hash.to_string();
+                session.last_used_at = last_used_at;
+                session.expires_at = expires_at;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn revoke_session(&self, session_id: &str) -> Result<()> {
+        if let Some(session) = self.store.tables().session.get_mut(session_id) {
+            session.revoked = true;
+        }
+
+        Ok(())
+    }
+
+    async fn revoke_user_sessions(&self, user_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .session
+            .values_mut()
+            .filter(|session| session.id_persona == user_id)
+            .for_each(|session| session.revoked = true);
+
+        Ok(())
+    }
+
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool> {
+        Ok(self
+            .store
+            .tables()
+            .session
+            .get(session_id)
+            .is_none_or(|session| session.revoked))
+    }
+
+    async fn get_active_sessions(&self, user_id: &str, now: i64) -> Result<Vec<Session>> {
+        let mut sessions: Vec<Session> = self
+            .store
+            .tables()
+            .session
+            .values()
+            .filter(|session| {
+                session.id_persona == user_id && !session.revoked && session.expires_at > now
+            })
+            .map(|session| Session {
+                session_id: session.session_id.clone(),
+                created_at: session.created_at,
+                last_used_at: session.last_used_at,
+                expires_at: session.expires_at,
+            })
+            .collect();
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_used_at));
+
+        Ok(sessions)
+    }
+}
@@ -0,0 +1,153 @@
+use crate::database::{DatabaseHandle, PooledConnection};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use libsql::{de, params};
+
+use crate::session_service::domain::{Session, SessionDb};
+
+use super::{
+    err::{Result, SessionRepositoryError},
+    SessionRepository,
+};
+
+#[derive(Clone)]
+pub struct LibSqlSessionRepository {
+    db: Arc<DatabaseHandle>,
+}
+
+impl LibSqlSessionRepository {
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
+    }
+
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        Ok(self.db.connect().await?)
+    }
+}
+
+#[async_trait]
+impl SessionRepository for LibSqlSessionRepository {
+    async fn create_session(&self, session: SessionDb) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "INSERT INTO session
+            (session_id, id_persona, nombre_rol, refresh_token_hash, created_at, last_used_at, expires_at, revoked, mfa_verified)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session.session_id,
+                session.id_persona,
+                session.nombre_rol.to_string(),
+                session.refresh_token_hash,
+                session.created_at,
+                session.last_used_at,
+                session.expires_at,
+                session.revoked,
+                session.mfa_verified
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_session(&self, session_id: &str) -> Result<SessionDb> {
+        let conn = self.get_connection().await?;
+
+        let mut rows = conn
+            .read(
+                "SELECT session_id, id_persona, nombre_rol, refresh_token_hash, created_at, last_used_at, expires_at, revoked, mfa_verified
+                FROM session WHERE session_id = ?1",
+                params![session_id],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(de::from_row(&row)?),
+            None => Err(SessionRepositoryError::SessionNotFound),
+        }
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        new_hash: &str,
+        last_used_at: i64,
+        expires_at: i64,
+    ) -> Result<bool> {
+        let conn = self.get_connection().await?;
+
+        let updated_rows = conn
+            .execute(
+                "UPDATE session SET refresh_token_hash = ?1, last_used_at = ?2, expires_at = ?3
+                WHERE session_id = ?4 AND refresh_token_hash = ?5 AND revoked = 0",
+                params![new_hash, last_used_at, expires_at, session_id, old_hash],
+            )
+            .await?;
+
+        Ok(updated_rows == 1)
+    }
+
+    async fn revoke_session(&self, session_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "UPDATE session SET revoked = 1 WHERE session_id = ?1",
+            params![session_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_user_sessions(&self, user_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "UPDATE session SET revoked = 1 WHERE id_persona = ?1",
+            params![user_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool> {
+        let conn = self.get_connection().await?;
+
+        let mut rows = conn
+            .read(
+                "SELECT revoked FROM session WHERE session_id = ?1",
+                params![session_id],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<bool>(0)?),
+            None => Ok(true),
+        }
+    }
+
+    async fn get_active_sessions(&self, user_id: &str, now: i64) -> Result<Vec<Session>> {
+        let conn = self.get_connection().await?;
+
+        let mut rows = conn
+            .read(
+                "SELECT session_id, created_at, last_used_at, expires_at FROM session
+                WHERE id_persona = ?1 AND revoked = 0 AND expires_at > ?2
+                ORDER BY last_used_at DESC",
+                params![user_id, now],
+            )
+            .await?;
+
+        let mut sessions = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            sessions.push(de::from_row(&row)?);
+        }
+
+        Ok(sessions)
+    }
+}
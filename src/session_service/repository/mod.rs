@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+use mockall::automock;
+
+pub mod err;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
+pub mod lib_sql_implementation;
+
+use super::domain::{Session, SessionDb};
+use err::Result;
+
+#[automock]
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    async fn create_session(&self, session: SessionDb) -> Result<()>;
+
+    async fn get_session(&self, session_id: &str) -> Result<SessionDb>;
+
+    /// Replaces the refresh token hash only if the stored one is still `old_hash`,
+    /// returns false when another refresh already rotated it.
+    async fn rotate_refresh_token(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        new_hash: &str,
+        last_used_at: i64,
+        expires_at: i64,
+    ) -> Result<bool>;
+
+    async fn revoke_session(&self, session_id: &str) -> Result<()>;
+
+    async fn revoke_user_sessions(&self, user_id: &str) -> Result<()>;
+
+    /// Revocation list check for access tokens, a session that does not exist counts as revoked.
+    async fn is_session_revoked(&self, session_id: &str) -> Result<bool>;
+
+    async fn get_active_sessions(&self, user_id: &str, now: i64) -> Result<Vec<Session>>;
+}
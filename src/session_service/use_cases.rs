@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::user_service::{
+    domain::UserRol,
+    token_provider::{RefreshToken, TokenProvider, REFRESH_TOKEN_DURATION_DAYS},
+};
+
+use super::domain::{Session, SessionDb, TokenPair};
+use super::err::{Result, SessionServiceError};
+use super::repository::SessionRepository;
+
+#[derive(Clone)]
+pub struct SessionService {
+    session_repository: Arc<dyn SessionRepository>,
+    token_provider: TokenProvider,
+}
+
+impl SessionService {
+    pub fn new(
+        session_repository: Arc<dyn SessionRepository>,
+        token_provider: TokenProvider,
+    ) -> Self {
+        Self {
+            session_repository,
+            token_provider,
+        }
+    }
+
+    pub async fn start_session(
+        &self,
+        user_id: String,
+        nombre_rol: UserRol,
+        mfa_verified: bool,
+    ) -> Result<TokenPair> {
+        let now = Utc::now().timestamp();
+        let refresh_token = RefreshToken::new(Uuid::new_v4().to_string());
+
+        let session = SessionDb {
+            session_id: refresh_token.session_id.clone(),
+            id_persona: user_id.clone(),
+            nombre_rol: nombre_rol.clone(),
+            refresh_token_hash: refresh_token.hash(),
+            created_at: now,
+            last_used_at: now,
+            expires_at: Self::refresh_expiration(now),
+            revoked: false,
+            mfa_verified,
+        };
+
+        self.session_repository.create_session(session).await?;
+
+        Ok(TokenPair {
+            access_token: self.token_provider.generate_token(
+                user_id,
+                nombre_rol,
+                refresh_token.session_id.clone(),
+                mfa_verified,
+            )?,
+            refresh_token: Some(refresh_token.to_string()),
+        })
+    }
+
+    /// Rotates the refresh token, every refresh token can be used only once, if an
+    /// already rotated token shows up again the whole session is revoked, because
+    /// either the legitimate client or an attacker holds a stolen copy.
+    pub async fn refresh_session(&self, refresh_token: &str) -> Result<TokenPair> {
+        let refresh_token =
+            RefreshToken::parse(refresh_token).ok_or(SessionServiceError::InvalidRefreshToken)?;
+
+        let session = self
+            .session_repository
+            .get_session(&refresh_token.session_id)
+            .await?;
+
+        if session.revoked {
+            return Err(SessionServiceError::SessionRevoked);
+        }
+
+        let now = Utc::now().timestamp();
+
+        if session.expires_at <= now {
+            return Err(SessionServiceError::SessionExpired);
+        }
+
+        let new_refresh_token = RefreshToken::new(session.session_id.clone());
+
+        let rotated = refresh_token.hash() == session.refresh_token_hash
+            && self
+                .session_repository
+                .rotate_refresh_token(
+                    &session.session_id,
+                    &session.refresh_token_hash,
+                    &new_refresh_token.hash(),
+                    now,
+                    Self::refresh_expiration(now),
+                )
+                .await?;
+
+        if !rotated {
+            warn!(
+                "Refresh token reuse detected for the session {}, revoking it",
+                session.session_id
+            );
+
+            self.session_repository
+                .revoke_session(&session.session_id)
+                .await?;
+
+            return Err(SessionServiceError::RefreshTokenReuse(session.session_id));
+        }
+
+        Ok(TokenPair {
+            access_token: self.token_provider.generate_token(
+                session.id_persona,
+                session.nombre_rol,
+                session.session_id,
+                session.mfa_verified,
+            )?,
+            refresh_token: Some(new_refresh_token.to_string()),
+        })
+    }
+
+    pub async fn end_session(&self, refresh_token: &str) -> Result<()> {
+        let refresh_token =
+            RefreshToken::parse(refresh_token).ok_or(SessionServiceError::InvalidRefreshToken)?;
+
+        let session = self
+            .session_repository
+            .get_session(&refresh_token.session_id)
+            .await?;
+
+        if refresh_token.hash() != session.refresh_token_hash {
+            return Err(SessionServiceError::InvalidRefreshToken);
+        }
+
+        self.session_repository
+            .revoke_session(&session.session_id)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_active_sessions(&self, user_id: &str) -> Result<Vec<Session>> {
+        Ok(self
+            .session_repository
+            .get_active_sessions(user_id, Utc::now().timestamp())
+            .await?)
+    }
+
+    pub async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        let session = self.session_repository.get_session(session_id).await?;
+
+        if session.id_persona != user_id {
+            return Err(SessionServiceError::SessionNotFound);
+        }
+
+        self.session_repository.revoke_session(session_id).await?;
+
+        Ok(())
+    }
+
+    /// Revokes every session of the user except the one making the request.
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: &str,
+        current_session_id: &str,
+    ) -> Result<()> {
+        for session in self.get_active_sessions(user_id).await? {
+            if session.session_id != current_session_id {
+                self.session_repository
+                    .revoke_session(&session.session_id)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Revokes every session of the user, the access tokens they issued stop working too.
+    pub async fn revoke_user_sessions(&self, user_id: &str) -> Result<()> {
+        self.session_repository
+            .revoke_user_sessions(user_id)
+            .await?;
+
+        Ok(())
+    }
+
+    fn refresh_expiration(now: i64) -> i64 {
+        now + Duration::days(REFRESH_TOKEN_DURATION_DAYS).num_seconds()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::session_service::repository::MockSessionRepository;
+
+    const TOKEN_KEY: &str = "gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=";
+
+    fn stored_session(refresh_token: &RefreshToken) -> SessionDb {
+        let now = Utc::now().timestamp();
+
+        SessionDb {
+            session_id: refresh_token.session_id.clone(),
+            id_persona: "5f405541-d1df-454a-b2fc-56004ba380cc".to_string(),
+            nombre_rol: UserRol::Usuario,
+            refresh_token_hash: refresh_token.hash(),
+            created_at: now,
+            last_used_at: now,
+            expires_at: now + 60,
+            revoked: false,
+            mfa_verified: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_token() {
+        let refresh_token = RefreshToken::new("session-1".to_string());
+        let session = stored_session(&refresh_token);
+
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_get_session()
+            .with(eq("session-1"))
+            .returning(move |_| Ok(session.clone()));
+        mock_repo
+            .expect_rotate_refresh_token()
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(true));
+        mock_repo.expect_revoke_session().never();
+
+        let session_service = SessionService::new(
+            Arc::new(mock_repo),
+            TokenProvider::new(TOKEN_KEY.to_string()),
+        );
+
+        let token_pair = session_service
+            .refresh_session(&refresh_token.to_string())
+            .await
+            .unwrap();
+
+        assert_ne!(token_pair.refresh_token, Some(refresh_token.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_reuse_revokes_session() {
+        let old_refresh_token = RefreshToken::new("session-1".to_string());
+        let current_refresh_token = RefreshToken::new("session-1".to_string());
+        let session = stored_session(&current_refresh_token);
+
+        let mut mock_repo = MockSessionRepository::new();
+        mock_repo
+            .expect_get_session()
+            .returning(move |_| Ok(session.clone()));
+        mock_repo.expect_rotate_refresh_token().never();
+        mock_repo
+            .expect_revoke_session()
+            .with(eq("session-1"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let session_service = SessionService::new(
+            Arc::new(mock_repo),
+            TokenProvider::new(TOKEN_KEY.to_string()),
+        );
+
+        let result = session_service
+            .refresh_session(&old_refresh_token.to_string())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(SessionServiceError::RefreshTokenReuse(_))
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+//! The full router of the API, built the way `start_http_server` does, over a migrated
+//! SQLite database in memory. Every `TestApp` gets its own database.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, OnceLock,
+};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::{
+    api_server::{app_router, build_http_services, ServerConfig},
+    database::{DatabaseHandle, DatabaseMode, PoolConfig},
+    migrations,
+    phone::PhoneNormalizer,
+    repositories::Repositories,
+    user_service::{
+        domain::{UserCreationInfo, UserRol},
+        notifier::LogNotifier,
+    },
+};
+
+pub const TOKEN_KEY: &str = "gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=";
+
+/// Password of every user made by `TestApp::create_user`.
+pub const TEST_PASSWORD: &str = "una-clave-segura";
+
+/// Lowest bcrypt cost, the default one takes about a second per hash in debug builds.
+const TEST_PASSWORD_COST: u32 = 4;
+
+static NEXT_PHONE: AtomicU64 = AtomicU64::new(3_000_000_000);
+
+fn test_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+
+    HASH.get_or_init(|| bcrypt::hash(TEST_PASSWORD, TEST_PASSWORD_COST).unwrap())
+}
+
+pub struct TestApp {
+    pub router: Router,
+    pub repositories: Repositories,
+}
+
+/// A user stored in the database and logged in with `TEST_PASSWORD`.
+pub struct TestUser {
+    pub id_persona: String,
+    pub correo: String,
+    /// In E.164, the way it is stored
+    pub telefono: String,
+    pub identificacion: String,
+    pub token: String,
+    pub refresh_token: String,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// `Value::Null` when the body is empty or not JSON
+    pub body: Value,
+    /// The raw body, for the answers that are not JSON
+    pub text: String,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        let db = DatabaseHandle::open(DatabaseMode::Memory, "", None, PoolConfig::default())
+            .await
+            .unwrap();
+        migrations::migrate(&db.connect().await.unwrap())
+            .await
+            .unwrap();
+
+        Self::with_repositories(Repositories::libsql(db)).await
+    }
+
+    pub async fn with_repositories(repositories: Repositories) -> Self {
+        let http_services = build_http_services(
+            &repositories,
+            TOKEN_KEY,
+            Arc::new(LogNotifier::new(None)),
+            PhoneNormalizer::default(),
+        )
+        .await;
+
+        Self {
+            router: app_router(http_services, None, &ServerConfig::default()).unwrap(),
+            repositories,
+        }
+    }
+
+    /// Stores a user with the role and logs it in, the name makes the email unique.
+    pub async fn create_user(&self, nombre: &str, rol: UserRol) -> TestUser {
+        let number = NEXT_PHONE.fetch_add(1, Ordering::Relaxed);
+        let telefono = format!("+57{number}");
+        let correo = format!("{nombre}@unisabana.edu.co");
+        let identificacion = format!("10{number}");
+
+        self.repositories
+            .user
+            .create_user(UserCreationInfo {
+                nombre: nombre.to_string(),
+                contrasena: test_password_hash().to_string(),
+                correo: correo.clone(),
+                telefono: telefono.clone(),
+                identificacion: identificacion.clone(),
+                nombre_tipo_identificacion: "CC".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let id_persona = self
+            .repositories
+            .unique_identifier
+            .get_user_id_by_email(&correo)
+            .await
+            .unwrap();
+        self.repositories
+            .user
+            .update_user_role(rol, &id_persona)
+            .await
+            .unwrap();
+
+        let login = self.log_in(&correo, TEST_PASSWORD).await;
+        assert_eq!(login.status, StatusCode::OK, "log in of {correo}");
+
+        TestUser {
+            id_persona,
+            correo,
+            telefono,
+            identificacion,
+            token: login.body["access_token"].as_str().unwrap().to_string(),
+            refresh_token: login.body["refresh_token"].as_str().unwrap().to_string(),
+        }
+    }
+
+    pub async fn log_in(&self, identificacion: &str, contrasena: &str) -> TestResponse {
+        self.post(
+            "/api/v1/auth/login",
+            None,
+            json!({ "identificacion": identificacion, "contrasena": contrasena }),
+        )
+        .await
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.call(Method::GET, uri, token, None).await
+    }
+
+    pub async fn post(&self, uri: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.call(Method::POST, uri, token, Some(body)).await
+    }
+
+    pub async fn put(&self, uri: &str, token: Option<&str>, body: Value) -> TestResponse {
+        self.call(Method::PUT, uri, token, Some(body)).await
+    }
+
+    pub async fn delete(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.call(Method::DELETE, uri, token, None).await
+    }
+
+    pub async fn call(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        self.send(request).await
+    }
+
+    /// Sends a request built by the test, for the headers `call` does not set.
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        TestResponse {
+            status,
+            headers,
+            body: serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            text: String::from_utf8_lossy(&bytes).into_owned(),
+        }
+    }
+}
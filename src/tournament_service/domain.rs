@@ -1,21 +1,37 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::pagination::{FilterKind, Listable};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Tournament {
     pub id_torneo: String,
     pub nombre: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Listable for Tournament {
+    const SORT_FIELDS: &'static [&'static str] = &["nombre", "id_torneo"];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = &[("nombre", FilterKind::Text)];
+    const ID_FIELD: &'static str = "id_torneo";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserTournamentRegistration {
     pub id_persona: String,
     pub id_torneo: String,
     pub puesto: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserTournamentInfo {
     pub id_torneo: String,
     pub nombre: String,
     pub puesto: i32,
 }
+
+/// A user is registered once per tournament, so the tournament tells the rows apart.
+impl Listable for UserTournamentInfo {
+    const SORT_FIELDS: &'static [&'static str] = &["nombre", "puesto", "id_torneo"];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = &[];
+    const ID_FIELD: &'static str = "id_torneo";
+}
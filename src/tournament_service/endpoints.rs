@@ -1,21 +1,23 @@
-use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    middleware,
-    routing::{delete, get, post},
-    Extension, Router,
-};
-use tracing::error;
+use crate::extract::{Json, Path};
+use axum::{extract::State, http::StatusCode, Extension};
+use serde::{Deserialize, Serialize};
+use utoipa::{OpenApi, ToSchema};
 
 use std::sync::Arc;
 
 use crate::{
-    auth_middleware::auth_middleware, global_traits::HttpService,
+    api_error::ErrorBody,
+    auth_middleware::AuthContext,
+    database::UnitOfWork,
+    global_traits::HttpService,
+    pagination::{ListParams, ListQuery, Page},
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
     unique_identifier_service::usecases::UniqueIdentifier,
 };
 
 use super::{
     domain::{Tournament, UserTournamentInfo, UserTournamentRegistration},
+    err::TournamentServiceError,
     repository::TournamentRepository,
     use_cases::TournamentService,
 };
@@ -23,19 +25,22 @@ use super::{
 pub struct TournamentHttpServer {
     tournament_repository: Arc<dyn TournamentRepository>,
     unique_identifier: Arc<dyn UniqueIdentifier>,
-    token_key: String,
+    unit_of_work: UnitOfWork,
+    access_control: AccessControl,
 }
 
 impl TournamentHttpServer {
     pub async fn new(
         tournament_repository: Arc<dyn TournamentRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
-        token_key: &str,
+        unit_of_work: UnitOfWork,
+        access_control: AccessControl,
     ) -> Self {
         Self {
             tournament_repository,
             unique_identifier,
-            token_key: token_key.to_string(),
+            unit_of_work,
+            access_control,
         }
     }
 }
@@ -45,157 +50,648 @@ impl HttpService for TournamentHttpServer {
         let tournament_service = TournamentService::new(
             self.tournament_repository.clone(),
             self.unique_identifier.clone(),
+            self.unit_of_work.clone(),
         );
 
-        Router::new()
-            .route("/tournament", get(get_tournament_by_user_with_extension))
-            .route(
-                "/tournament/positions/{tournament_id}",
-                get(get_tournament_positions),
-            )
-            .route("/tournament/id/{tournament_id}", get(get_tournament))
-            .layer(middleware::from_fn_with_state(
-                self.token_key.clone(),
-                auth_middleware,
-            ))
-            .route(
-                "/tournament/delete/{tournament_id}",
-                delete(delete_tournament),
-            )
-            .route(
-                "/tournament/name/{tournament_name}",
-                post(create_tournament),
-            )
-            .route("/tournament/register", post(register_user_in_tournament))
-            .route("/tournament/all", get(get_all_tournaments))
-            .route("/tournament/{identificator}", get(get_tournament_by_user))
-            .route(
-                "/tournament/users/{id_tournament}",
-                post(get_users_in_tournament),
+        self.access_control
+            .build_router("tournament", routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("tournament", legacy_routes()),
             )
             .with_state(tournament_service)
     }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(TournamentApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<TournamentService>> {
+    vec![
+        RouteAccess::get("/api/v1/tournaments", Access::Public, list_tournaments),
+        RouteAccess::post("/api/v1/tournaments", Access::staff(), create_tournament),
+        RouteAccess::get(
+            "/api/v1/tournaments/{tournament_id}",
+            Access::Authenticated,
+            get_tournament,
+        ),
+        RouteAccess::delete(
+            "/api/v1/tournaments/{tournament_id}",
+            Access::admin(),
+            delete_tournament,
+        ),
+        RouteAccess::get(
+            "/api/v1/tournaments/{tournament_id}/positions",
+            Access::Authenticated,
+            get_tournament_positions,
+        ),
+        RouteAccess::get(
+            "/api/v1/tournaments/{tournament_id}/participants",
+            Access::Authenticated,
+            get_users_in_tournament,
+        ),
+        RouteAccess::post(
+            "/api/v1/tournaments/{tournament_id}/participants",
+            Access::staff(),
+            register_participant,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/me/tournaments",
+            Access::Authenticated,
+            list_my_tournaments,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/{user_identifier}/tournaments",
+            Access::Authenticated,
+            list_user_tournaments,
+        ),
+    ]
+}
+
+/// Paths from before `/api/v1`, removed once the mobile clients are migrated.
+fn legacy_routes() -> Vec<RouteAccess<TournamentService>> {
+    vec![
+        RouteAccess::get(
+            "/tournament",
+            Access::Authenticated,
+            get_tournament_by_user_with_extension,
+        ),
+        RouteAccess::get(
+            "/tournament/positions/{tournament_id}",
+            Access::Authenticated,
+            get_tournament_positions,
+        ),
+        RouteAccess::get(
+            "/tournament/id/{tournament_id}",
+            Access::Authenticated,
+            get_tournament,
+        ),
+        RouteAccess::delete(
+            "/tournament/delete/{tournament_id}",
+            Access::admin(),
+            legacy_delete_tournament,
+        ),
+        RouteAccess::post(
+            "/tournament/name/{tournament_name}",
+            Access::staff(),
+            create_tournament_by_name,
+        ),
+        RouteAccess::post(
+            "/tournament/register",
+            Access::staff(),
+            register_user_in_tournament,
+        ),
+        RouteAccess::get("/tournament/all", Access::Public, get_all_tournaments),
+        RouteAccess::get(
+            "/tournament/{identificator}",
+            Access::Authenticated,
+            get_tournament_by_user,
+        ),
+        RouteAccess::post(
+            "/tournament/users/{id_tournament}",
+            Access::Authenticated,
+            get_users_in_tournament,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_tournaments,
+        create_tournament,
+        get_tournament,
+        delete_tournament,
+        get_tournament_positions,
+        get_users_in_tournament,
+        register_participant,
+        list_my_tournaments,
+        list_user_tournaments,
+    ),
+    tags((name = "tournament", description = "Tournaments and the positions of the users in them"))
+)]
+struct TournamentApi;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct TournamentCreation {
+    nombre: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct TournamentParticipant {
+    id_persona: String,
+    puesto: i32,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/tournaments/{tournament_id}",
+    tag = "tournament",
+    params(("tournament_id" = String, Path)),
+    responses(
+        (status = 200, description = "The tournament", body = Tournament),
+        (status = 404, description = "`tournament_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_tournament(
     State(state): State<TournamentService>,
     Path(tournament_id): Path<String>,
-) -> Result<Json<Tournament>, StatusCode> {
-    match state.get_tournament(&tournament_id).await {
-        Err(err) => {
-            error!("Error getting tournament by id: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_tournaments_info) => Ok(Json(user_tournaments_info)),
-    }
+) -> Result<Json<Tournament>, TournamentServiceError> {
+    Ok(Json(state.get_tournament(&tournament_id).await?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/tournaments/{tournament_id}/positions",
+    tag = "tournament",
+    params(("tournament_id" = String, Path)),
+    responses((status = 200, description = "Positions taken in the tournament", body = Vec<u32>))
+)]
 async fn get_tournament_positions(
     State(state): State<TournamentService>,
     Path(tournament_id): Path<String>,
-) -> Result<Json<Vec<u32>>, StatusCode> {
-    match state.get_tournament_positions(&tournament_id).await {
-        Err(err) => {
-            error!("Error getting tournament positions: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(tournament_positions) => Ok(Json(tournament_positions)),
-    }
+) -> Result<Json<Vec<u32>>, TournamentServiceError> {
+    Ok(Json(state.get_tournament_positions(&tournament_id).await?))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tournaments/{tournament_id}",
+    tag = "tournament",
+    params(("tournament_id" = String, Path)),
+    responses(
+        (status = 204, description = "The tournament was deleted"),
+        (status = 404, description = "`tournament_not_found`", body = ErrorBody),
+    )
+)]
 async fn delete_tournament(
     State(state): State<TournamentService>,
     Path(tournament_id): Path<String>,
-) -> StatusCode {
-    match state.delete_tournament(&tournament_id).await {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error creating tournament: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+) -> Result<StatusCode, TournamentServiceError> {
+    state.delete_tournament(&tournament_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
+/// The clients of the old path expect the 201 it always answered.
+async fn legacy_delete_tournament(
+    State(state): State<TournamentService>,
+    Path(tournament_id): Path<String>,
+) -> Result<StatusCode, TournamentServiceError> {
+    state.delete_tournament(&tournament_id).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tournaments",
+    tag = "tournament",
+    request_body = TournamentCreation,
+    responses(
+        (status = 201, description = "The tournament was created"),
+        (status = 409, description = "`duplicate`, there is a tournament with the name", body = ErrorBody),
+    )
+)]
 async fn create_tournament(
+    State(state): State<TournamentService>,
+    Json(tournament_creation): Json<TournamentCreation>,
+) -> Result<StatusCode, TournamentServiceError> {
+    state.create_tournament(tournament_creation.nombre).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+async fn create_tournament_by_name(
     State(state): State<TournamentService>,
     Path(tournament_name): Path<String>,
-) -> StatusCode {
-    match state.create_tournament(tournament_name).await {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error creating tournament: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+) -> Result<StatusCode, TournamentServiceError> {
+    state.create_tournament(tournament_name).await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/tournaments",
+    tag = "tournament",
+    params(ListQuery<UserTournamentInfo>),
+    responses(
+        (status = 200, description = "Page of the tournaments of the caller", body = Page<UserTournamentInfo>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_my_tournaments(
+    State(state): State<TournamentService>,
+    Extension(auth_context): Extension<AuthContext>,
+    ListQuery(params, _): ListQuery<UserTournamentInfo>,
+) -> Result<Json<Page<UserTournamentInfo>>, TournamentServiceError> {
+    Ok(Json(
+        state
+            .get_tournaments_by_identificator(auth_context.user_id, &params)
+            .await?,
+    ))
 }
 
 async fn get_tournament_by_user_with_extension(
     State(state): State<TournamentService>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<Vec<UserTournamentInfo>>, StatusCode> {
-    match state.get_tournaments_by_identificator(user_id).await {
-        Err(err) => {
-            error!("Error getting tournament by user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_tournaments_info) => Ok(Json(user_tournaments_info)),
-    }
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<UserTournamentInfo>>, TournamentServiceError> {
+    let tournaments = state
+        .get_tournaments_by_identificator(
+            auth_context.user_id,
+            &ListParams::unbounded::<UserTournamentInfo>(),
+        )
+        .await?;
+
+    Ok(Json(tournaments.items))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_identifier}/tournaments",
+    tag = "tournament",
+    params(
+        ("user_identifier" = String, Path, description = "Id, email, identification or phone of the user, tried in that order"),
+        ListQuery<UserTournamentInfo>,
+    ),
+    responses(
+        (status = 200, description = "Page of the tournaments of the user", body = Page<UserTournamentInfo>),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_user_tournaments(
+    State(state): State<TournamentService>,
+    Path(identificator): Path<String>,
+    ListQuery(params, _): ListQuery<UserTournamentInfo>,
+) -> Result<Json<Page<UserTournamentInfo>>, TournamentServiceError> {
+    Ok(Json(
+        state
+            .get_tournaments_by_identificator(identificator, &params)
+            .await?,
+    ))
 }
 
 async fn get_tournament_by_user(
     State(state): State<TournamentService>,
     Path(identificator): Path<String>,
-) -> Result<Json<Vec<UserTournamentInfo>>, StatusCode> {
-    match state.get_tournaments_by_identificator(identificator).await {
-        Err(err) => {
-            error!("Error getting tournament by user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_tournaments_info) => Ok(Json(user_tournaments_info)),
-    }
+) -> Result<Json<Vec<UserTournamentInfo>>, TournamentServiceError> {
+    let tournaments = state
+        .get_tournaments_by_identificator(
+            identificator,
+            &ListParams::unbounded::<UserTournamentInfo>(),
+        )
+        .await?;
+
+    Ok(Json(tournaments.items))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tournaments/{tournament_id}/participants",
+    tag = "tournament",
+    params(("tournament_id" = String, Path)),
+    request_body = TournamentParticipant,
+    responses(
+        (status = 201, description = "The user was registered"),
+        (status = 409, description = "`user_already_registered`", body = ErrorBody),
+        (status = 422, description = "`invalid_reference`, the user or the tournament does not exist", body = ErrorBody),
+    )
+)]
+async fn register_participant(
+    State(state): State<TournamentService>,
+    Path(tournament_id): Path<String>,
+    Json(participant): Json<TournamentParticipant>,
+) -> Result<StatusCode, TournamentServiceError> {
+    state
+        .register_user_in_tournament(participant.id_persona, tournament_id, participant.puesto)
+        .await?;
+
+    Ok(StatusCode::CREATED)
 }
 
 async fn register_user_in_tournament(
     State(state): State<TournamentService>,
     Json(registration): Json<UserTournamentRegistration>,
-) -> StatusCode {
-    match state
+) -> Result<StatusCode, TournamentServiceError> {
+    state
         .register_user_in_tournament(
             registration.id_persona,
             registration.id_torneo,
             registration.puesto,
         )
-        .await
-    {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error registering user in tournament: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+        .await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tournaments",
+    tag = "tournament",
+    params(ListQuery<Tournament>),
+    responses(
+        (status = 200, description = "Page of the tournaments", body = Page<Tournament>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_tournaments(
+    State(state): State<TournamentService>,
+    ListQuery(params, _): ListQuery<Tournament>,
+) -> Result<Json<Page<Tournament>>, TournamentServiceError> {
+    Ok(Json(state.list_tournaments(&params).await?))
 }
 
 async fn get_all_tournaments(
     State(state): State<TournamentService>,
-) -> Result<Json<Vec<Tournament>>, StatusCode> {
-    match state.get_all_tournaments().await {
-        Ok(tournaments) => Ok(Json(tournaments)),
-        Err(err) => {
-            error!("Error fetching all tournaments: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<Vec<Tournament>>, TournamentServiceError> {
+    let tournaments = state
+        .list_tournaments(&ListParams::unbounded::<Tournament>())
+        .await?;
+
+    Ok(Json(tournaments.items))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/tournaments/{tournament_id}/participants",
+    tag = "tournament",
+    params(("tournament_id" = String, Path)),
+    responses((status = 200, description = "Registrations of the tournament", body = Vec<UserTournamentRegistration>))
+)]
 async fn get_users_in_tournament(
     State(state): State<TournamentService>,
     Path(id_torneo): Path<String>,
-) -> Result<Json<Vec<UserTournamentRegistration>>, StatusCode> {
-    match state.get_users_in_tournament(id_torneo).await {
-        Ok(users) => Ok(Json(users)),
-        Err(err) => {
-            error!("Error fetching users in tournament: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+) -> Result<Json<Vec<UserTournamentRegistration>>, TournamentServiceError> {
+    Ok(Json(state.get_users_in_tournament(id_torneo).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        test_harness::{TestApp, TestUser},
+        user_service::domain::UserRol,
+    };
+
+    async fn create_tournament(app: &TestApp, trainer: &TestUser, nombre: &str) -> String {
+        let response = app
+            .post(
+                "/api/v1/tournaments",
+                Some(&trainer.token),
+                json!({ "nombre": nombre }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        let tournaments = app.get("/api/v1/tournaments", None).await.body;
+        tournaments["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|tournament| tournament["nombre"] == nombre)
+            .unwrap()["id_torneo"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_tournaments() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let copa = json!({ "nombre": "Copa" });
+        assert_eq!(
+            app.post("/api/v1/tournaments", None, copa.clone())
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/tournaments", Some(&user.token), copa)
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+        let id_torneo = create_tournament(&app, &trainer, "Copa").await;
+
+        let response = app.get("/api/v1/tournaments", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["total"], 1);
+        assert_eq!(response.body["items"].as_array().unwrap().len(), 1);
+
+        let uri = format!("/api/v1/tournaments/{id_torneo}");
+        assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+        let response = app.get(&uri, Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["nombre"], "Copa");
+        let response = app
+            .get("/api/v1/tournaments/no-existe", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "tournament_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_register_users_in_tournament() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let id_torneo = create_tournament(&app, &trainer, "Copa").await;
+
+        let uri = format!("/api/v1/tournaments/{id_torneo}/participants");
+        let participant = json!({ "id_persona": user.id_persona, "puesto": 2 });
+        assert_eq!(
+            app.post(&uri, None, participant.clone()).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post(&uri, Some(&user.token), participant.clone())
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(&uri, Some(&trainer.token), participant.clone())
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+        let response = app
+            .post(&uri, Some(&trainer.token), participant.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["code"], "user_already_registered");
+        let response = app
+            .post(
+                "/api/v1/tournaments/no-existe/participants",
+                Some(&trainer.token),
+                participant,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["code"], "invalid_reference");
+
+        let response = app
+            .get(
+                &format!("/api/v1/tournaments/{id_torneo}/positions"),
+                Some(&user.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, json!([2]));
+
+        let response = app.get(&uri, Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body[0]["id_persona"], json!(user.id_persona));
+
+        for uri in [
+            "/api/v1/users/me/tournaments".to_string(),
+            format!("/api/v1/users/{}/tournaments", user.correo),
+        ] {
+            assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+            let response = app.get(&uri, Some(&user.token)).await;
+            assert_eq!(response.status, StatusCode::OK, "GET {uri}");
+            assert_eq!(response.body["items"][0]["puesto"], 2, "GET {uri}");
+            assert_eq!(response.body["total"], 1, "GET {uri}");
         }
+        let response = app
+            .get(
+                "/api/v1/users/nadie@unisabana.edu.co/tournaments",
+                Some(&user.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "user_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_list_tournaments_by_pages() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        for nombre in ["Copa A", "Copa B", "Copa C"] {
+            create_tournament(&app, &trainer, nombre).await;
+        }
+
+        let response = app
+            .get("/api/v1/tournaments?limit=2&sort=nombre&order=desc", None)
+            .await;
+        assert_eq!(response.body["total"], 3);
+        assert_eq!(response.body["items"][0]["nombre"], "Copa C");
+        assert_eq!(response.body["items"][1]["nombre"], "Copa B");
+
+        let cursor = response.body["next_cursor"].as_str().unwrap();
+        let uri = format!("/api/v1/tournaments?limit=2&sort=nombre&order=desc&cursor={cursor}");
+        let response = app.get(&uri, None).await;
+        assert_eq!(response.body["items"][0]["nombre"], "Copa A");
+        assert_eq!(response.body["next_cursor"], json!(null));
+
+        let response = app
+            .get("/api/v1/tournaments?page=2&limit=2&sort=nombre", None)
+            .await;
+        assert_eq!(response.body["items"][0]["nombre"], "Copa C");
+
+        let response = app.get("/api/v1/tournaments?nombre=Copa%20B", None).await;
+        assert_eq!(response.body["total"], 1);
+
+        let response = app
+            .get("/api/v1/tournaments?limit=0&sort=fecha", None)
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["errors"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_delete_tournament() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let id_torneo = create_tournament(&app, &trainer, "Copa").await;
+
+        let uri = format!("/api/v1/tournaments/{id_torneo}");
+        assert_eq!(
+            app.delete(&uri, None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&trainer.token)).await.status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&admin.token)).await.status,
+            StatusCode::NO_CONTENT
+        );
+
+        let response = app.get("/api/v1/tournaments", None).await;
+        assert_eq!(response.body["items"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_tournament() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+
+        let response = app
+            .delete("/api/v1/tournaments/no-existe", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "tournament_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated_aliases() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+
+        let response = app
+            .post("/tournament/name/Copa", Some(&trainer.token), json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert!(response.headers.contains_key("deprecation"));
+        assert!(response.headers.contains_key("sunset"));
+
+        let response = app.get("/tournament/all", None).await;
+        assert_eq!(response.body[0]["nombre"], "Copa");
+        assert!(response.headers.contains_key("deprecation"));
+        let id_torneo = response.body[0]["id_torneo"].as_str().unwrap();
+
+        let registration = json!({
+            "id_persona": trainer.id_persona,
+            "id_torneo": id_torneo,
+            "puesto": 1
+        });
+        assert_eq!(
+            app.post("/tournament/register", Some(&trainer.token), registration)
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+        let response = app
+            .post(
+                &format!("/tournament/users/{id_torneo}"),
+                Some(&trainer.token),
+                json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body[0]["puesto"], 1);
+
+        let response = app.get("/api/v1/tournaments", None).await;
+        assert!(!response.headers.contains_key("deprecation"));
+
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let response = app
+            .delete(
+                &format!("/tournament/delete/{id_torneo}"),
+                Some(&admin.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
     }
 }
@@ -1,3 +1,7 @@
+use axum::response::{IntoResponse, Response};
+
+use crate::api_error::ApiError;
+
 use super::repository::err::TournamentRepositoryError;
 
 pub type Result<T> = std::result::Result<T, TournamentServiceError>;
@@ -6,10 +10,32 @@ pub type Result<T> = std::result::Result<T, TournamentServiceError>;
 pub enum TournamentServiceError {
     #[error("Database error: {0}")]
     DatabaseError(#[from] TournamentRepositoryError),
-    #[error("Tournament not found")]
-    TournamentNotFound,
-    #[error("User already registered in tournament")]
-    UserAlreadyRegistered,
+    #[error("Transaction error: {0}")]
+    TransactionError(#[from] libsql::Error),
     #[error("Could not identify user with identificator: {0}")]
     UserNotIdentifiable(String),
 }
+
+impl IntoResponse for TournamentServiceError {
+    fn into_response(self) -> Response {
+        match &self {
+            TournamentServiceError::DatabaseError(
+                TournamentRepositoryError::TournamentNotFound,
+            ) => ApiError::not_found("tournament_not_found", &self),
+            TournamentServiceError::DatabaseError(
+                TournamentRepositoryError::UserAlreadyRegistered,
+            ) => ApiError::conflict("user_already_registered", &self),
+            TournamentServiceError::DatabaseError(TournamentRepositoryError::Duplicate {
+                field,
+            }) => ApiError::duplicate(field),
+            TournamentServiceError::DatabaseError(
+                TournamentRepositoryError::ForeignKeyViolation,
+            ) => ApiError::invalid_reference(),
+            TournamentServiceError::UserNotIdentifiable(_) => {
+                ApiError::not_found("user_not_found", &self)
+            }
+            _ => ApiError::internal(&self),
+        }
+        .into_response()
+    }
+}
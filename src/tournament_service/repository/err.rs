@@ -1,4 +1,4 @@
-use std::error::Error;
+use crate::database::from_libsql_error;
 
 pub type Result<T> = std::result::Result<T, TournamentRepositoryError>;
 
@@ -10,4 +10,12 @@ pub enum TournamentRepositoryError {
     TournamentNotFound,
     #[error("User already registered in tournament")]
     UserAlreadyRegistered,
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
 }
+
+from_libsql_error!(TournamentRepositoryError, |err: libsql::Error| {
+    TournamentRepositoryError::DatabaseError(err.to_string())
+});
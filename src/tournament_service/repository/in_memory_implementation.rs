@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+
+use crate::{
+    in_memory::InMemoryStore,
+    pagination::{paginate, ListParams, Page},
+    tournament_service::domain::{Tournament, UserTournamentInfo, UserTournamentRegistration},
+};
+
+use super::{
+    err::{Result, TournamentRepositoryError},
+    TournamentRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemoryTournamentRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryTournamentRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TournamentRepository for InMemoryTournamentRepository {
+    async fn create_tournament(&self, tournament: Tournament) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        if tables.torneo.contains_key(&tournament.id_torneo) {
+            return Err(TournamentRepositoryError::DatabaseError(format!(
+                "Tournament {} already exists",
+                tournament.id_torneo
+            )));
+        }
+
+        tables
+            .torneo
+            .insert(tournament.id_torneo.clone(), tournament);
+
+        Ok(())
+    }
+
+    async fn register_user_in_tournament(
+        &self,
+        registration: UserTournamentRegistration,
+    ) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        let already_registered = tables.persona_torneo.iter().any(|existing| {
+            existing.id_persona == registration.id_persona
+                && existing.id_torneo == registration.id_torneo
+        });
+
+        if already_registered {
+            return Err(TournamentRepositoryError::UserAlreadyRegistered);
+        }
+
+        tables.persona_torneo.push(registration);
+
+        Ok(())
+    }
+
+    async fn list_tournaments(&self, params: &ListParams) -> Result<Page<Tournament>> {
+        let tournaments = self.store.tables().torneo.values().cloned().collect();
+
+        Ok(paginate(tournaments, params))
+    }
+
+    async fn get_users_in_tournament(
+        &self,
+        id_torneo: &str,
+    ) -> Result<Vec<UserTournamentRegistration>> {
+        Ok(self
+            .store
+            .tables()
+            .persona_torneo
+            .iter()
+            .filter(|registration| registration.id_torneo == id_torneo)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_tournaments_info_for_user(
+        &self,
+        user_id: &str,
+        params: &ListParams,
+    ) -> Result<Page<UserTournamentInfo>> {
+        let tables = self.store.tables();
+
+        let tournaments = tables
+            .persona_torneo
+            .iter()
+            .filter(|registration| registration.id_persona == user_id)
+            .filter_map(|registration| {
+                tables
+                    .torneo
+                    .get(&registration.id_torneo)
+                    .map(|tournament| UserTournamentInfo {
+                        id_torneo: tournament.id_torneo.clone(),
+                        nombre: tournament.nombre.clone(),
+                        puesto: registration.puesto,
+                    })
+            })
+            .collect();
+
+        Ok(paginate(tournaments, params))
+    }
+
+    async fn delete_tournament(&self, tournament_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .torneo
+            .remove(tournament_id)
+            .map(|_| ())
+            .ok_or(TournamentRepositoryError::TournamentNotFound)
+    }
+
+    async fn delete_tournament_registrations(&self, tournament_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .persona_torneo
+            .retain(|registration| registration.id_torneo != tournament_id);
+
+        Ok(())
+    }
+
+    async fn get_tournament_positions(&self, tournament_id: &str) -> Result<Vec<u32>> {
+        Ok(self
+            .store
+            .tables()
+            .persona_torneo
+            .iter()
+            .filter(|registration| registration.id_torneo == tournament_id)
+            .map(|registration| registration.puesto as u32)
+            .collect())
+    }
+
+    async fn get_tournament(&self, tournament_id: &str) -> Result<Tournament> {
+        self.store
+            .tables()
+            .torneo
+            .get(tournament_id)
+            .cloned()
+            .ok_or(TournamentRepositoryError::TournamentNotFound)
+    }
+}
@@ -1,4 +1,6 @@
 use super::err::Result;
+use crate::database::{DatabaseHandle, PooledConnection};
+use crate::pagination::{count_rows, ListParams, Page};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -12,26 +14,18 @@ use super::{err::TournamentRepositoryError, TournamentRepository};
 
 #[derive(Clone)]
 pub struct TournamentRepositoryImpl {
-    db: Arc<libsql::Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl TournamentRepositoryImpl {
-    pub async fn new(
-        url: &str,
-        token: &str,
-    ) -> std::result::Result<Arc<dyn TournamentRepository>, String> {
-        let db = libsql::Builder::new_remote(url.to_string(), token.to_string())
-            .build()
-            .await
-            .map_err(|err| format!("Error creating new remote database for libsql: {err}"))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<libsql::Connection> {
-        self.db
-            .connect()
-            .map_err(|err| TournamentRepositoryError::DatabaseError("Error connecting".to_string()))
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        self.db.connect().await.map_err(|err| {
+            TournamentRepositoryError::DatabaseError(format!("Error connecting: {err}"))
+        })
     }
 }
 
@@ -41,18 +35,13 @@ impl TournamentRepository for TournamentRepositoryImpl {
         let conn = self.get_connection().await?;
 
         let mut row = conn
-            .query(
+            .read(
                 "SELECT id_torneo, nombre FROM torneo WHERE id_torneo = ?1",
                 params![tournament_id],
             )
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
-
-        if let Some(row) = row
-            .next()
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?
-        {
+            .await?;
+
+        if let Some(row) = row.next().await? {
             let tournament = de::from_row(&row)
                 .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
 
@@ -66,24 +55,16 @@ impl TournamentRepository for TournamentRepositoryImpl {
         let conn = self.get_connection().await?;
 
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT puesto FROM persona_torneo WHERE id_torneo = ?1",
                 params![tournament_id],
             )
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut puestos = Vec::new();
 
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?
-        {
-            puestos.push(
-                row.get(0)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-            );
+        while let Some(row) = rows.next().await? {
+            puestos.push(row.get(0)?);
         }
 
         Ok(puestos)
@@ -95,8 +76,7 @@ impl TournamentRepository for TournamentRepositoryImpl {
             "INSERT INTO torneo (id_torneo, nombre) VALUES (?1, ?2)",
             libsql::params![tournament.id_torneo, tournament.nombre],
         )
-        .await
-        .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(())
     }
@@ -104,12 +84,28 @@ impl TournamentRepository for TournamentRepositoryImpl {
     async fn delete_tournament(&self, tournament_id: &str) -> Result<()> {
         let conn = self.get_connection().await?;
 
+        let deleted = conn
+            .execute(
+                "DELETE FROM torneo WHERE id_torneo = ?1",
+                libsql::params![tournament_id],
+            )
+            .await?;
+
+        if deleted == 0 {
+            return Err(TournamentRepositoryError::TournamentNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_tournament_registrations(&self, tournament_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
         conn.execute(
-            "DELETE FROM torneo WHERE id_torneo = ?1",
+            "DELETE FROM persona_torneo WHERE id_torneo = ?1",
             libsql::params![tournament_id],
         )
-        .await
-        .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(())
     }
@@ -128,35 +124,47 @@ impl TournamentRepository for TournamentRepositoryImpl {
             ],
         )
         .await
-        .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+        .map_err(|err| match TournamentRepositoryError::from(err) {
+            // The primary key of the table is the pair of ids
+            TournamentRepositoryError::Duplicate { .. } => {
+                TournamentRepositoryError::UserAlreadyRegistered
+            }
+            err => err,
+        })?;
 
         Ok(())
     }
 
-    async fn get_all_tournaments(&self) -> Result<Vec<Tournament>> {
+    async fn list_tournaments(&self, params: &ListParams) -> Result<Page<Tournament>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("");
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM torneo WHERE {conditions}"),
+            params.filter_values(),
+        )
+        .await?;
+
         let mut rows = conn
-            .query("SELECT id_torneo, nombre FROM torneo", libsql::params![])
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+            .read(
+                &format!(
+                    "SELECT id_torneo, nombre FROM torneo WHERE {conditions} {}",
+                    params.sql_page("")
+                ),
+                params.filter_values(),
+            )
+            .await?;
 
         let mut tournaments = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             tournaments.push(Tournament {
-                id_torneo: row
-                    .get(0)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-                nombre: row
-                    .get(1)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
+                id_torneo: row.get(0)?,
+                nombre: row.get(1)?,
             });
         }
 
-        Ok(tournaments)
+        Ok(Page::new(tournaments, total, params))
     }
 
     async fn get_users_in_tournament(
@@ -165,29 +173,18 @@ impl TournamentRepository for TournamentRepositoryImpl {
     ) -> Result<Vec<UserTournamentRegistration>> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_persona, id_torneo, puesto FROM persona_torneo WHERE id_torneo = ?1",
                 libsql::params![id_torneo.to_string()],
             )
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut registrations = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             registrations.push(UserTournamentRegistration {
-                id_persona: row
-                    .get(0)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-                id_torneo: row
-                    .get(1)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-                puesto: row
-                    .get(2)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
+                id_persona: row.get(0)?,
+                id_torneo: row.get(1)?,
+                puesto: row.get(2)?,
             });
         }
 
@@ -197,39 +194,44 @@ impl TournamentRepository for TournamentRepositoryImpl {
     async fn get_tournaments_info_for_user(
         &self,
         user_id: &str,
-    ) -> Result<Vec<UserTournamentInfo>> {
+        params: &ListParams,
+    ) -> Result<Page<UserTournamentInfo>> {
         let conn = self.get_connection().await?;
+        // The sort fields are columns of both tables, the page is taken from the join
+        let user_tournaments = "SELECT torneo.id_torneo, torneo.nombre, persona_torneo.puesto
+            FROM torneo
+            INNER JOIN persona_torneo ON torneo.id_torneo = persona_torneo.id_torneo
+            WHERE persona_torneo.id_persona = ?";
+        let conditions = params.sql_conditions("");
+        let mut values = vec![libsql::Value::Text(user_id.to_string())];
+        values.extend(params.filter_values());
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM ({user_tournaments}) WHERE {conditions}"),
+            values.clone(),
+        )
+        .await?;
 
         let mut rows = conn
-            .query(
-                "SELECT torneo.id_torneo, torneo.nombre, persona_torneo.puesto
-                 FROM torneo
-                 INNER JOIN persona_torneo ON torneo.id_torneo = persona_torneo.id_torneo
-                 WHERE persona_torneo.id_persona = ?1",
-                libsql::params![user_id],
+            .read(
+                &format!(
+                    "SELECT id_torneo, nombre, puesto FROM ({user_tournaments}) WHERE {conditions} {}",
+                    params.sql_page("")
+                ),
+                values,
             )
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut tournaments = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             tournaments.push(UserTournamentInfo {
-                id_torneo: row
-                    .get(0)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-                nombre: row
-                    .get(1)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
-                puesto: row
-                    .get(2)
-                    .map_err(|e| TournamentRepositoryError::DatabaseError(e.to_string()))?,
+                id_torneo: row.get(0)?,
+                nombre: row.get(1)?,
+                puesto: row.get(2)?,
             });
         }
 
-        Ok(tournaments)
+        Ok(Page::new(tournaments, total, params))
     }
 }
@@ -1,10 +1,13 @@
 pub mod err;
-use std::sync::Arc;
 
 use super::domain::{Tournament, UserTournamentInfo, UserTournamentRegistration};
 use async_trait::async_trait;
 use err::Result;
 use mockall::automock;
+
+use crate::pagination::{ListParams, Page};
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod lib_sql_implementation;
 
 #[automock]
@@ -17,18 +20,23 @@ pub trait TournamentRepository: Send + Sync {
         registration: UserTournamentRegistration,
     ) -> Result<()>;
 
-    async fn get_all_tournaments(&self) -> Result<Vec<Tournament>>;
+    async fn list_tournaments(&self, params: &ListParams) -> Result<Page<Tournament>>;
 
     async fn get_users_in_tournament(
         &self,
         id_torneo: &str,
     ) -> Result<Vec<UserTournamentRegistration>>;
 
-    async fn get_tournaments_info_for_user(&self, user_id: &str)
-        -> Result<Vec<UserTournamentInfo>>;
+    async fn get_tournaments_info_for_user(
+        &self,
+        user_id: &str,
+        params: &ListParams,
+    ) -> Result<Page<UserTournamentInfo>>;
 
     async fn delete_tournament(&self, tournament_id: &str) -> Result<()>;
 
+    async fn delete_tournament_registrations(&self, tournament_id: &str) -> Result<()>;
+
     async fn get_tournament_positions(&self, tournament_id: &str) -> Result<Vec<u32>>;
 
     async fn get_tournament(&self, tournament_id: &str) -> Result<Tournament>;
@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use uuid::Uuid;
 
+use crate::database::UnitOfWork;
+use crate::pagination::{ListParams, Page};
 use crate::unique_identifier_service::usecases::UniqueIdentifier;
 
 use super::domain::{UserTournamentInfo, UserTournamentRegistration};
@@ -12,16 +14,19 @@ use super::{domain::Tournament, repository::TournamentRepository};
 pub struct TournamentService {
     tournament_repository: Arc<dyn TournamentRepository>,
     unique_identifier: Arc<dyn UniqueIdentifier>,
+    unit_of_work: UnitOfWork,
 }
 
 impl TournamentService {
     pub fn new(
         tournament_repository: Arc<dyn TournamentRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
+        unit_of_work: UnitOfWork,
     ) -> Self {
         Self {
             tournament_repository,
             unique_identifier,
+            unit_of_work,
         }
     }
 
@@ -41,12 +46,20 @@ impl TournamentService {
         Ok(positions)
     }
 
+    /// Deletes the tournament together with the positions of its participants.
     pub async fn delete_tournament(&self, tournament_id: &str) -> Result<()> {
-        self.tournament_repository
-            .delete_tournament(tournament_id)
-            .await?;
-
-        Ok(())
+        self.unit_of_work
+            .run(async {
+                self.tournament_repository
+                    .delete_tournament_registrations(tournament_id)
+                    .await?;
+                self.tournament_repository
+                    .delete_tournament(tournament_id)
+                    .await?;
+
+                Ok(())
+            })
+            .await
     }
 
     pub async fn create_tournament(&self, nombre: String) -> Result<()> {
@@ -66,7 +79,8 @@ impl TournamentService {
     pub async fn get_tournaments_by_identificator(
         &self,
         identificator: String,
-    ) -> Result<Vec<UserTournamentInfo>> {
+        params: &ListParams,
+    ) -> Result<Page<UserTournamentInfo>> {
         let user_id = self.unique_identifier.identify(identificator.clone()).await;
 
         let user_id = match user_id {
@@ -80,7 +94,7 @@ impl TournamentService {
 
         let user_tournaments_info = self
             .tournament_repository
-            .get_tournaments_info_for_user(&user_id)
+            .get_tournaments_info_for_user(&user_id, params)
             .await?;
 
         Ok(user_tournaments_info)
@@ -104,8 +118,8 @@ impl TournamentService {
         Ok(())
     }
 
-    pub async fn get_all_tournaments(&self) -> Result<Vec<Tournament>> {
-        Ok(self.tournament_repository.get_all_tournaments().await?)
+    pub async fn list_tournaments(&self, params: &ListParams) -> Result<Page<Tournament>> {
+        Ok(self.tournament_repository.list_tournaments(params).await?)
     }
 
     pub async fn get_users_in_tournament(
@@ -1,22 +1,23 @@
 use std::sync::Arc;
 
+use crate::extract::{Json, Path};
 use async_trait::async_trait;
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    middleware,
-    routing::{delete, get, post},
-    Extension, Json, Router,
-};
+use axum::{extract::State, http::StatusCode, Extension, Router};
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use utoipa::{OpenApi, ToSchema};
 
 use crate::{
-    auth_middleware::auth_middleware, global_traits::HttpService,
+    api_error::ErrorBody,
+    auth_middleware::AuthContext,
+    database::UnitOfWork,
+    global_traits::HttpService,
+    pagination::{ListParams, ListQuery, Page},
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
     unique_identifier_service::usecases::UniqueIdentifier,
 };
 
 use super::{
+    err::TrainingServiceError,
     model::{Training, TrainingRegistration},
     repository::TrainingRepository,
     use_cases::TrainingService,
@@ -24,19 +25,21 @@ use super::{
 
 pub struct TrainingHttpServer {
     training_service: Arc<TrainingService>,
-    token_key: String,
+    access_control: AccessControl,
 }
 
 impl TrainingHttpServer {
     pub async fn new(
         training_repository: Arc<dyn TrainingRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
-        token_key: &str,
+        unit_of_work: UnitOfWork,
+        access_control: AccessControl,
     ) -> Self {
-        let training_service = TrainingService::new(training_repository, unique_identifier.clone());
+        let training_service =
+            TrainingService::new(training_repository, unique_identifier.clone(), unit_of_work);
         Self {
             training_service: Arc::new(training_service),
-            token_key: token_key.to_string(),
+            access_control,
         }
     }
 }
@@ -44,90 +47,251 @@ impl TrainingHttpServer {
 #[async_trait]
 impl HttpService for TrainingHttpServer {
     fn get_router(&self) -> Router {
-        Router::new()
-            .route("/training", get(get_trainings_for_user_with_extension))
-            .route(
-                "/training/delete/{id_entrenamiento}",
-                delete(delete_training),
-            )
-            .route("/training/id/{id_entrenamiento}", get(get_training))
-            .layer(middleware::from_fn_with_state(
-                self.token_key.clone(),
-                auth_middleware,
-            ))
-            .route("/training", post(create_training))
-            .route("/training/register", post(register_user_in_training))
-            .route("/training/all", get(get_all_trainings))
-            .route(
-                "/training/users/{id_entrenamiento}",
-                get(get_users_in_training),
+        self.access_control
+            .build_router("training", routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("training", legacy_routes()),
             )
-            .route("/training/{user_identifier}", get(get_trainings_for_user))
             .with_state(self.training_service.clone())
     }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(TrainingApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<Arc<TrainingService>>> {
+    vec![
+        RouteAccess::get("/api/v1/trainings", Access::Public, list_trainings),
+        RouteAccess::post("/api/v1/trainings", Access::staff(), create_training),
+        RouteAccess::get(
+            "/api/v1/trainings/{training_id}",
+            Access::Authenticated,
+            get_training,
+        ),
+        RouteAccess::delete(
+            "/api/v1/trainings/{training_id}",
+            Access::admin(),
+            delete_training,
+        ),
+        RouteAccess::get(
+            "/api/v1/trainings/{training_id}/participants",
+            Access::Authenticated,
+            get_users_in_training,
+        ),
+        RouteAccess::post(
+            "/api/v1/trainings/{training_id}/participants",
+            Access::staff(),
+            register_participant,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/me/trainings",
+            Access::Authenticated,
+            list_my_trainings,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/{user_identifier}/trainings",
+            Access::Authenticated,
+            list_user_trainings,
+        ),
+    ]
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Paths from before `/api/v1`, removed once the mobile clients are migrated.
+fn legacy_routes() -> Vec<RouteAccess<Arc<TrainingService>>> {
+    vec![
+        RouteAccess::get(
+            "/training",
+            Access::Authenticated,
+            get_trainings_for_user_with_extension,
+        ),
+        RouteAccess::delete(
+            "/training/delete/{id_entrenamiento}",
+            Access::admin(),
+            legacy_delete_training,
+        ),
+        RouteAccess::get(
+            "/training/id/{id_entrenamiento}",
+            Access::Authenticated,
+            get_training,
+        ),
+        RouteAccess::post("/training", Access::staff(), create_training),
+        RouteAccess::post(
+            "/training/register",
+            Access::staff(),
+            register_user_in_training,
+        ),
+        RouteAccess::get("/training/all", Access::Public, get_all_trainings),
+        RouteAccess::get(
+            "/training/users/{id_entrenamiento}",
+            Access::Authenticated,
+            get_users_in_training,
+        ),
+        RouteAccess::get(
+            "/training/{user_identifier}",
+            Access::Authenticated,
+            get_trainings_for_user,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_trainings,
+        create_training,
+        get_training,
+        delete_training,
+        get_users_in_training,
+        register_participant,
+        list_my_trainings,
+        list_user_trainings,
+    ),
+    tags((name = "training", description = "Trainings and the users registered in them"))
+)]
+struct TrainingApi;
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 struct TrainingInfo {
     nombre_entrenamiento: String,
     tiempo_minutos: i32,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+struct TrainingParticipant {
+    id_persona: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trainings/{training_id}",
+    tag = "training",
+    params(("training_id" = String, Path)),
+    responses(
+        (status = 200, description = "The training", body = Training),
+        (status = 404, description = "`training_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_training(
     State(state): State<Arc<TrainingService>>,
     Path(training_id): Path<String>,
-) -> Result<Json<Training>, StatusCode> {
-    match state.get_training(&training_id).await {
-        Err(err) => {
-            error!("Error getting trainings for id: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_trainings) => Ok(Json(user_trainings)),
-    }
+) -> Result<Json<Training>, TrainingServiceError> {
+    Ok(Json(state.get_training(&training_id).await?))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/trainings/{training_id}",
+    tag = "training",
+    params(("training_id" = String, Path)),
+    responses(
+        (status = 204, description = "The training was deleted"),
+        (status = 404, description = "`training_not_found`", body = ErrorBody),
+    )
+)]
 async fn delete_training(
     State(state): State<Arc<TrainingService>>,
     Path(training_id): Path<String>,
-) -> StatusCode {
-    match state.delete_training(&training_id).await {
-        Err(err) => {
-            error!("Error deleting training: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+) -> Result<StatusCode, TrainingServiceError> {
+    state.delete_training(&training_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn legacy_delete_training(
+    State(state): State<Arc<TrainingService>>,
+    Path(training_id): Path<String>,
+) -> Result<StatusCode, TrainingServiceError> {
+    state.delete_training(&training_id).await?;
+
+    Ok(StatusCode::OK)
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/trainings",
+    tag = "training",
+    params(ListQuery<Training>),
+    responses(
+        (status = 200, description = "Page of the trainings of the caller", body = Page<Training>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_my_trainings(
+    State(state): State<Arc<TrainingService>>,
+    Extension(auth_context): Extension<AuthContext>,
+    ListQuery(params, _): ListQuery<Training>,
+) -> Result<Json<Page<Training>>, TrainingServiceError> {
+    Ok(Json(
+        state
+            .get_trainings_for_user(auth_context.user_id, &params)
+            .await?,
+    ))
+}
+
 async fn get_trainings_for_user_with_extension(
     State(state): State<Arc<TrainingService>>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<Vec<Training>>, StatusCode> {
-    match state.get_trainings_for_user(user_id).await {
-        Err(err) => {
-            error!("Error getting trainings for user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_trainings) => Ok(Json(user_trainings)),
-    }
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<Training>>, TrainingServiceError> {
+    let trainings = state
+        .get_trainings_for_user(auth_context.user_id, &ListParams::unbounded::<Training>())
+        .await?;
+
+    Ok(Json(trainings.items))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_identifier}/trainings",
+    tag = "training",
+    params(
+        ("user_identifier" = String, Path, description = "Id, email, identification or phone of the user, tried in that order"),
+        ListQuery<Training>,
+    ),
+    responses(
+        (status = 200, description = "Page of the trainings of the user", body = Page<Training>),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_user_trainings(
+    State(state): State<Arc<TrainingService>>,
+    Path(user_identification): Path<String>,
+    ListQuery(params, _): ListQuery<Training>,
+) -> Result<Json<Page<Training>>, TrainingServiceError> {
+    Ok(Json(
+        state
+            .get_trainings_for_user(user_identification, &params)
+            .await?,
+    ))
 }
 
 async fn get_trainings_for_user(
     State(state): State<Arc<TrainingService>>,
     Path(user_identification): Path<String>,
-) -> Result<Json<Vec<Training>>, StatusCode> {
-    match state.get_trainings_for_user(user_identification).await {
-        Err(err) => {
-            error!("Error getting trainings for user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(user_trainings) => Ok(Json(user_trainings)),
-    }
+) -> Result<Json<Vec<Training>>, TrainingServiceError> {
+    let trainings = state
+        .get_trainings_for_user(user_identification, &ListParams::unbounded::<Training>())
+        .await?;
+
+    Ok(Json(trainings.items))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/trainings",
+    tag = "training",
+    request_body = TrainingInfo,
+    responses(
+        (status = 200, description = "Id of the new training", body = String),
+        (status = 409, description = "`duplicate`", body = ErrorBody),
+    )
+)]
 async fn create_training(
     State(state): State<Arc<TrainingService>>,
     Json(training_info): Json<TrainingInfo>,
-) -> Result<Json<String>, StatusCode> {
+) -> Result<Json<String>, TrainingServiceError> {
     state
         .create_training(
             training_info.nombre_entrenamiento,
@@ -135,47 +299,290 @@ async fn create_training(
         )
         .await
         .map(Json)
-        .map_err(|err| {
-            error!("Error creating training: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/trainings/{training_id}/participants",
+    tag = "training",
+    params(("training_id" = String, Path)),
+    request_body = TrainingParticipant,
+    responses(
+        (status = 201, description = "The user was registered"),
+        (status = 409, description = "`user_already_registered`", body = ErrorBody),
+        (status = 422, description = "`invalid_reference`, the user or the training does not exist", body = ErrorBody),
+    )
+)]
+async fn register_participant(
+    State(state): State<Arc<TrainingService>>,
+    Path(training_id): Path<String>,
+    Json(participant): Json<TrainingParticipant>,
+) -> Result<StatusCode, TrainingServiceError> {
+    state
+        .register_user_in_training(training_id, participant.id_persona)
+        .await?;
+
+    Ok(StatusCode::CREATED)
 }
 
 async fn register_user_in_training(
     State(state): State<Arc<TrainingService>>,
     Json(registration): Json<TrainingRegistration>,
-) -> StatusCode {
-    match state
+) -> Result<StatusCode, TrainingServiceError> {
+    state
         .register_user_in_training(registration.id_entrenamiento, registration.id_persona)
-        .await
-    {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error registering user in training: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+        .await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trainings",
+    tag = "training",
+    params(ListQuery<Training>),
+    responses(
+        (status = 200, description = "Page of the trainings", body = Page<Training>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_trainings(
+    State(state): State<Arc<TrainingService>>,
+    ListQuery(params, _): ListQuery<Training>,
+) -> Result<Json<Page<Training>>, TrainingServiceError> {
+    state.list_trainings(&params).await.map(Json)
 }
 
 async fn get_all_trainings(
     State(state): State<Arc<TrainingService>>,
-) -> Result<Json<Vec<Training>>, StatusCode> {
-    state.get_all_trainings().await.map(Json).map_err(|err| {
-        error!("Error fetching all trainings: {err}");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })
+) -> Result<Json<Vec<Training>>, TrainingServiceError> {
+    let trainings = state
+        .list_trainings(&ListParams::unbounded::<Training>())
+        .await?;
+
+    Ok(Json(trainings.items))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/trainings/{training_id}/participants",
+    tag = "training",
+    params(("training_id" = String, Path)),
+    responses((status = 200, description = "Registrations of the training", body = Vec<TrainingRegistration>))
+)]
 async fn get_users_in_training(
     State(state): State<Arc<TrainingService>>,
     Path(id_entrenamiento): Path<String>,
-) -> Result<Json<Vec<TrainingRegistration>>, StatusCode> {
+) -> Result<Json<Vec<TrainingRegistration>>, TrainingServiceError> {
     state
         .get_users_in_training(id_entrenamiento)
         .await
         .map(Json)
-        .map_err(|err| {
-            error!("Error fetching users in training: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        test_harness::{TestApp, TestUser},
+        user_service::domain::UserRol,
+    };
+
+    use super::*;
+
+    async fn create_training(app: &TestApp, trainer: &TestUser) -> String {
+        let response = app
+            .post(
+                "/api/v1/trainings",
+                Some(&trainer.token),
+                json!({ "nombre_entrenamiento": "Fondo", "tiempo_minutos": 90 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        response.body.as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_create_and_list_trainings() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let training = json!({ "nombre_entrenamiento": "Fondo", "tiempo_minutos": 90 });
+        assert_eq!(
+            app.post("/api/v1/trainings", None, training.clone())
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/trainings", Some(&user.token), training)
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/trainings",
+                Some(&trainer.token),
+                json!({ "nombre_entrenamiento": "Fondo" })
+            )
+            .await
+            .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let id_entrenamiento = create_training(&app, &trainer).await;
+
+        let response = app.get("/api/v1/trainings", None).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.body["items"][0]["id_entrenamiento"],
+            json!(id_entrenamiento)
+        );
+
+        let uri = format!("/api/v1/trainings/{id_entrenamiento}");
+        assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+        let response = app.get(&uri, Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["tiempo_minutos"], 90);
+        let response = app
+            .get("/api/v1/trainings/no-existe", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "training_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_register_users_in_training() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let id_entrenamiento = create_training(&app, &trainer).await;
+
+        let uri = format!("/api/v1/trainings/{id_entrenamiento}/participants");
+        let participant = json!({ "id_persona": user.id_persona });
+        assert_eq!(
+            app.post(&uri, None, participant.clone()).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post(&uri, Some(&user.token), participant.clone())
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(&uri, Some(&trainer.token), participant.clone())
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+        let response = app.post(&uri, Some(&trainer.token), participant).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["code"], "user_already_registered");
+
+        assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+        let response = app.get(&uri, Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body[0]["id_persona"], json!(user.id_persona));
+
+        for uri in [
+            "/api/v1/users/me/trainings".to_string(),
+            format!("/api/v1/users/{}/trainings", user.correo),
+        ] {
+            assert_eq!(app.get(&uri, None).await.status, StatusCode::UNAUTHORIZED);
+            let response = app.get(&uri, Some(&user.token)).await;
+            assert_eq!(response.status, StatusCode::OK, "GET {uri}");
+            assert_eq!(
+                response.body["items"][0]["id_entrenamiento"],
+                json!(id_entrenamiento),
+                "GET {uri}"
+            );
+            assert_eq!(response.body["total"], 1, "GET {uri}");
+        }
+        let response = app
+            .get(
+                "/api/v1/users/nadie@unisabana.edu.co/trainings",
+                Some(&user.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "user_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_delete_training() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let id_entrenamiento = create_training(&app, &trainer).await;
+
+        let uri = format!("/api/v1/trainings/{id_entrenamiento}");
+        assert_eq!(
+            app.delete(&uri, None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&trainer.token)).await.status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.delete(&uri, Some(&admin.token)).await.status,
+            StatusCode::NO_CONTENT
+        );
+
+        let response = app.get("/api/v1/trainings", None).await;
+        assert_eq!(response.body["items"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_delete_unknown_training() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+
+        let response = app
+            .delete("/api/v1/trainings/no-existe", Some(&admin.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "training_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated_aliases() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+
+        let response = app
+            .post(
+                "/training",
+                Some(&trainer.token),
+                json!({ "nombre_entrenamiento": "Fondo", "tiempo_minutos": 90 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.headers.contains_key("deprecation"));
+        assert!(response.headers.contains_key("sunset"));
+        let id_entrenamiento = response.body.as_str().unwrap();
+
+        let registration = json!({
+            "id_entrenamiento": id_entrenamiento,
+            "id_persona": trainer.id_persona
+        });
+        assert_eq!(
+            app.post("/training/register", Some(&trainer.token), registration)
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+        let response = app
+            .get(
+                &format!("/training/users/{id_entrenamiento}"),
+                Some(&trainer.token),
+            )
+            .await;
+        assert_eq!(response.body[0]["id_persona"], json!(trainer.id_persona));
+        assert!(response.headers.contains_key("deprecation"));
+    }
 }
@@ -1,3 +1,7 @@
+use axum::response::{IntoResponse, Response};
+
+use crate::api_error::ApiError;
+
 use super::repository::err::TrainingRepositoryError;
 
 pub type Result<T> = std::result::Result<T, TrainingServiceError>;
@@ -6,6 +10,32 @@ pub type Result<T> = std::result::Result<T, TrainingServiceError>;
 pub enum TrainingServiceError {
     #[error("Training repository error: {0}")]
     TrainingRepositoryError(#[from] TrainingRepositoryError),
+    #[error("Transaction error: {0}")]
+    TransactionError(#[from] libsql::Error),
     #[error("Could not identify user with identificator: {0}")]
     UserNotIdentifiable(String),
 }
+
+impl IntoResponse for TrainingServiceError {
+    fn into_response(self) -> Response {
+        match &self {
+            TrainingServiceError::TrainingRepositoryError(
+                TrainingRepositoryError::TrainingNotFound,
+            ) => ApiError::not_found("training_not_found", &self),
+            TrainingServiceError::TrainingRepositoryError(
+                TrainingRepositoryError::UserAlreadyRegistered,
+            ) => ApiError::conflict("user_already_registered", &self),
+            TrainingServiceError::TrainingRepositoryError(TrainingRepositoryError::Duplicate {
+                field,
+            }) => ApiError::duplicate(field),
+            TrainingServiceError::TrainingRepositoryError(
+                TrainingRepositoryError::ForeignKeyViolation,
+            ) => ApiError::invalid_reference(),
+            TrainingServiceError::UserNotIdentifiable(_) => {
+                ApiError::not_found("user_not_found", &self)
+            }
+            _ => ApiError::internal(&self),
+        }
+        .into_response()
+    }
+}
@@ -1,13 +1,24 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::pagination::{FilterKind, Listable};
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Training {
     pub id_entrenamiento: String,
     pub nombre_entrenamiento: String,
     pub tiempo_minutos: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Listable for Training {
+    const SORT_FIELDS: &'static [&'static str] =
+        &["nombre_entrenamiento", "tiempo_minutos", "id_entrenamiento"];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] =
+        &[("nombre_entrenamiento", FilterKind::Text)];
+    const ID_FIELD: &'static str = "id_entrenamiento";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct TrainingRegistration {
     pub id_entrenamiento: String,
     pub id_persona: String,
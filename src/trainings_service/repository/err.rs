@@ -1,3 +1,5 @@
+use crate::database::from_libsql_error;
+
 pub type Result<T> = std::result::Result<T, TrainingRepositoryError>;
 
 #[derive(thiserror::Error, Debug)]
@@ -8,4 +10,12 @@ pub enum TrainingRepositoryError {
     TrainingNotFound,
     #[error("User already registered in training")]
     UserAlreadyRegistered,
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
 }
+
+from_libsql_error!(TrainingRepositoryError, |err: libsql::Error| {
+    TrainingRepositoryError::DatabaseError(err.to_string())
+});
@@ -0,0 +1,138 @@
+use async_trait::async_trait;
+
+use crate::{
+    in_memory::InMemoryStore,
+    pagination::{paginate, ListParams, Page},
+    trainings_service::model::{Training, TrainingRegistration},
+};
+
+use super::{
+    err::{Result, TrainingRepositoryError},
+    TrainingRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemoryTrainingRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryTrainingRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl TrainingRepository for InMemoryTrainingRepository {
+    async fn create_training(&self, training: Training) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        if tables
+            .entrenamiento
+            .contains_key(&training.id_entrenamiento)
+        {
+            return Err(TrainingRepositoryError::DatabaseError(format!(
+                "Training {} already exists",
+                training.id_entrenamiento
+            )));
+        }
+
+        tables
+            .entrenamiento
+            .insert(training.id_entrenamiento.clone(), training);
+
+        Ok(())
+    }
+
+    async fn register_user_in_training(&self, registration: TrainingRegistration) -> Result<()> {
+        let mut tables = self.store.tables();
+
+        let already_registered = tables.entrenamiento_persona.iter().any(|existing| {
+            existing.id_entrenamiento == registration.id_entrenamiento
+                && existing.id_persona == registration.id_persona
+        });
+
+        if already_registered {
+            return Err(TrainingRepositoryError::UserAlreadyRegistered);
+        }
+
+        tables.entrenamiento_persona.push(registration);
+
+        Ok(())
+    }
+
+    async fn list_trainings(&self, params: &ListParams) -> Result<Page<Training>> {
+        let trainings = self
+            .store
+            .tables()
+            .entrenamiento
+            .values()
+            .cloned()
+            .collect();
+
+        Ok(paginate(trainings, params))
+    }
+
+    async fn get_users_in_training(
+        &self,
+        id_entrenamiento: &str,
+    ) -> Result<Vec<TrainingRegistration>> {
+        Ok(self
+            .store
+            .tables()
+            .entrenamiento_persona
+            .iter()
+            .filter(|registration| registration.id_entrenamiento == id_entrenamiento)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_trainings_for_user(
+        &self,
+        user_id: &str,
+        params: &ListParams,
+    ) -> Result<Page<Training>> {
+        let tables = self.store.tables();
+
+        let trainings = tables
+            .entrenamiento_persona
+            .iter()
+            .filter(|registration| registration.id_persona == user_id)
+            .filter_map(|registration| {
+                tables
+                    .entrenamiento
+                    .get(&registration.id_entrenamiento)
+                    .cloned()
+            })
+            .collect();
+
+        Ok(paginate(trainings, params))
+    }
+
+    async fn delete_training(&self, training_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .entrenamiento
+            .remove(training_id)
+            .map(|_| ())
+            .ok_or(TrainingRepositoryError::TrainingNotFound)
+    }
+
+    async fn delete_training_registrations(&self, training_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .entrenamiento_persona
+            .retain(|registration| registration.id_entrenamiento != training_id);
+
+        Ok(())
+    }
+
+    async fn get_training(&self, training_id: &str) -> Result<Training> {
+        self.store
+            .tables()
+            .entrenamiento
+            .get(training_id)
+            .cloned()
+            .ok_or(TrainingRepositoryError::TrainingNotFound)
+    }
+}
@@ -1,3 +1,5 @@
+use crate::database::{DatabaseHandle, PooledConnection};
+use crate::pagination::{count_rows, ListParams, Page};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -9,22 +11,18 @@ use super::{err::Result, err::TrainingRepositoryError, TrainingRepository};
 
 #[derive(Clone)]
 pub struct TrainingRepositoryImpl {
-    db: Arc<libsql::Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl TrainingRepositoryImpl {
-    pub async fn new(db_url: &str, db_token: &str) -> Result<Arc<dyn TrainingRepository>> {
-        let db = libsql::Builder::new_remote(db_url.to_string(), db_token.to_string())
-            .build()
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<libsql::Connection> {
+    async fn get_connection(&self) -> Result<PooledConnection> {
         self.db
             .connect()
+            .await
             .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))
     }
 }
@@ -35,38 +33,47 @@ impl TrainingRepository for TrainingRepositoryImpl {
         let conn = self.get_connection().await?;
 
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_entrenamiento, tiempo_minutos, nombre_entrenamiento FROM entrenamiento WHERE id_entrenamiento = ?1",
                 libsql::params![training_id],
             )
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
-        if let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?
-        {
+        if let Some(row) = rows.next().await? {
             let training = de::from_row(&row)
                 .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
 
             Ok(training)
         } else {
-            Err(TrainingRepositoryError::DatabaseError(
-                "Value not found".to_string(),
-            ))
+            Err(TrainingRepositoryError::TrainingNotFound)
         }
     }
 
     async fn delete_training(&self, training_id: &str) -> Result<()> {
         let conn = self.get_connection().await?;
 
+        let deleted = conn
+            .execute(
+                "DELETE FROM entrenamiento WHERE id_entrenamiento = ?1",
+                params![training_id],
+            )
+            .await?;
+
+        if deleted == 0 {
+            return Err(TrainingRepositoryError::TrainingNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_training_registrations(&self, training_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
         conn.execute(
-            "DELETE FROM entrenamiento WHERE id_entrenamiento = ?1",
+            "DELETE FROM entrenamiento_persona WHERE id_entrenamiento = ?1",
             params![training_id],
         )
-        .await
-        .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(())
     }
@@ -77,8 +84,7 @@ impl TrainingRepository for TrainingRepositoryImpl {
             "INSERT INTO entrenamiento (id_entrenamiento, tiempo_minutos, nombre_entrenamiento) VALUES (?1, ?2, ?3)",
             libsql::params![training.id_entrenamiento, training.tiempo_minutos, training.nombre_entrenamiento],
         )
-        .await
-        .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(())
     }
@@ -90,41 +96,49 @@ impl TrainingRepository for TrainingRepositoryImpl {
             libsql::params![registration.id_entrenamiento, registration.id_persona],
         )
         .await
-        .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+        .map_err(|err| match TrainingRepositoryError::from(err) {
+            // The primary key of the table is the pair of ids
+            TrainingRepositoryError::Duplicate { .. } => {
+                TrainingRepositoryError::UserAlreadyRegistered
+            }
+            err => err,
+        })?;
 
         Ok(())
     }
 
-    async fn get_all_trainings(&self) -> Result<Vec<Training>> {
+    async fn list_trainings(&self, params: &ListParams) -> Result<Page<Training>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("");
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM entrenamiento WHERE {conditions}"),
+            params.filter_values(),
+        )
+        .await?;
+
         let mut rows = conn
-            .query(
-                "SELECT id_entrenamiento, tiempo_minutos, nombre_entrenamiento FROM entrenamiento",
-                libsql::params![],
+            .read(
+                &format!(
+                    "SELECT id_entrenamiento, tiempo_minutos, nombre_entrenamiento
+                    FROM entrenamiento WHERE {conditions} {}",
+                    params.sql_page("")
+                ),
+                params.filter_values(),
             )
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut trainings = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             trainings.push(Training {
-                id_entrenamiento: row
-                    .get(0)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
-                tiempo_minutos: row
-                    .get(1)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
-                nombre_entrenamiento: row
-                    .get(2)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
+                id_entrenamiento: row.get(0)?,
+                tiempo_minutos: row.get(1)?,
+                nombre_entrenamiento: row.get(2)?,
             });
         }
 
-        Ok(trainings)
+        Ok(Page::new(trainings, total, params))
     }
 
     async fn get_users_in_training(
@@ -133,65 +147,66 @@ impl TrainingRepository for TrainingRepositoryImpl {
     ) -> Result<Vec<TrainingRegistration>> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_entrenamiento, id_persona FROM entrenamiento_persona WHERE id_entrenamiento = ?1",
                 libsql::params![id_entrenamiento],
             )
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut registrations = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             registrations.push(TrainingRegistration {
-                id_entrenamiento: row
-                    .get(0)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
-                id_persona: row
-                    .get(1)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
+                id_entrenamiento: row.get(0)?,
+                id_persona: row.get(1)?,
             });
         }
 
         Ok(registrations)
     }
 
-    async fn get_trainings_for_user(&self, user_id: &str) -> Result<Vec<Training>> {
+    async fn get_trainings_for_user(
+        &self,
+        user_id: &str,
+        params: &ListParams,
+    ) -> Result<Page<Training>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("entrenamiento.");
+        let mut values = vec![libsql::Value::Text(user_id.to_string())];
+        values.extend(params.filter_values());
+
+        let total = count_rows(
+            &conn,
+            &format!(
+                "SELECT COUNT(*) FROM entrenamiento
+                 INNER JOIN entrenamiento_persona ON entrenamiento.id_entrenamiento = entrenamiento_persona.id_entrenamiento
+                 WHERE entrenamiento_persona.id_persona = ? AND {conditions}"
+            ),
+            values.clone(),
+        )
+        .await?;
 
         let mut rows = conn
-            .query(
-                "SELECT entrenamiento.id_entrenamiento, entrenamiento.nombre_entrenamiento, entrenamiento.tiempo_minutos
-                 FROM entrenamiento
-                 INNER JOIN entrenamiento_persona ON entrenamiento.id_entrenamiento = entrenamiento_persona.id_entrenamiento
-                 WHERE entrenamiento_persona.id_persona = ?1",
-                libsql::params![user_id],
+            .read(
+                &format!(
+                    "SELECT entrenamiento.id_entrenamiento, entrenamiento.nombre_entrenamiento, entrenamiento.tiempo_minutos
+                     FROM entrenamiento
+                     INNER JOIN entrenamiento_persona ON entrenamiento.id_entrenamiento = entrenamiento_persona.id_entrenamiento
+                     WHERE entrenamiento_persona.id_persona = ? AND {conditions} {}",
+                    params.sql_page("entrenamiento.")
+                ),
+                values,
             )
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut trainings = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             trainings.push(Training {
-                id_entrenamiento: row
-                    .get(0)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
-                nombre_entrenamiento: row
-                    .get(1)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
-                tiempo_minutos: row
-                    .get(2)
-                    .map_err(|e| TrainingRepositoryError::DatabaseError(e.to_string()))?,
+                id_entrenamiento: row.get(0)?,
+                nombre_entrenamiento: row.get(1)?,
+                tiempo_minutos: row.get(2)?,
             });
         }
 
-        Ok(trainings)
+        Ok(Page::new(trainings, total, params))
     }
 }
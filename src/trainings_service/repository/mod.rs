@@ -1,9 +1,12 @@
+use crate::pagination::{ListParams, Page};
 use crate::trainings_service::model::{Training, TrainingRegistration};
 use async_trait::async_trait;
 use mockall::automock;
 
 pub mod err;
 use err::Result;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod lib_sql_implementation;
 
 #[automock]
@@ -15,8 +18,8 @@ pub trait TrainingRepository: Send + Sync {
     /// Registers a user in a training session.
     async fn register_user_in_training(&self, registration: TrainingRegistration) -> Result<()>;
 
-    /// Retrieves all training sessions.
-    async fn get_all_trainings(&self) -> Result<Vec<Training>>;
+    /// Retrieves one page of the training sessions.
+    async fn list_trainings(&self, params: &ListParams) -> Result<Page<Training>>;
 
     /// Retrieves all users registered in a specific training session.
     async fn get_users_in_training(
@@ -24,9 +27,17 @@ pub trait TrainingRepository: Send + Sync {
         id_entrenamiento: &str,
     ) -> Result<Vec<TrainingRegistration>>;
 
-    async fn get_trainings_for_user(&self, user_id: &str) -> Result<Vec<Training>>;
+    /// Retrieves one page of the training sessions a user is registered in.
+    async fn get_trainings_for_user(
+        &self,
+        user_id: &str,
+        params: &ListParams,
+    ) -> Result<Page<Training>>;
 
     async fn delete_training(&self, training_id: &str) -> Result<()>;
 
+    /// Removes every user registration of a training session.
+    async fn delete_training_registrations(&self, training_id: &str) -> Result<()>;
+
     async fn get_training(&self, training_id: &str) -> Result<Training>;
 }
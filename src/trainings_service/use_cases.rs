@@ -2,6 +2,8 @@ use std::sync::Arc;
 
 use uuid::Uuid;
 
+use crate::database::UnitOfWork;
+use crate::pagination::{ListParams, Page};
 use crate::unique_identifier_service::usecases::UniqueIdentifier;
 
 use super::err::{Result, TrainingServiceError};
@@ -13,16 +15,19 @@ use super::repository::TrainingRepository;
 pub struct TrainingService {
     training_repository: Arc<dyn TrainingRepository>,
     unique_identifier: Arc<dyn UniqueIdentifier>,
+    unit_of_work: UnitOfWork,
 }
 
 impl TrainingService {
     pub fn new(
         training_repository: Arc<dyn TrainingRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
+        unit_of_work: UnitOfWork,
     ) -> Self {
         Self {
             training_repository,
             unique_identifier,
+            unit_of_work,
         }
     }
 
@@ -32,18 +37,27 @@ impl TrainingService {
         Ok(training)
     }
 
+    /// Deletes the training together with the registrations of its users.
     pub async fn delete_training(&self, training_id: &str) -> Result<()> {
-        self.training_repository
-            .delete_training(training_id)
-            .await?;
-
-        Ok(())
+        self.unit_of_work
+            .run(async {
+                self.training_repository
+                    .delete_training_registrations(training_id)
+                    .await?;
+                self.training_repository
+                    .delete_training(training_id)
+                    .await?;
+
+                Ok(())
+            })
+            .await
     }
 
     pub async fn get_trainings_for_user(
         &self,
         user_identification: String,
-    ) -> Result<Vec<Training>> {
+        params: &ListParams,
+    ) -> Result<Page<Training>> {
         let user_id = self
             .unique_identifier
             .identify(user_identification.clone())
@@ -60,7 +74,7 @@ impl TrainingService {
 
         let user_trainings = self
             .training_repository
-            .get_trainings_for_user(&user_id)
+            .get_trainings_for_user(&user_id, params)
             .await?;
 
         Ok(user_trainings)
@@ -99,8 +113,8 @@ impl TrainingService {
         Ok(())
     }
 
-    pub async fn get_all_trainings(&self) -> Result<Vec<Training>> {
-        Ok(self.training_repository.get_all_trainings().await?)
+    pub async fn list_trainings(&self, params: &ListParams) -> Result<Page<Training>> {
+        Ok(self.training_repository.list_trainings(params).await?)
     }
 
     pub async fn get_users_in_training(
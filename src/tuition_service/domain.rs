@@ -1,13 +1,25 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+use crate::pagination::{FilterKind, Listable};
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct Tuition {
+    pub id_matricula: i64,
     pub id_persona: String,
     pub monto_usd: f64,
     pub fecha_inscripccion: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+/// The lists are always of one user, so `id_persona` is not a filter.
+impl Listable for Tuition {
+    const SORT_FIELDS: &'static [&'static str] =
+        &["fecha_inscripccion", "monto_usd", "id_matricula"];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = &[];
+    const ID_FIELD: &'static str = "id_matricula";
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct TuitionInfo {
     pub id_persona: String,
     pub monto_usd: f64,
@@ -1,39 +1,39 @@
+use crate::extract::{Json, Path};
 use crate::{
-    auth_middleware::auth_middleware, global_traits::HttpService,
+    api_error::ErrorBody,
+    auth_middleware::AuthContext,
+    global_traits::HttpService,
+    pagination::{ListParams, ListQuery, Page},
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
     unique_identifier_service::usecases::UniqueIdentifier,
 };
 use async_trait::async_trait;
-use axum::{
-    extract::{Json, Path, State},
-    http::StatusCode,
-    middleware,
-    routing::{get, post},
-    Extension, Router,
-};
+use axum::{extract::State, http::StatusCode, Extension, Router};
 use std::sync::Arc;
-use tracing::error;
+use utoipa::OpenApi;
 
 use super::{
     domain::{Tuition, TuitionInfo},
+    err::TuitionServiceError,
     repository::TuitionRepository,
     use_cases::TuitionService,
 };
 
 pub struct TuitionHttpServer {
     tuition_service: TuitionService,
-    token_key: String,
+    access_control: AccessControl,
 }
 
 impl TuitionHttpServer {
     pub async fn new(
         tuition_repository: Arc<dyn TuitionRepository>,
         unique_identifier: Arc<dyn UniqueIdentifier>,
-        token_key: &str,
+        access_control: AccessControl,
     ) -> Self {
         let tuition_service = TuitionService::new(tuition_repository, unique_identifier.clone());
         Self {
             tuition_service,
-            token_key: token_key.to_string(),
+            access_control,
         }
     }
 }
@@ -41,93 +41,383 @@ impl TuitionHttpServer {
 #[async_trait]
 impl HttpService for TuitionHttpServer {
     fn get_router(&self) -> Router {
-        Router::new()
-            .route("/tuition", get(get_tuitions_for_user_with_extension))
-            .route(
-                "/tuition/user/recent",
-                get(get_most_recent_tuition_with_extension),
-            )
-            .layer(middleware::from_fn_with_state(
-                self.token_key.clone(),
-                auth_middleware,
-            ))
-            .route("/tuition", post(create_tuition))
-            .route(
-                "/tuition/user/{user_identifier}",
-                get(get_tuitions_for_user),
-            )
-            .route(
-                "/tuition/user/{id_persona}/recent",
-                get(get_most_recent_tuition),
+        self.access_control
+            .build_router("tuition", routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("tuition", legacy_routes()),
             )
             .with_state(self.tuition_service.clone())
     }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(TuitionApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<TuitionService>> {
+    vec![
+        RouteAccess::post("/api/v1/tuitions", Access::admin(), create_tuition),
+        RouteAccess::get(
+            "/api/v1/users/me/tuitions",
+            Access::Authenticated,
+            list_my_tuitions,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/me/tuitions/recent",
+            Access::Authenticated,
+            get_most_recent_tuition_with_extension,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/{user_identifier}/tuitions",
+            Access::staff(),
+            list_user_tuitions,
+        ),
+        RouteAccess::get(
+            "/api/v1/users/{user_identifier}/tuitions/recent",
+            Access::staff(),
+            get_most_recent_tuition,
+        ),
+    ]
 }
 
+/// Paths from before `/api/v1`, removed once the mobile clients are migrated.
+fn legacy_routes() -> Vec<RouteAccess<TuitionService>> {
+    vec![
+        RouteAccess::get(
+            "/tuition",
+            Access::Authenticated,
+            get_tuitions_for_user_with_extension,
+        ),
+        RouteAccess::get(
+            "/tuition/user/recent",
+            Access::Authenticated,
+            get_most_recent_tuition_with_extension,
+        ),
+        RouteAccess::post("/tuition", Access::admin(), create_tuition),
+        RouteAccess::get(
+            "/tuition/user/{user_identifier}",
+            Access::staff(),
+            get_tuitions_for_user,
+        ),
+        RouteAccess::get(
+            "/tuition/user/{id_persona}/recent",
+            Access::staff(),
+            get_most_recent_tuition,
+        ),
+    ]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_my_tuitions,
+        get_most_recent_tuition_with_extension,
+        create_tuition,
+        list_user_tuitions,
+        get_most_recent_tuition,
+    ),
+    tags((name = "tuition", description = "Tuitions paid by the users"))
+)]
+struct TuitionApi;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tuitions",
+    tag = "tuition",
+    request_body = TuitionInfo,
+    responses(
+        (status = 201, description = "The tuition was created"),
+        (status = 422, description = "`invalid_reference`, the user does not exist", body = ErrorBody),
+    )
+)]
 async fn create_tuition(
     State(state): State<TuitionService>,
     Json(payload): Json<TuitionInfo>,
-) -> StatusCode {
-    match state
+) -> Result<StatusCode, TuitionServiceError> {
+    state
         .create_tuition(payload.id_persona, payload.monto_usd)
-        .await
-    {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error creating tuition: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+        .await?;
+
+    Ok(StatusCode::CREATED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/tuitions",
+    tag = "tuition",
+    params(ListQuery<Tuition>),
+    responses(
+        (status = 200, description = "Page of the tuitions of the caller", body = Page<Tuition>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_my_tuitions(
+    State(state): State<TuitionService>,
+    Extension(auth_context): Extension<AuthContext>,
+    ListQuery(params, _): ListQuery<Tuition>,
+) -> Result<Json<Page<Tuition>>, TuitionServiceError> {
+    Ok(Json(
+        state
+            .get_tuitions_for_user(auth_context.user_id, &params)
+            .await?,
+    ))
 }
 
 async fn get_tuitions_for_user_with_extension(
     State(state): State<TuitionService>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<Vec<Tuition>>, StatusCode> {
-    match state.get_tuitions_for_user(user_id).await {
-        Ok(tuitions) => Ok(Json(tuitions)),
-        Err(err) => {
-            error!("Error fetching tuitions for user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<Tuition>>, TuitionServiceError> {
+    let tuitions = state
+        .get_tuitions_for_user(auth_context.user_id, &ListParams::unbounded::<Tuition>())
+        .await?;
+
+    Ok(Json(tuitions.items))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_identifier}/tuitions",
+    tag = "tuition",
+    params(
+        ("user_identifier" = String, Path, description = "Id, email, identification or phone of the user, tried in that order"),
+        ListQuery<Tuition>,
+    ),
+    responses(
+        (status = 200, description = "Page of the tuitions of the user", body = Page<Tuition>),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_user_tuitions(
+    State(state): State<TuitionService>,
+    Path(user_identifier): Path<String>,
+    ListQuery(params, _): ListQuery<Tuition>,
+) -> Result<Json<Page<Tuition>>, TuitionServiceError> {
+    Ok(Json(
+        state
+            .get_tuitions_for_user(user_identifier, &params)
+            .await?,
+    ))
 }
 
 async fn get_tuitions_for_user(
     State(state): State<TuitionService>,
     Path(user_identifier): Path<String>,
-) -> Result<Json<Vec<Tuition>>, StatusCode> {
-    match state.get_tuitions_for_user(user_identifier).await {
-        Ok(tuitions) => Ok(Json(tuitions)),
-        Err(err) => {
-            error!("Error fetching tuitions for user: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<Vec<Tuition>>, TuitionServiceError> {
+    let tuitions = state
+        .get_tuitions_for_user(user_identifier, &ListParams::unbounded::<Tuition>())
+        .await?;
+
+    Ok(Json(tuitions.items))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_identifier}/tuitions/recent",
+    tag = "tuition",
+    params(("user_identifier" = String, Path, description = "Id, email, identification or phone of the user, tried in that order")),
+    responses(
+        (status = 200, description = "Last tuition of the user", body = Tuition),
+        (status = 404, description = "`tuition_not_found` or `user_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_most_recent_tuition(
     State(state): State<TuitionService>,
     Path(id_persona): Path<String>,
-) -> Result<Json<Tuition>, StatusCode> {
-    match state.get_most_recent_tuition(id_persona).await {
-        Ok(tuition) => Ok(Json(tuition)),
-        Err(err) => {
-            error!("Error fetching most recent tuition: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<Tuition>, TuitionServiceError> {
+    Ok(Json(state.get_most_recent_tuition(id_persona).await?))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/tuitions/recent",
+    tag = "tuition",
+    responses(
+        (status = 200, description = "Last tuition of the caller", body = Tuition),
+        (status = 404, description = "`tuition_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_most_recent_tuition_with_extension(
     State(state): State<TuitionService>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<Tuition>, StatusCode> {
-    match state.get_most_recent_tuition(user_id).await {
-        Ok(tuition) => Ok(Json(tuition)),
-        Err(err) => {
-            error!("Error fetching most recent tuition: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Tuition>, TuitionServiceError> {
+    Ok(Json(
+        state.get_most_recent_tuition(auth_context.user_id).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{test_harness::TestApp, user_service::domain::UserRol};
+
+    #[tokio::test]
+    async fn test_create_tuition() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+
+        let tuition = json!({ "id_persona": trainer.id_persona, "monto_usd": 20.0 });
+        assert_eq!(
+            app.post("/api/v1/tuitions", None, tuition.clone())
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/tuitions", Some(&trainer.token), tuition.clone())
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/tuitions",
+                Some(&admin.token),
+                json!({ "monto_usd": 20.0 })
+            )
+            .await
+            .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let response = app
+            .post(
+                "/api/v1/tuitions",
+                Some(&admin.token),
+                json!({ "id_persona": "no-existe", "monto_usd": 20.0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["code"], "invalid_reference");
+        assert_eq!(
+            app.post("/api/v1/tuitions", Some(&admin.token), tuition)
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_own_tuitions() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        for uri in [
+            "/api/v1/users/me/tuitions",
+            "/api/v1/users/me/tuitions/recent",
+        ] {
+            assert_eq!(app.get(uri, None).await.status, StatusCode::UNAUTHORIZED);
         }
+        let response = app
+            .get("/api/v1/users/me/tuitions/recent", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "tuition_not_found");
+
+        let response = app
+            .post(
+                "/api/v1/tuitions",
+                Some(&admin.token),
+                json!({ "id_persona": user.id_persona, "monto_usd": 20.0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        let response = app
+            .get("/api/v1/users/me/tuitions", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["items"].as_array().unwrap().len(), 1);
+        assert_eq!(response.body["total"], 1);
+
+        let response = app
+            .get("/api/v1/users/me/tuitions/recent", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["monto_usd"], 20.0);
+    }
+
+    #[tokio::test]
+    async fn test_tuitions_of_other_users() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let response = app
+            .post(
+                "/api/v1/tuitions",
+                Some(&admin.token),
+                json!({ "id_persona": user.id_persona, "monto_usd": 20.0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        let tuitions = format!("/api/v1/users/{}/tuitions", user.correo);
+        let recent = format!("/api/v1/users/{}/tuitions/recent", user.id_persona);
+        for uri in [&tuitions, &recent] {
+            assert_eq!(app.get(uri, None).await.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                app.get(uri, Some(&user.token)).await.status,
+                StatusCode::FORBIDDEN,
+                "GET {uri}"
+            );
+        }
+
+        let response = app.get(&tuitions, Some(&trainer.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.body["items"][0]["id_persona"],
+            json!(user.id_persona)
+        );
+        let response = app
+            .get(
+                "/api/v1/users/nadie@unisabana.edu.co/tuitions",
+                Some(&trainer.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "user_not_found");
+
+        let response = app.get(&recent, Some(&trainer.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["monto_usd"], 20.0);
+        let response = app
+            .get(
+                &format!("/api/v1/users/{}/tuitions/recent", trainer.id_persona),
+                Some(&trainer.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "tuition_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated_aliases() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+
+        let response = app
+            .post(
+                "/tuition",
+                Some(&admin.token),
+                json!({ "id_persona": admin.id_persona, "monto_usd": 20.0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+        assert!(response.headers.contains_key("deprecation"));
+
+        let response = app
+            .get(
+                &format!("/tuition/user/{}/recent", admin.id_persona),
+                Some(&admin.token),
+            )
+            .await;
+        assert_eq!(response.body["monto_usd"], 20.0);
+        assert!(response.headers.contains_key("sunset"));
     }
 }
@@ -1,5 +1,8 @@
+use axum::response::{IntoResponse, Response};
 use thiserror::Error;
 
+use crate::api_error::ApiError;
+
 use super::repository::err::TuitionRepositoryError;
 
 pub type Result<T> = std::result::Result<T, TuitionServiceError>;
@@ -11,3 +14,24 @@ pub enum TuitionServiceError {
     #[error("Could not identify user with identificator: {0}")]
     UserNotIdentifiable(String),
 }
+
+impl IntoResponse for TuitionServiceError {
+    fn into_response(self) -> Response {
+        match &self {
+            TuitionServiceError::TuitionRepositoryError(
+                TuitionRepositoryError::TuitionNotFound,
+            ) => ApiError::not_found("tuition_not_found", &self),
+            TuitionServiceError::TuitionRepositoryError(TuitionRepositoryError::Duplicate {
+                field,
+            }) => ApiError::duplicate(field),
+            TuitionServiceError::TuitionRepositoryError(
+                TuitionRepositoryError::ForeignKeyViolation,
+            ) => ApiError::invalid_reference(),
+            TuitionServiceError::UserNotIdentifiable(_) => {
+                ApiError::not_found("user_not_found", &self)
+            }
+            _ => ApiError::internal(&self),
+        }
+        .into_response()
+    }
+}
@@ -1,3 +1,5 @@
+use crate::database::from_libsql_error;
+
 pub type Result<T> = std::result::Result<T, TuitionRepositoryError>;
 
 #[derive(thiserror::Error, Debug)]
@@ -6,4 +8,12 @@ pub enum TuitionRepositoryError {
     DatabaseError(String),
     #[error("Tuition not found")]
     TuitionNotFound,
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
 }
+
+from_libsql_error!(TuitionRepositoryError, |err: libsql::Error| {
+    TuitionRepositoryError::DatabaseError(err.to_string())
+});
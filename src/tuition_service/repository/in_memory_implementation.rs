@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::{
+    in_memory::{InMemoryStore, MatriculaRow},
+    pagination::{paginate, ListParams, Page},
+    tuition_service::domain::{Tuition, TuitionInfo},
+};
+
+use super::{
+    err::{Result, TuitionRepositoryError},
+    TuitionRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemoryTuitionRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryTuitionRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+}
+
+impl From<&MatriculaRow> for Tuition {
+    fn from(row: &MatriculaRow) -> Self {
+        Tuition {
+            id_matricula: row.id_matricula,
+            id_persona: row.id_persona.clone(),
+            monto_usd: row.monto_usd,
+            fecha_inscripccion: row.fecha_inscripccion.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl TuitionRepository for InMemoryTuitionRepository {
+    async fn create_tuition(&self, tuition: TuitionInfo) -> Result<()> {
+        let mut tables = self.store.tables();
+        // Same as the AUTOINCREMENT key and the CURRENT_DATE default of the columns
+        let id_matricula = tables
+            .matricula
+            .iter()
+            .map(|row| row.id_matricula)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        tables.matricula.push(MatriculaRow {
+            id_matricula,
+            id_persona: tuition.id_persona,
+            monto_usd: tuition.monto_usd,
+            fecha_inscripccion: Utc::now().date_naive(),
+        });
+
+        Ok(())
+    }
+
+    async fn get_tuitions_for_user(
+        &self,
+        id_persona: &str,
+        params: &ListParams,
+    ) -> Result<Page<Tuition>> {
+        let tuitions = self
+            .store
+            .tables()
+            .matricula
+            .iter()
+            .filter(|row| row.id_persona == id_persona)
+            .map(Tuition::from)
+            .collect();
+
+        Ok(paginate(tuitions, params))
+    }
+
+    async fn get_most_recent_tuition(&self, id_persona: &str) -> Result<Tuition> {
+        self.store
+            .tables()
+            .matricula
+            .iter()
+            .filter(|row| row.id_persona == id_persona)
+            .max_by_key(|row| row.fecha_inscripccion)
+            .map(Tuition::from)
+            .ok_or(TuitionRepositoryError::TuitionNotFound)
+    }
+}
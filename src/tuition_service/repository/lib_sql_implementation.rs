@@ -1,3 +1,5 @@
+use crate::database::{DatabaseHandle, PooledConnection};
+use crate::pagination::{count_rows, ListParams, Page};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -12,22 +14,18 @@ use super::{
 
 #[derive(Clone)]
 pub struct TuitionRepositoryImpl {
-    db: Arc<libsql::Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl TuitionRepositoryImpl {
-    pub async fn new(db_url: &str, db_token: &str) -> Result<Arc<dyn TuitionRepository>> {
-        let db = libsql::Builder::new_remote(db_url.to_string(), db_token.to_string())
-            .build()
-            .await
-            .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<libsql::Connection> {
+    async fn get_connection(&self) -> Result<PooledConnection> {
         self.db
             .connect()
+            .await
             .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))
     }
 }
@@ -40,75 +38,73 @@ impl TuitionRepository for TuitionRepositoryImpl {
             "INSERT INTO matricula (id_persona, monto_usd) VALUES (?1, ?2)",
             libsql::params![tuition.id_persona, tuition.monto_usd,],
         )
-        .await
-        .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?;
+        .await?;
 
         Ok(())
     }
 
-    async fn get_tuitions_for_user(&self, id_persona: &str) -> Result<Vec<Tuition>> {
+    async fn get_tuitions_for_user(
+        &self,
+        id_persona: &str,
+        params: &ListParams,
+    ) -> Result<Page<Tuition>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("");
+        let mut values = vec![libsql::Value::Text(id_persona.to_string())];
+        values.extend(params.filter_values());
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM matricula WHERE id_persona = ? AND {conditions}"),
+            values.clone(),
+        )
+        .await?;
+
         let mut rows = conn
-            .query(
-                "SELECT id_persona, monto_usd, fecha_inscripccion FROM matricula WHERE id_persona = ?1",
-                libsql::params![id_persona],
+            .read(
+                &format!(
+                    "SELECT id_matricula, id_persona, monto_usd, fecha_inscripccion
+                    FROM matricula WHERE id_persona = ? AND {conditions} {}",
+                    params.sql_page("")
+                ),
+                values,
             )
-            .await
-            .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut tuitions = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?
-        {
+        while let Some(row) = rows.next().await? {
             tuitions.push(Tuition {
-                id_persona: row
-                    .get(0)
-                    .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?,
-                monto_usd: row
-                    .get(1)
-                    .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?,
-                fecha_inscripccion: row
-                    .get(2)
-                    .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?,
+                id_matricula: row.get(0)?,
+                id_persona: row.get(1)?,
+                monto_usd: row.get(2)?,
+                fecha_inscripccion: row.get(3)?,
             });
         }
 
-        Ok(tuitions)
+        Ok(Page::new(tuitions, total, params))
     }
 
-    async fn get_most_recent_tuition(&self, id_persona: &String) -> Result<Tuition> {
+    async fn get_most_recent_tuition(&self, id_persona: &str) -> Result<Tuition> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
-                "SELECT id_persona, monto_usd, fecha_inscripccion FROM matricula WHERE id_persona = ?1",
-                libsql::params![id_persona.clone()],
+            .read(
+                "SELECT id_matricula, id_persona, monto_usd, fecha_inscripccion FROM matricula WHERE id_persona = ?1",
+                libsql::params![id_persona],
             )
-            .await
-            .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?;
+            .await?;
 
         let mut tuitions = Vec::new();
-        while let Some(row) = rows
-            .next()
-            .await
-            .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?
-        {
-            let fecha_inscripccion_str: String = row
-                .get(2)
-                .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?;
+        while let Some(row) = rows.next().await? {
+            let fecha_inscripccion_str: String = row.get(3)?;
             let fecha_inscripccion = NaiveDate::parse_from_str(&fecha_inscripccion_str, "%Y-%m-%d")
                 .map_err(|e| {
                     TuitionRepositoryError::DatabaseError(format!("Invalid date format: {}", e))
                 })?;
 
             let tuition = Tuition {
-                id_persona: row
-                    .get(0)
-                    .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?,
-                monto_usd: row
-                    .get(1)
-                    .map_err(|e| TuitionRepositoryError::DatabaseError(e.to_string()))?,
+                id_matricula: row.get(0)?,
+                id_persona: row.get(1)?,
+                monto_usd: row.get(2)?,
                 fecha_inscripccion: fecha_inscripccion.to_string(),
             };
             tuitions.push((fecha_inscripccion, tuition));
@@ -1,16 +1,24 @@
 use async_trait::async_trait;
 
 use super::domain::{Tuition, TuitionInfo};
+use crate::pagination::{ListParams, Page};
 use err::Result;
 
 pub mod err;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod lib_sql_implementation;
 
 #[async_trait]
 pub trait TuitionRepository: Send + Sync {
     async fn create_tuition(&self, tuition: TuitionInfo) -> Result<()>;
 
-    async fn get_tuitions_for_user(&self, id_persona: &str) -> Result<Vec<Tuition>>;
+    /// Retrieves one page of the tuitions paid by a user.
+    async fn get_tuitions_for_user(
+        &self,
+        id_persona: &str,
+        params: &ListParams,
+    ) -> Result<Page<Tuition>>;
 
-    async fn get_most_recent_tuition(&self, id_persona: &String) -> Result<Tuition>;
+    async fn get_most_recent_tuition(&self, id_persona: &str) -> Result<Tuition>;
 }
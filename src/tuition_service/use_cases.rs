@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use crate::pagination::{ListParams, Page};
 use crate::unique_identifier_service::usecases::UniqueIdentifier;
 
 use super::domain::TuitionInfo;
@@ -35,7 +36,11 @@ impl TuitionService {
         Ok(())
     }
 
-    pub async fn get_tuitions_for_user(&self, user_identifier: String) -> Result<Vec<Tuition>> {
+    pub async fn get_tuitions_for_user(
+        &self,
+        user_identifier: String,
+        params: &ListParams,
+    ) -> Result<Page<Tuition>> {
         let id_persona = match self
             .unique_identifier
             .identify(user_identifier.clone())
@@ -47,7 +52,7 @@ impl TuitionService {
 
         Ok(self
             .tuition_repository
-            .get_tuitions_for_user(&id_persona)
+            .get_tuitions_for_user(&id_persona, params)
             .await?)
     }
 
@@ -0,0 +1,297 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+use utoipa::ToSchema;
+
+use super::usecases::UniqueIdentifier;
+
+#[derive(Debug, Clone)]
+pub struct IdentifierCacheConfig {
+    /// How long a resolved identifier is trusted
+    pub ttl: Duration,
+    /// Unknown identifiers are kept less, the user can sign up in the meantime
+    pub negative_ttl: Duration,
+    pub max_entries: usize,
+}
+
+impl Default for IdentifierCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(300),
+            negative_ttl: Duration::from_secs(10),
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IdentifierCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+struct CacheEntry {
+    /// `None` when no user has the identifier
+    user_id: Option<String>,
+    expires_at: Instant,
+}
+
+/// Remembers what the wrapped chain resolved, so a request with an identifier does not
+/// walk the chain and query the database every time.
+pub struct CachedIdentifier {
+    inner: Arc<dyn UniqueIdentifier>,
+    config: IdentifierCacheConfig,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Bumped when the identifiers change, a lookup started before is not stored
+    generation: AtomicU64,
+}
+
+impl CachedIdentifier {
+    pub fn new(inner: Arc<dyn UniqueIdentifier>, config: IdentifierCacheConfig) -> Self {
+        Self {
+            inner,
+            config,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn stats(&self) -> IdentifierCacheStats {
+        IdentifierCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries().len(),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn cached(&self, identification_token: &str) -> Option<Option<String>> {
+        let mut entries = self.entries();
+        let entry = entries.get(identification_token)?;
+
+        if entry.expires_at <= Instant::now() {
+            entries.remove(identification_token);
+            return None;
+        }
+
+        Some(entry.user_id.clone())
+    }
+
+    fn store(&self, identification_token: String, user_id: Option<String>, generation: u64) {
+        let now = Instant::now();
+        let ttl = if user_id.is_some() {
+            self.config.ttl
+        } else {
+            self.config.negative_ttl
+        };
+
+        let mut entries = self.entries();
+        // Compared under the lock of the entries, the same one the invalidation takes
+        if generation != self.generation.load(Ordering::Acquire) {
+            return;
+        }
+        if entries.len() >= self.config.max_entries {
+            entries.retain(|_, entry| entry.expires_at > now);
+        }
+        if entries.len() >= self.config.max_entries {
+            let first_to_expire = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(token, _)| token.clone());
+
+            if let Some(token) = first_to_expire {
+                entries.remove(&token);
+            }
+        }
+
+        entries.insert(
+            identification_token,
+            CacheEntry {
+                user_id,
+                expires_at: now + ttl,
+            },
+        );
+    }
+}
+
+#[async_trait]
+impl UniqueIdentifier for CachedIdentifier {
+    async fn identify(&self, identification_token: String) -> Option<String> {
+        if let Some(user_id) = self.cached(&identification_token) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return user_id;
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let generation = self.generation.load(Ordering::Acquire);
+        let user_id = self.inner.identify(identification_token.clone()).await;
+        self.store(identification_token, user_id.clone(), generation);
+
+        user_id
+    }
+
+    fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+        Some(self.inner.clone())
+    }
+
+    fn identifiers_changed(&self) {
+        let mut entries = self.entries();
+        self.generation.fetch_add(1, Ordering::Release);
+        entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    /// Knows the users `a` and `b`, counts the lookups that reach it.
+    struct CountingIdentifier {
+        lookups: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl UniqueIdentifier for CountingIdentifier {
+        async fn identify(&self, identification_token: String) -> Option<String> {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+
+            ["a", "b"]
+                .contains(&identification_token.as_str())
+                .then(|| format!("id-{identification_token}"))
+        }
+
+        fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+            None
+        }
+    }
+
+    fn cache(max_entries: usize) -> (Arc<CountingIdentifier>, CachedIdentifier) {
+        let inner = Arc::new(CountingIdentifier {
+            lookups: AtomicUsize::new(0),
+        });
+        let config = IdentifierCacheConfig {
+            ttl: Duration::from_secs(60),
+            negative_ttl: Duration::from_secs(5),
+            max_entries,
+        };
+
+        (inner.clone(), CachedIdentifier::new(inner, config))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_results_are_cached_until_they_expire() {
+        let (inner, cache) = cache(10);
+
+        for _ in 0..3 {
+            assert_eq!(
+                cache.identify("a".to_string()).await.as_deref(),
+                Some("id-a")
+            );
+            assert_eq!(cache.identify("x".to_string()).await, None);
+        }
+        assert_eq!(inner.lookups.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            cache.stats(),
+            IdentifierCacheStats {
+                hits: 4,
+                misses: 2,
+                entries: 2
+            }
+        );
+
+        // Only the unknown identifier expired
+        tokio::time::advance(Duration::from_secs(6)).await;
+        cache.identify("a".to_string()).await;
+        cache.identify("x".to_string()).await;
+        assert_eq!(inner.lookups.load(Ordering::Relaxed), 3);
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        cache.identify("a".to_string()).await;
+        assert_eq!(inner.lookups.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_size_is_bounded() {
+        let (_, cache) = cache(2);
+
+        cache.identify("a".to_string()).await;
+        tokio::time::advance(Duration::from_secs(1)).await;
+        cache.identify("b".to_string()).await;
+        cache.identify("x".to_string()).await;
+
+        // The entry closest to expire makes room
+        assert_eq!(cache.stats().entries, 2);
+        assert!(cache.cached("a").is_none());
+        assert!(cache.cached("b").is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_changed_identifiers_empty_the_cache() {
+        let (inner, cache) = cache(10);
+
+        for token in ["a", "x"] {
+            cache.identify(token.to_string()).await;
+        }
+        cache.identifiers_changed();
+        assert_eq!(cache.stats().entries, 0);
+
+        for token in ["a", "x"] {
+            cache.identify(token.to_string()).await;
+        }
+        assert_eq!(inner.lookups.load(Ordering::Relaxed), 4);
+    }
+
+    /// Answers `id-a` for every identifier once it is released.
+    struct GatedIdentifier {
+        release: Notify,
+    }
+
+    #[async_trait]
+    impl UniqueIdentifier for GatedIdentifier {
+        async fn identify(&self, _identification_token: String) -> Option<String> {
+            self.release.notified().await;
+            Some("id-a".to_string())
+        }
+
+        fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+            None
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lookups_started_before_a_change_are_not_stored() {
+        let inner = Arc::new(GatedIdentifier {
+            release: Notify::new(),
+        });
+        let cache = CachedIdentifier::new(inner.clone(), IdentifierCacheConfig::default());
+
+        let (user_id, _) = tokio::join!(cache.identify("a".to_string()), async {
+            cache.identifiers_changed();
+            inner.release.notify_one();
+        });
+
+        assert_eq!(user_id.as_deref(), Some("id-a"));
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
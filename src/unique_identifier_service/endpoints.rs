@@ -1,27 +1,40 @@
 use std::sync::Arc;
 
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    routing::get,
-    Json, Router,
-};
+use crate::extract::{Json, Path};
+use axum::{extract::State, http::StatusCode};
+use utoipa::OpenApi;
 
-use crate::global_traits::HttpService;
+use crate::{
+    global_traits::HttpService,
+    phone::PhoneNormalizer,
+    route_access::{document_routes, Access, AccessControl, RouteAccess},
+};
 
 use super::{
+    cache::{CachedIdentifier, IdentifierCacheStats},
     repository::UniqueIdentifierRepository,
     usecases::{EMailIdentifier, PhoneIdentifier, UniqueIdentifier},
 };
 
 pub struct UniqueIdentifierHttpServer {
     unique_identifier_repository: Arc<dyn UniqueIdentifierRepository>,
+    phone_normalizer: PhoneNormalizer,
+    identifier_cache: Arc<CachedIdentifier>,
+    access_control: AccessControl,
 }
 
 impl UniqueIdentifierHttpServer {
-    pub async fn new(unique_identifier_repository: Arc<dyn UniqueIdentifierRepository>) -> Self {
+    pub async fn new(
+        unique_identifier_repository: Arc<dyn UniqueIdentifierRepository>,
+        phone_normalizer: PhoneNormalizer,
+        identifier_cache: Arc<CachedIdentifier>,
+        access_control: AccessControl,
+    ) -> Self {
         Self {
             unique_identifier_repository,
+            phone_normalizer,
+            identifier_cache,
+            access_control,
         }
     }
 }
@@ -30,6 +43,7 @@ impl UniqueIdentifierHttpServer {
 struct ServiceState {
     phone_identifier: Arc<PhoneIdentifier>,
     email_identifier: Arc<EMailIdentifier>,
+    identifier_cache: Arc<CachedIdentifier>,
 }
 
 impl HttpService for UniqueIdentifierHttpServer {
@@ -37,21 +51,71 @@ impl HttpService for UniqueIdentifierHttpServer {
         let state: ServiceState = ServiceState {
             phone_identifier: Arc::new(PhoneIdentifier::new(
                 self.unique_identifier_repository.clone(),
+                self.phone_normalizer.clone(),
                 None,
             )),
             email_identifier: Arc::new(EMailIdentifier::new(
                 self.unique_identifier_repository.clone(),
                 None,
             )),
+            identifier_cache: self.identifier_cache.clone(),
         };
 
-        Router::new()
-            .route("/check_email/{email}", get(exists_email))
-            .route("/check_phone/{phone}", get(exists_phone))
+        self.access_control
+            .build_router("unique identifier", routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("unique identifier", legacy_routes()),
+            )
             .with_state(state.into())
     }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        document_routes(UniqueIdentifierApi::openapi(), &routes())
+    }
+}
+
+fn routes() -> Vec<RouteAccess<Arc<ServiceState>>> {
+    vec![
+        RouteAccess::get(
+            "/api/v1/identifiers/email/{email}",
+            Access::Public,
+            exists_email,
+        ),
+        RouteAccess::get(
+            "/api/v1/identifiers/phone/{phone}",
+            Access::Public,
+            exists_phone,
+        ),
+        RouteAccess::get(
+            "/api/v1/identifiers/cache/stats",
+            Access::admin(),
+            identifier_cache_stats,
+        ),
+    ]
+}
+
+fn legacy_routes() -> Vec<RouteAccess<Arc<ServiceState>>> {
+    vec![
+        RouteAccess::get("/check_email/{email}", Access::Public, exists_email),
+        RouteAccess::get("/check_phone/{phone}", Access::Public, exists_phone),
+    ]
 }
 
+#[derive(OpenApi)]
+#[openapi(
+    paths(exists_email, exists_phone, identifier_cache_stats),
+    tags((name = "unique identifier", description = "Checks on the values that identify a user"))
+)]
+struct UniqueIdentifierApi;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/identifiers/email/{email}",
+    tag = "unique identifier",
+    params(("email" = String, Path)),
+    responses((status = 200, description = "Whether a user has the email", body = bool))
+)]
 async fn exists_email(
     Path(email): Path<String>,
     State(state): State<Arc<ServiceState>>,
@@ -60,6 +124,13 @@ async fn exists_email(
     Ok(Json(exists))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/identifiers/phone/{phone}",
+    tag = "unique identifier",
+    params(("phone" = String, Path, description = "In any format, it is normalized to E.164")),
+    responses((status = 200, description = "Whether a user has the phone", body = bool))
+)]
 async fn exists_phone(
     Path(phone): Path<String>,
     State(state): State<Arc<ServiceState>>,
@@ -67,3 +138,94 @@ async fn exists_phone(
     let exists = state.phone_identifier.identify(phone).await.is_some();
     Ok(Json(exists))
 }
+
+/// Hits and misses of the cache in front of the user identifiers since the server started.
+#[utoipa::path(
+    get,
+    path = "/api/v1/identifiers/cache/stats",
+    tag = "unique identifier",
+    responses((status = 200, description = "Stats of the identifier cache", body = IdentifierCacheStats))
+)]
+async fn identifier_cache_stats(
+    State(state): State<Arc<ServiceState>>,
+) -> Json<IdentifierCacheStats> {
+    Json(state.identifier_cache.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        test_harness::{TestApp, TEST_PASSWORD},
+        user_service::domain::UserRol,
+    };
+
+    #[tokio::test]
+    async fn test_check_email_and_phone() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let national = user.telefono.trim_start_matches("+57");
+        let (first, rest) = national.split_at(3);
+
+        for (uri, exists) in [
+            (format!("/api/v1/identifiers/email/{}", user.correo), true),
+            (
+                "/api/v1/identifiers/email/nadie@unisabana.edu.co".to_string(),
+                false,
+            ),
+            (format!("/api/v1/identifiers/phone/{}", user.telefono), true),
+            (format!("/api/v1/identifiers/phone/{national}"), true),
+            (format!("/api/v1/identifiers/phone/{first}%20{rest}"), true),
+            ("/api/v1/identifiers/phone/3009999999".to_string(), false),
+        ] {
+            let response = app.get(&uri, None).await;
+            assert_eq!(response.status, StatusCode::OK, "GET {uri}");
+            assert_eq!(response.body, json!(exists), "GET {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identifier_cache_stats() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        assert_eq!(
+            app.get("/api/v1/identifiers/cache/stats", Some(&user.token))
+                .await
+                .status,
+            StatusCode::FORBIDDEN
+        );
+
+        // Logging in resolves the email through the cache
+        let before = app
+            .get("/api/v1/identifiers/cache/stats", Some(&admin.token))
+            .await;
+        assert_eq!(before.status, StatusCode::OK);
+        for _ in 0..2 {
+            app.log_in(&user.correo, TEST_PASSWORD).await;
+        }
+
+        let after = app
+            .get("/api/v1/identifiers/cache/stats", Some(&admin.token))
+            .await;
+        let count = |body: &serde_json::Value, key: &str| body[key].as_u64().unwrap();
+        assert_eq!(count(&after.body, "hits"), count(&before.body, "hits") + 2);
+        assert_eq!(count(&after.body, "misses"), count(&before.body, "misses"));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated_aliases() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let response = app
+            .get(&format!("/check_email/{}", user.correo), None)
+            .await;
+        assert_eq!(response.body, json!(true));
+        assert!(response.headers.contains_key("deprecation"));
+        assert!(response.headers.contains_key("sunset"));
+    }
+}
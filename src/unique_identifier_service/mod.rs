@@ -1,3 +1,4 @@
+pub mod cache;
 pub mod endpoints;
 pub mod err;
 pub mod repository;
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+
+use crate::in_memory::{InMemoryStore, PersonaRow};
+
+use super::{
+    err::{Result, UserRepositoryError},
+    DocumentHolder, UniqueIdentifierRepository,
+};
+
+#[derive(Clone)]
+pub struct InMemoryUniqueIdentifierRepo {
+    store: InMemoryStore,
+}
+
+impl InMemoryUniqueIdentifierRepo {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+
+    fn find_user_id(&self, filter: impl Fn(&PersonaRow) -> bool) -> Result<String> {
+        self.store
+            .tables()
+            .persona
+            .values()
+            .find(|persona| filter(persona))
+            .map(|persona| persona.user.id_persona.clone())
+            .ok_or(UserRepositoryError::UserNotFound)
+    }
+}
+
+#[async_trait]
+impl UniqueIdentifierRepository for InMemoryUniqueIdentifierRepo {
+    async fn get_user_id_by_email(&self, email: &str) -> Result<String> {
+        self.find_user_id(|persona| persona.user.correo == email)
+    }
+
+    async fn get_user_id_by_phone_number(&self, phone_number: &str) -> Result<String> {
+        self.find_user_id(|persona| persona.user.telefono == phone_number)
+    }
+
+    async fn get_user_id_by_identification(
+        &self,
+        identificacion: &str,
+    ) -> Result<Vec<DocumentHolder>> {
+        Ok(self
+            .store
+            .tables()
+            .persona
+            .values()
+            .filter(|persona| persona.user.identificacion == identificacion)
+            .map(|persona| DocumentHolder {
+                id_persona: persona.user.id_persona.clone(),
+                nombre_tipo_identificacion: persona.user.nombre_tipo_identificacion.clone(),
+            })
+            .collect())
+    }
+
+    async fn comprove_id_existance(&self, user_id: &str) -> Result<()> {
+        if self.store.tables().persona.contains_key(user_id) {
+            Ok(())
+        } else {
+            Err(UserRepositoryError::UserNotFound)
+        }
+    }
+}
@@ -1,32 +1,25 @@
-use std::{result, sync::Arc};
+use crate::database::{DatabaseHandle, PooledConnection};
+use std::sync::Arc;
 
 use super::{
     err::{Result, UserRepositoryError},
-    UniqueIdentifierRepository,
+    DocumentHolder, UniqueIdentifierRepository,
 };
 use async_trait::async_trait;
-use libsql::{params, Connection, Database};
+use libsql::params;
 
 #[derive(Clone)]
 pub struct LibSqlUniqueIdentifierRepo {
-    db: Arc<Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl LibSqlUniqueIdentifierRepo {
-    pub async fn new(
-        url: &str,
-        token: &str,
-    ) -> result::Result<Arc<dyn UniqueIdentifierRepository>, String> {
-        let db = libsql::Builder::new_remote(url.to_string(), token.to_string())
-            .build()
-            .await
-            .map_err(|err| format!("Error creating new remote database for libsql: {err}"))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<Connection> {
-        Ok(self.db.connect()?)
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        Ok(self.db.connect().await?)
     }
 }
 
@@ -35,7 +28,7 @@ impl UniqueIdentifierRepository for LibSqlUniqueIdentifierRepo {
     async fn get_user_id_by_email(&self, email: &str) -> Result<String> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_persona FROM persona WHERE correo = ?1 LIMIT 1",
                 params![email.to_string()],
             )
@@ -52,7 +45,7 @@ impl UniqueIdentifierRepository for LibSqlUniqueIdentifierRepo {
     async fn get_user_id_by_phone_number(&self, phone_number: &str) -> Result<String> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_persona FROM persona WHERE telefono = ?1 LIMIT 1",
                 params![phone_number.to_string()],
             )
@@ -66,11 +59,34 @@ impl UniqueIdentifierRepository for LibSqlUniqueIdentifierRepo {
         }
     }
 
+    async fn get_user_id_by_identification(
+        &self,
+        identificacion: &str,
+    ) -> Result<Vec<DocumentHolder>> {
+        let conn = self.get_connection().await?;
+        let mut rows = conn
+            .read(
+                "SELECT id_persona, nombre_tipo_identificacion FROM persona WHERE identificacion = ?1",
+                params![identificacion],
+            )
+            .await?;
+
+        let mut holders = Vec::new();
+        while let Some(row) = rows.next().await? {
+            holders.push(DocumentHolder {
+                id_persona: row.get(0)?,
+                nombre_tipo_identificacion: row.get(1)?,
+            });
+        }
+
+        Ok(holders)
+    }
+
     async fn comprove_id_existance(&self, user_id: &str) -> Result<()> {
         let conn = self.get_connection().await?;
 
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT EXISTS (SELECT 1 FROM persona WHERE id_persona = ?1)",
                 params![user_id],
             )
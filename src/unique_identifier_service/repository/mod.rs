@@ -3,12 +3,27 @@ use mockall::automock;
 
 pub mod err;
 use err::Result;
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod lib_sql_implementation;
 
+/// A user with a document of the number looked up, the same number can belong to
+/// documents of different types.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentHolder {
+    pub id_persona: String,
+    pub nombre_tipo_identificacion: String,
+}
+
 #[automock]
 #[async_trait]
 pub trait UniqueIdentifierRepository: Send + Sync {
     async fn get_user_id_by_email(&self, email: &str) -> Result<String>;
     async fn get_user_id_by_phone_number(&self, phone_number: &str) -> Result<String>;
+    /// Every user with a document of this number, whatever its type.
+    async fn get_user_id_by_identification(
+        &self,
+        identificacion: &str,
+    ) -> Result<Vec<DocumentHolder>>;
     async fn comprove_id_existance(&self, user_id: &str) -> Result<()>;
 }
@@ -1,26 +1,41 @@
 use std::{str::FromStr, sync::Arc};
 
 use async_trait::async_trait;
-use tracing::{error, info};
+use tracing::{debug, error, warn};
 use uuid::Uuid;
 
+use crate::{phone::PhoneNormalizer, user_service::validation::TIPOS_IDENTIFICACION};
+
+use super::cache::{CachedIdentifier, IdentifierCacheConfig};
 use super::repository::UniqueIdentifierRepository;
 
+/// The id, the email, the document and last the phone. Any document number is also a
+/// phone once the country code is added, so the document is tried first: a number that is
+/// the document of one user and the phone of another one resolves to the document holder.
 pub fn build_unique_identifier(
     user_repository: Arc<dyn UniqueIdentifierRepository>,
-) -> Arc<dyn UniqueIdentifier> {
-    let phone_identifier: Arc<dyn UniqueIdentifier> =
-        Arc::new(PhoneIdentifier::new(user_repository.clone(), None));
+    phone_normalizer: PhoneNormalizer,
+    cache_config: IdentifierCacheConfig,
+) -> Arc<CachedIdentifier> {
+    let phone_identifier: Arc<dyn UniqueIdentifier> = Arc::new(PhoneIdentifier::new(
+        user_repository.clone(),
+        phone_normalizer,
+        None,
+    ));
+
+    let document_identifier: Arc<dyn UniqueIdentifier> = Arc::new(
+        IdentificationDocumentIdentifier::new(user_repository.clone(), Some(phone_identifier)),
+    );
 
     let email_identifier: Arc<dyn UniqueIdentifier> = Arc::new(EMailIdentifier::new(
         user_repository.clone(),
-        Some(phone_identifier),
+        Some(document_identifier),
     ));
 
     let unique_identifier: Arc<dyn UniqueIdentifier> =
         Arc::new(UserIdentifier::new(Some(email_identifier)));
 
-    unique_identifier
+    Arc::new(CachedIdentifier::new(unique_identifier, cache_config))
 }
 
 #[async_trait]
@@ -28,20 +43,28 @@ pub trait UniqueIdentifier: Sync + Send {
     async fn identify(&self, identification_token: String) -> Option<String>;
 
     fn next(&self) -> Option<Arc<dyn UniqueIdentifier>>;
+
+    /// A user was created or changed their email, phone or document. What was resolved
+    /// before can be wrong now, even for other users when a document number becomes shared.
+    fn identifiers_changed(&self) {}
 }
 
+/// Finds the user by the phone written in any format, it is looked up in E.164.
 pub struct PhoneIdentifier {
     user_repository: Arc<dyn UniqueIdentifierRepository>,
+    phone_normalizer: PhoneNormalizer,
     next_identifier: Option<Arc<dyn UniqueIdentifier>>,
 }
 
 impl PhoneIdentifier {
     pub fn new(
         user_repository: Arc<dyn UniqueIdentifierRepository>,
+        phone_normalizer: PhoneNormalizer,
         next_identifier: Option<Arc<dyn UniqueIdentifier>>,
     ) -> Self {
         PhoneIdentifier {
             user_repository,
+            phone_normalizer,
             next_identifier,
         }
     }
@@ -50,11 +73,11 @@ impl PhoneIdentifier {
 #[async_trait]
 impl UniqueIdentifier for PhoneIdentifier {
     async fn identify(&self, identification_token: String) -> Option<String> {
-        info!("Executing unique phone identifier");
-        if identification_token.chars().all(|char| char.is_numeric()) {
+        debug!("Executing unique phone identifier");
+        if let Some(phone_number) = self.phone_normalizer.normalize(&identification_token) {
             let user_id = self
                 .user_repository
-                .get_user_id_by_phone_number(&identification_token)
+                .get_user_id_by_phone_number(&phone_number)
                 .await;
 
             match user_id {
@@ -101,7 +124,7 @@ impl EMailIdentifier {
 #[async_trait]
 impl UniqueIdentifier for EMailIdentifier {
     async fn identify(&self, identification_token: String) -> Option<String> {
-        info!("Executing unique email identifier");
+        debug!("Executing unique email identifier");
         if identification_token.contains('@') && identification_token.contains('.') {
             let user_id = self
                 .user_repository
@@ -132,6 +155,96 @@ impl UniqueIdentifier for EMailIdentifier {
     }
 }
 
+/// Finds the user by the number of their identification document. The number can be
+/// preceded by its type, like `CC 1014739191` or `TI-1014739191`, which is needed when
+/// the number belongs to documents of different types.
+pub struct IdentificationDocumentIdentifier {
+    user_repository: Arc<dyn UniqueIdentifierRepository>,
+    next_identifier: Option<Arc<dyn UniqueIdentifier>>,
+}
+
+impl IdentificationDocumentIdentifier {
+    pub fn new(
+        user_repository: Arc<dyn UniqueIdentifierRepository>,
+        next_identifier: Option<Arc<dyn UniqueIdentifier>>,
+    ) -> Self {
+        IdentificationDocumentIdentifier {
+            user_repository,
+            next_identifier,
+        }
+    }
+
+    async fn find_holder(&self, tipo: Option<&str>, identificacion: &str) -> Option<String> {
+        let holders = match self
+            .user_repository
+            .get_user_id_by_identification(identificacion)
+            .await
+        {
+            Ok(holders) => holders,
+            Err(err) => {
+                error!("Error getting user id with the document <{identificacion}>, error: {err}");
+                return None;
+            }
+        };
+
+        let mut holders = holders
+            .into_iter()
+            .filter(|holder| tipo.is_none_or(|tipo| holder.nombre_tipo_identificacion == tipo));
+
+        match (holders.next(), holders.next()) {
+            (Some(holder), None) => Some(holder.id_persona),
+            (Some(_), Some(_)) => {
+                warn!("The document <{identificacion}> belongs to several users, the type is needed to tell them apart");
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The type, when given, and the number of a document.
+fn parse_document(identification_token: &str) -> Option<(Option<&'static str>, String)> {
+    let token = identification_token.trim();
+
+    let (tipo, number) = TIPOS_IDENTIFICACION
+        .iter()
+        .find_map(|tipo| {
+            let prefix = token.get(..tipo.len())?;
+            let number = token[tipo.len()..].strip_prefix([' ', '-', ':'])?;
+
+            prefix
+                .eq_ignore_ascii_case(tipo)
+                .then_some((Some(*tipo), number.trim()))
+        })
+        .unwrap_or((None, token));
+
+    let is_document = !number.is_empty() && number.chars().all(|c| c.is_ascii_alphanumeric());
+
+    is_document.then(|| (tipo, number.to_uppercase()))
+}
+
+#[async_trait]
+impl UniqueIdentifier for IdentificationDocumentIdentifier {
+    async fn identify(&self, identification_token: String) -> Option<String> {
+        debug!("Executing unique identification document identifier");
+        if let Some((tipo, identificacion)) = parse_document(&identification_token) {
+            if let Some(user_id) = self.find_holder(tipo, &identificacion).await {
+                return Some(user_id);
+            }
+        }
+
+        if let Some(next_identifier) = &self.next_identifier {
+            next_identifier.identify(identification_token).await
+        } else {
+            None
+        }
+    }
+
+    fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+        self.next_identifier.clone()
+    }
+}
+
 pub struct UserIdentifier {
     next_identifier: Option<Arc<dyn UniqueIdentifier>>,
 }
@@ -147,8 +260,6 @@ impl UniqueIdentifier for UserIdentifier {
     async fn identify(&self, identification_token: String) -> Option<String> {
         if Uuid::from_str(&identification_token).is_ok() {
             return Some(identification_token);
-        } else {
-            println!("Error identifiying token by user id, id_token: {identification_token}, the token is not UUID");
         }
 
         if let Some(next_identifier) = &self.next_identifier {
@@ -165,7 +276,9 @@ impl UniqueIdentifier for UserIdentifier {
 
 #[cfg(test)]
 mod tests {
-    use crate::unique_identifier_service::repository::MockUniqueIdentifierRepository;
+    use crate::unique_identifier_service::repository::{
+        DocumentHolder, MockUniqueIdentifierRepository,
+    };
 
     use super::*;
     use mockall::predicate::eq;
@@ -178,12 +291,16 @@ mod tests {
 
         mock_repo
             .expect_get_user_id_by_phone_number()
-            .with(eq("9999999999".to_string()))
+            .with(eq("+573185920708".to_string()))
             .returning(|_| Ok("user-id-123".to_string()));
 
-        let phone_identifier = Arc::new(PhoneIdentifier::new(Arc::new(mock_repo), None));
+        let phone_identifier = Arc::new(PhoneIdentifier::new(
+            Arc::new(mock_repo),
+            PhoneNormalizer::default(),
+            None,
+        ));
 
-        let result = phone_identifier.identify("9999999999".to_string()).await;
+        let result = phone_identifier.identify("318 592 0708".to_string()).await;
 
         assert_eq!(result, Some("user-id-123".to_string()));
     }
@@ -210,7 +327,11 @@ mod tests {
     async fn test_phone_identifier_invalid_number() {
         let mock_repo = MockUniqueIdentifierRepository::new();
 
-        let phone_identifier = Arc::new(PhoneIdentifier::new(Arc::new(mock_repo), None));
+        let phone_identifier = Arc::new(PhoneIdentifier::new(
+            Arc::new(mock_repo),
+            PhoneNormalizer::default(),
+            None,
+        ));
 
         let result = phone_identifier.identify("abc123".to_string()).await;
 
@@ -241,6 +362,7 @@ mod tests {
         let email_identifier = Arc::new(EMailIdentifier::new(Arc::new(mock_repo_email), None));
         let phone_identifier = Arc::new(PhoneIdentifier::new(
             Arc::new(mock_repo_phone),
+            PhoneNormalizer::default(),
             Some(email_identifier.clone()),
         ));
 
@@ -250,4 +372,88 @@ mod tests {
 
         assert_eq!(result, Some("chained-user-id".to_string()));
     }
+
+    fn holders(tipos: &[&str]) -> Vec<DocumentHolder> {
+        tipos
+            .iter()
+            .map(|tipo| DocumentHolder {
+                id_persona: format!("user-{tipo}"),
+                nombre_tipo_identificacion: tipo.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_document() {
+        assert_eq!(
+            parse_document("1014739191"),
+            Some((None, "1014739191".to_string()))
+        );
+        assert_eq!(
+            parse_document("cc 1014739191"),
+            Some((Some("CC"), "1014739191".to_string()))
+        );
+        assert_eq!(
+            parse_document("PASAPORTE-ab123"),
+            Some((Some("PASAPORTE"), "AB123".to_string()))
+        );
+        // Without a separator it is a number that starts with letters
+        assert_eq!(parse_document("CE123"), Some((None, "CE123".to_string())));
+        assert_eq!(parse_document("CC -"), None);
+        assert_eq!(parse_document("test@example.com"), None);
+    }
+
+    #[tokio::test]
+    async fn test_document_identifier_found() {
+        let mut mock_repo = MockUniqueIdentifierRepository::new();
+        mock_repo
+            .expect_get_user_id_by_identification()
+            .with(eq("1014739191"))
+            .returning(|_| Ok(holders(&["CC"])));
+
+        let identifier = IdentificationDocumentIdentifier::new(Arc::new(mock_repo), None);
+
+        assert_eq!(
+            identifier.identify("1014739191".to_string()).await,
+            Some("user-CC".to_string())
+        );
+        assert_eq!(identifier.identify("TI 1014739191".to_string()).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_document_is_tried_before_the_phone() {
+        let mut mock_repo = MockUniqueIdentifierRepository::new();
+        mock_repo
+            .expect_get_user_id_by_identification()
+            .with(eq("3185920708"))
+            .returning(|_| Ok(holders(&["CC"])));
+        mock_repo.expect_get_user_id_by_phone_number().never();
+
+        let identifier = build_unique_identifier(
+            Arc::new(mock_repo),
+            PhoneNormalizer::default(),
+            IdentifierCacheConfig::default(),
+        );
+
+        assert_eq!(
+            identifier.identify("3185920708".to_string()).await,
+            Some("user-CC".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_document_identifier_needs_the_type_of_a_shared_number() {
+        let mut mock_repo = MockUniqueIdentifierRepository::new();
+        mock_repo
+            .expect_get_user_id_by_identification()
+            .returning(|_| Ok(holders(&["CC", "TI"])));
+
+        let identifier = IdentificationDocumentIdentifier::new(Arc::new(mock_repo), None);
+
+        assert_eq!(identifier.identify("1014739191".to_string()).await, None);
+        assert_eq!(
+            identifier.identify("TI:1014739191".to_string()).await,
+            Some("user-TI".to_string())
+        );
+    }
 }
@@ -1,68 +1,180 @@
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    pagination::{FilterKind, Listable},
+    phone::{deserialize_phone, serialize_legacy_phone},
+    session_service::domain::TokenPair,
+};
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct UserCreationInfo {
     pub nombre: String,
     pub contrasena: String,
     pub correo: String,
-    pub telefono: u64,
+    /// E.164 once it is stored, a number or a string in any format on the way in
+    #[serde(deserialize_with = "deserialize_phone")]
+    pub telefono: String,
     pub identificacion: String,
     pub nombre_tipo_identificacion: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, ToSchema)]
 pub struct UserUpdating {
     pub nombre: String,
     pub correo: String,
-    pub telefono: u64,
+    #[serde(deserialize_with = "deserialize_phone")]
+    pub telefono: String,
     pub identificacion: String,
     pub nombre_tipo_identificacion: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 pub struct UserInfo {
     pub id_persona: String,
     pub nombre: String,
     pub correo: String,
-    pub telefono: u64,
+    pub telefono: String,
     pub identificacion: String,
     pub nombre_tipo_identificacion: String,
     pub nombre_rol: UserRol,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, ToSchema)]
 pub struct UserSelectionInfo {
     pub id_persona: String,
     pub nombre: String,
     pub correo: String,
-    pub telefono: u64,
+    pub telefono: String,
+    pub identificacion: String,
+    pub nombre_tipo_identificacion: String,
+    pub nombre_rol: UserRol,
+    pub matricula_valida: bool,
+}
+
+impl Listable for UserInfo {
+    const SORT_FIELDS: &'static [&'static str] = &[
+        "nombre",
+        "correo",
+        "identificacion",
+        "nombre_rol",
+        "id_persona",
+    ];
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = &[
+        ("nombre_rol", FilterKind::Text),
+        ("nombre_tipo_identificacion", FilterKind::Text),
+    ];
+    const ID_FIELD: &'static str = "id_persona";
+}
+
+/// `UserInfo` as the legacy routes answer it, the phone is still a number there.
+#[derive(Serialize)]
+pub struct LegacyUserInfo {
+    pub id_persona: String,
+    pub nombre: String,
+    pub correo: String,
+    #[serde(serialize_with = "serialize_legacy_phone")]
+    pub telefono: String,
+    pub identificacion: String,
+    pub nombre_tipo_identificacion: String,
+    pub nombre_rol: UserRol,
+}
+
+impl From<UserInfo> for LegacyUserInfo {
+    fn from(user: UserInfo) -> Self {
+        Self {
+            id_persona: user.id_persona,
+            nombre: user.nombre,
+            correo: user.correo,
+            telefono: user.telefono,
+            identificacion: user.identificacion,
+            nombre_tipo_identificacion: user.nombre_tipo_identificacion,
+            nombre_rol: user.nombre_rol,
+        }
+    }
+}
+
+/// `UserSelectionInfo` as the legacy search answers it, the phone is still a number there.
+#[derive(Serialize)]
+pub struct LegacyUserSelectionInfo {
+    pub id_persona: String,
+    pub nombre: String,
+    pub correo: String,
+    #[serde(serialize_with = "serialize_legacy_phone")]
+    pub telefono: String,
     pub identificacion: String,
     pub nombre_tipo_identificacion: String,
     pub nombre_rol: UserRol,
     pub matricula_valida: bool,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+impl From<UserSelectionInfo> for LegacyUserSelectionInfo {
+    fn from(user: UserSelectionInfo) -> Self {
+        Self {
+            id_persona: user.id_persona,
+            nombre: user.nombre,
+            correo: user.correo,
+            telefono: user.telefono,
+            identificacion: user.identificacion,
+            nombre_tipo_identificacion: user.nombre_tipo_identificacion,
+            nombre_rol: user.nombre_rol,
+            matricula_valida: user.matricula_valida,
+        }
+    }
+}
+
+/// The search sorts and filters on the same columns of `persona` as the list of users.
+impl Listable for UserSelectionInfo {
+    const SORT_FIELDS: &'static [&'static str] = UserInfo::SORT_FIELDS;
+    const FILTER_FIELDS: &'static [(&'static str, FilterKind)] = UserInfo::FILTER_FIELDS;
+    const ID_FIELD: &'static str = UserInfo::ID_FIELD;
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
 pub enum UserRol {
     Usuario,
     Admin,
     Entrenador,
 }
 
-impl ToString for UserRol {
-    fn to_string(&self) -> String {
-        match self {
+impl Display for UserRol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let role = match self {
             UserRol::Usuario => "Usuario",
             UserRol::Admin => "Admin",
             UserRol::Entrenador => "Entrenador",
-        }
-        .to_string()
+        };
+
+        write!(f, "{role}")
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub enum SearchSelection {
     Email,
     PhoneNumber,
     UserName,
 }
+
+/// One login attempt of a user, kept so the user can spot logins that were not theirs.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct LoginHistoryEntry {
+    pub id_login: String,
+    pub id_persona: String,
+    pub ip_address: String,
+    pub success: bool,
+    pub created_at: i64,
+}
+
+/// Result of the password step of the login.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginOutcome {
+    Authenticated(TokenPair),
+    /// The user has MFA enabled, the token has to be exchanged in `/api/v1/auth/login/mfa`
+    MfaPending {
+        mfa_token: String,
+    },
+}
@@ -1,37 +1,79 @@
 use std::sync::Arc;
 
-use axum::extract::Path;
-use axum::routing::{get, put};
-use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
-use axum::{middleware, Extension};
+use crate::extract::{Json, Path, Query};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use axum::{extract::State, http::StatusCode};
 use serde::{Deserialize, Serialize};
 use tracing::error;
+use utoipa::{IntoParams, OpenApi, ToSchema};
 
-use crate::auth_middleware::auth_middleware;
+use axum_extra::extract::cookie::CookieJar;
+
+use crate::api_error::{ApiError, ErrorBody};
+use crate::auth_middleware::{add_auth_cookies, AuthContext, AuthMode};
+use crate::client_ip::ClientIp;
+use crate::database::UnitOfWork;
 use crate::global_traits::HttpService;
+use crate::mfa_service::repository::MfaRepository;
+use crate::mfa_service::use_cases::MfaService;
+use crate::pagination::{ListParams, ListQuery, Page};
+use crate::phone::PhoneNormalizer;
+use crate::route_access::{document_routes, Access, AccessControl, RouteAccess};
+use crate::session_service::domain::TokenPair;
+use crate::session_service::repository::SessionRepository;
+use crate::session_service::use_cases::SessionService;
 use crate::unique_identifier_service::usecases::UniqueIdentifier;
 
-use super::domain::{SearchSelection, UserInfo, UserRol, UserSelectionInfo, UserUpdating};
+use super::domain::{
+    LegacyUserInfo, LegacyUserSelectionInfo, LoginHistoryEntry, LoginOutcome, SearchSelection,
+    UserInfo, UserRol, UserSelectionInfo, UserUpdating,
+};
+use super::err::UserServiceError;
+use super::login_service::LoginService;
+use super::notifier::Notifier;
+use super::password_service::PasswordService;
 use super::repository::UserRepository;
 use super::token_provider::TokenProvider;
 use super::{domain::UserCreationInfo, use_cases::UserService};
 
 pub struct UserHttpServer {
     user_repository: Arc<dyn UserRepository>,
+    session_repository: Arc<dyn SessionRepository>,
+    mfa_repository: Arc<dyn MfaRepository>,
     token_key: String,
     unique_identifier: Arc<dyn UniqueIdentifier>,
+    notifier: Arc<dyn Notifier>,
+    unit_of_work: UnitOfWork,
+    phone_normalizer: PhoneNormalizer,
+    access_control: AccessControl,
 }
 
 impl UserHttpServer {
+    // One argument per collaborator of the user, login and password routers, a config
+    // struct would only move the same list elsewhere
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         token_key: String,
         unique_identifier: Arc<dyn UniqueIdentifier>,
         user_repository: Arc<dyn UserRepository>,
+        session_repository: Arc<dyn SessionRepository>,
+        mfa_repository: Arc<dyn MfaRepository>,
+        notifier: Arc<dyn Notifier>,
+        unit_of_work: UnitOfWork,
+        phone_normalizer: PhoneNormalizer,
+        access_control: AccessControl,
     ) -> Self {
         Self {
             user_repository,
+            session_repository,
+            mfa_repository,
             token_key,
             unique_identifier,
+            notifier,
+            unit_of_work,
+            phone_normalizer,
+            access_control,
         }
     }
 }
@@ -39,149 +81,413 @@ impl UserHttpServer {
 impl HttpService for UserHttpServer {
     fn get_router(&self) -> axum::Router {
         let token_provider = TokenProvider::new(self.token_key.clone());
+        let session_service =
+            SessionService::new(self.session_repository.clone(), token_provider.clone());
 
         let user_service = UserService::new(
             self.user_repository.clone(),
             self.unique_identifier.clone(),
+            session_service.clone(),
+            self.phone_normalizer.clone(),
+        );
+
+        let password_service = PasswordService::new(
+            self.user_repository.clone(),
+            self.unique_identifier.clone(),
+            session_service.clone(),
+            self.notifier.clone(),
+            self.unit_of_work.clone(),
+        );
+
+        let login_service = LoginService::new(
+            self.user_repository.clone(),
+            self.unique_identifier.clone(),
+            session_service,
+            MfaService::new(self.mfa_repository.clone(), self.user_repository.clone()),
             token_provider,
         );
 
-        Router::new()
-            .route("/test_auth", get(test_auth))
-            .route("/user", get(get_user))
-            .route("/user/admin", get(user_rol))
-            .route("/user", put(update_user))
-            .route("/user/id/{user_id}", put(update_other_user))
-            .route("/user/role/{user_role}/{user_id}", put(update_user_rol))
-            .layer(middleware::from_fn_with_state(
-                self.token_key.clone(),
-                auth_middleware,
-            ))
-            .route(
-                "/user/search/{query}/{selection}/{limt}",
-                get(search_user_selection_info),
-            )
-            .route("/user", post(create_user))
-            .route("/user/{identification}", get(get_user_by_identification))
-            .route("/user/all", get(get_all_users))
-            .route("/log_in", post(login_user))
+        self.access_control
+            .build_router("user", user_routes())
+            .merge(
+                self.access_control
+                    .build_legacy_router("user", legacy_user_routes()),
+            )
             .with_state(user_service)
+            .merge(
+                self.access_control
+                    .build_router("login", login_routes())
+                    .merge(
+                        self.access_control
+                            .build_legacy_router("login", legacy_login_routes()),
+                    )
+                    .with_state(login_service),
+            )
+            .merge(
+                self.access_control
+                    .build_router("password", password_routes())
+                    .with_state(password_service),
+            )
+    }
+
+    fn openapi(&self) -> utoipa::openapi::OpenApi {
+        let openapi = document_routes(UserApi::openapi(), &user_routes());
+        let openapi = document_routes(openapi, &login_routes());
+
+        document_routes(openapi, &password_routes())
     }
 }
 
+fn user_routes() -> Vec<RouteAccess<UserService>> {
+    vec![
+        RouteAccess::get("/api/v1/auth/check", Access::Authenticated, test_auth),
+        RouteAccess::get("/api/v1/users", Access::staff(), list_users),
+        RouteAccess::post("/api/v1/users", Access::Public, create_user),
+        RouteAccess::get(
+            "/api/v1/users/search/{query}/{selection}",
+            Access::staff(),
+            search_users,
+        ),
+        RouteAccess::get("/api/v1/users/me", Access::Authenticated, get_user),
+        RouteAccess::put("/api/v1/users/me", Access::Authenticated, update_user),
+        RouteAccess::get("/api/v1/users/me/role", Access::Authenticated, user_rol),
+        RouteAccess::get(
+            "/api/v1/users/{user_id}",
+            Access::staff(),
+            get_user_by_identification,
+        ),
+        RouteAccess::put(
+            "/api/v1/users/{user_id}",
+            Access::admin(),
+            update_other_user,
+        ),
+        RouteAccess::put(
+            "/api/v1/users/{user_id}/role/{user_role}",
+            Access::admin(),
+            update_user_rol,
+        ),
+    ]
+}
+
+fn login_routes() -> Vec<RouteAccess<LoginService>> {
+    vec![
+        RouteAccess::post("/api/v1/auth/login", Access::Public, login_user),
+        RouteAccess::post("/api/v1/auth/login/mfa", Access::Public, login_user_mfa),
+        RouteAccess::get(
+            "/api/v1/users/me/login_history",
+            Access::Authenticated,
+            get_login_history,
+        ),
+        RouteAccess::post(
+            "/api/v1/users/{user_id}/unlock",
+            Access::admin(),
+            unlock_user,
+        ),
+    ]
+}
+
+fn password_routes() -> Vec<RouteAccess<PasswordService>> {
+    vec![
+        RouteAccess::put(
+            "/api/v1/users/me/password",
+            Access::Authenticated,
+            change_password,
+        ),
+        RouteAccess::post(
+            "/api/v1/auth/password/forgot",
+            Access::Public,
+            forgot_password,
+        ),
+        RouteAccess::post(
+            "/api/v1/auth/password/reset",
+            Access::Public,
+            reset_password,
+        ),
+    ]
+}
+
+fn legacy_user_routes() -> Vec<RouteAccess<UserService>> {
+    vec![
+        RouteAccess::get("/test_auth", Access::Authenticated, test_auth),
+        RouteAccess::get("/user", Access::Authenticated, legacy_get_user),
+        RouteAccess::get("/user/admin", Access::Authenticated, user_rol),
+        RouteAccess::put("/user", Access::Authenticated, update_user),
+        RouteAccess::put("/user/id/{user_id}", Access::admin(), update_other_user),
+        RouteAccess::put(
+            "/user/role/{user_role}/{user_id}",
+            Access::admin(),
+            update_user_rol,
+        ),
+        RouteAccess::get(
+            "/user/search/{query}/{selection}/{limt}",
+            Access::staff(),
+            search_user_selection_info,
+        ),
+        RouteAccess::post("/user", Access::Public, create_user),
+        RouteAccess::get(
+            "/user/{identification}",
+            Access::staff(),
+            legacy_get_user_by_identification,
+        ),
+        RouteAccess::get("/user/all", Access::staff(), get_all_users),
+    ]
+}
+
+fn legacy_login_routes() -> Vec<RouteAccess<LoginService>> {
+    vec![RouteAccess::post(
+        "/log_in",
+        Access::Public,
+        legacy_login_user,
+    )]
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        test_auth,
+        get_user,
+        user_rol,
+        update_user,
+        update_other_user,
+        update_user_rol,
+        search_users,
+        create_user,
+        get_user_by_identification,
+        list_users,
+        login_user,
+        login_user_mfa,
+        unlock_user,
+        get_login_history,
+        change_password,
+        forgot_password,
+        reset_password,
+    ),
+    tags(
+        (name = "user", description = "Users of the club and their roles"),
+        (name = "login", description = "Login with the password and the second factor"),
+        (name = "password", description = "Password change and reset"),
+    )
+)]
+struct UserApi;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/check",
+    tag = "user",
+    responses((status = 200, description = "The access token is valid", body = String))
+)]
 async fn test_auth() -> &'static str {
     "Test for auth"
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+struct RoleChange {
+    user_id: String,
+    user_role: UserRol,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}/role/{user_role}",
+    tag = "user",
+    params(RoleChange),
+    responses(
+        (status = 200, description = "The role was changed"),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+    )
+)]
 async fn update_user_rol(
     State(service): State<UserService>,
-    Path(path_info): Path<(UserRol, String)>,
-) -> StatusCode {
-    match service.update_user_rol(path_info.0, &path_info.1).await {
-        Err(err) => {
-            error!("Error updating the user rol: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+    Path(role_change): Path<RoleChange>,
+) -> Result<StatusCode, UserServiceError> {
+    service
+        .update_user_rol(role_change.user_role, &role_change.user_id)
+        .await?;
+
+    Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/role",
+    tag = "user",
+    responses((status = 200, description = "Role of the caller", body = UserRol))
+)]
 async fn user_rol(
     State(service): State<UserService>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<UserRol>, StatusCode> {
-    match service.user_rol(&user_id).await {
-        Err(err) => {
-            error!("Error in is admin: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-        Ok(result) => Ok(Json(result)),
-    }
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<UserRol>, UserServiceError> {
+    Ok(Json(service.user_rol(&auth_context.user_id).await?))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{user_id}",
+    tag = "user",
+    params(("user_id" = String, Path)),
+    request_body = UserUpdating,
+    responses(
+        (status = 200, description = "The user was updated"),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+        (status = 409, description = "`duplicate`, another user has the value of `field`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid fields are in `errors`", body = ErrorBody),
+    )
+)]
 async fn update_other_user(
     State(service): State<UserService>,
     Path(user_id): Path<String>,
     Json(user_updation_info): Json<UserUpdating>,
-) -> StatusCode {
-    match service.update_user(user_updation_info, &user_id).await {
-        Err(err) => {
-            error!("Error in update user: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+) -> Result<StatusCode, UserServiceError> {
+    service.update_user(user_updation_info, &user_id).await?;
+
+    Ok(StatusCode::OK)
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me",
+    tag = "user",
+    request_body = UserUpdating,
+    responses(
+        (status = 200, description = "The caller was updated"),
+        (status = 409, description = "`duplicate`, another user has the value of `field`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid fields are in `errors`", body = ErrorBody),
+    )
+)]
 async fn update_user(
     State(service): State<UserService>,
-    Extension(user_id): Extension<String>,
+    Extension(auth_context): Extension<AuthContext>,
     Json(user_updation_info): Json<UserUpdating>,
-) -> StatusCode {
-    match service.update_user(user_updation_info, &user_id).await {
-        Err(err) => {
-            error!("Error in update user: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-        Ok(_) => StatusCode::OK,
-    }
+) -> Result<StatusCode, UserServiceError> {
+    service
+        .update_user(user_updation_info, &auth_context.user_id)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/search/{query}/{selection}",
+    tag = "user",
+    params(
+        ("query" = String, Path),
+        ("selection" = SearchSelection, Path, description = "Field the query is matched against"),
+        ListQuery<UserSelectionInfo>,
+    ),
+    responses(
+        (status = 200, description = "Page of the users that match the query", body = Page<UserSelectionInfo>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn search_users(
+    State(state): State<UserService>,
+    Path((query, selection)): Path<(String, SearchSelection)>,
+    ListQuery(params, _): ListQuery<UserSelectionInfo>,
+) -> Result<Json<Page<UserSelectionInfo>>, UserServiceError> {
+    let users_selection = state
+        .search_user_by_search_selection(&query, selection, &params)
+        .await?;
+
+    Ok(Json(users_selection))
 }
 
 async fn search_user_selection_info(
     State(state): State<UserService>,
     Path(query_info): Path<(String, SearchSelection, u8)>,
-) -> Result<Json<Vec<UserSelectionInfo>>, StatusCode> {
-    match state
-        .search_user_by_search_selection(&query_info.0, query_info.2, query_info.1)
-        .await
-    {
-        Ok(users_selection) => Ok(Json(users_selection)),
-        Err(err) => {
-            error!("Error searching user selection info: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<Vec<LegacyUserSelectionInfo>>, UserServiceError> {
+    let params = ListParams::new::<UserSelectionInfo>().with_limit(query_info.2.into());
+    let users_selection = state
+        .search_user_by_search_selection(&query_info.0, query_info.1, &params)
+        .await?;
+
+    Ok(Json(
+        users_selection.items.into_iter().map(Into::into).collect(),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    tag = "user",
+    params(ListQuery<UserInfo>),
+    responses(
+        (status = 200, description = "Page of the users", body = Page<UserInfo>),
+        (status = 422, description = "`validation_failed`, the invalid query parameters are in `errors`", body = ErrorBody),
+    )
+)]
+async fn list_users(
+    State(user_service): State<UserService>,
+    ListQuery(params, _): ListQuery<UserInfo>,
+) -> Result<Json<Page<UserInfo>>, UserServiceError> {
+    Ok(Json(user_service.list_users(&params).await?))
 }
 
 async fn get_all_users(
     State(user_service): State<UserService>,
-) -> Result<Json<Vec<UserInfo>>, StatusCode> {
-    match user_service.get_users().await {
-        Ok(user_info) => Ok(Json(user_info)),
-        Err(err) => {
-            error!("Error fetching user by identification: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+) -> Result<Json<Vec<LegacyUserInfo>>, UserServiceError> {
+    let users = user_service
+        .list_users(&ListParams::unbounded::<UserInfo>())
+        .await?;
+
+    Ok(Json(users.items.into_iter().map(Into::into).collect()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "user",
+    responses((status = 200, description = "The caller", body = UserInfo))
+)]
 async fn get_user(
     State(user_service): State<UserService>,
-    Extension(user_id): Extension<String>,
-) -> Result<Json<UserInfo>, StatusCode> {
-    match user_service.get_user_by_identification(user_id).await {
-        Ok(user_info) => Ok(Json(user_info)),
-        Err(err) => {
-            error!("Error fetching user by identification: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<UserInfo>, UserServiceError> {
+    let user_info = user_service
+        .get_user_by_identification(auth_context.user_id)
+        .await?;
+
+    Ok(Json(user_info))
 }
 
+async fn legacy_get_user(
+    user_service: State<UserService>,
+    auth_context: Extension<AuthContext>,
+) -> Result<Json<LegacyUserInfo>, UserServiceError> {
+    let Json(user_info) = get_user(user_service, auth_context).await?;
+
+    Ok(Json(user_info.into()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}",
+    tag = "user",
+    params(("user_id" = String, Path, description = "Id, email, identification or phone of the user, tried in that order")),
+    responses(
+        (status = 200, description = "The user", body = UserInfo),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+    )
+)]
 async fn get_user_by_identification(
     State(user_service): State<UserService>,
     Path(identification): Path<String>,
-) -> Result<Json<UserInfo>, StatusCode> {
-    match user_service
+) -> Result<Json<UserInfo>, UserServiceError> {
+    let user_info = user_service
         .get_user_by_identification(identification)
-        .await
-    {
-        Ok(user_info) => Ok(Json(user_info)),
-        Err(err) => {
-            error!("Error fetching user by identification: {err}");
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+        .await?;
+
+    Ok(Json(user_info))
+}
+
+async fn legacy_get_user_by_identification(
+    user_service: State<UserService>,
+    identification: Path<String>,
+) -> Result<Json<LegacyUserInfo>, UserServiceError> {
+    let Json(user_info) = get_user_by_identification(user_service, identification).await?;
+
+    Ok(Json(user_info.into()))
 }
 
 // async fn get_user_by_identification(
@@ -200,37 +506,898 @@ async fn get_user_by_identification(
 //     }
 // }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "user",
+    request_body = UserCreationInfo,
+    responses(
+        (status = 201, description = "The user was created"),
+        (status = 409, description = "`duplicate`, another user has the value of `field`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid fields are in `errors`", body = ErrorBody),
+    )
+)]
 async fn create_user(
     State(state): State<UserService>,
     Json(user_creation_info): Json<UserCreationInfo>,
-) -> StatusCode {
-    match state.create_user_with_hashing(user_creation_info).await {
-        Ok(_) => StatusCode::CREATED,
-        Err(err) => {
-            error!("Error creating user: {err}");
-            StatusCode::INTERNAL_SERVER_ERROR
-        }
-    }
+) -> Result<StatusCode, UserServiceError> {
+    state.create_user_with_hashing(user_creation_info).await?;
+
+    Ok(StatusCode::CREATED)
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct AuthInfo {
     identificacion: String,
     contrasena: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "login",
+    params(AuthMode),
+    request_body = AuthInfo,
+    responses(
+        (status = 200, description = "The tokens, or the token for the MFA step", body = LoginOutcome),
+        (status = 401, description = "`invalid_credentials`, a wrong password or an unknown user", body = ErrorBody),
+        (status = 429, description = "`login_locked`, too many failed logins", body = ErrorBody,
+            headers(("Retry-After" = i64, description = "Seconds until the login is allowed again"))),
+    )
+)]
 pub async fn login_user(
-    State(service): State<UserService>,
+    State(service): State<LoginService>,
+    client_ip: ClientIp,
+    Query(auth_mode): Query<AuthMode>,
+    jar: CookieJar,
+    Json(payload): Json<AuthInfo>,
+) -> Result<(CookieJar, Json<LoginOutcome>), Response> {
+    match service
+        .authenticate_user(payload.identificacion, payload.contrasena, &client_ip.0)
+        .await
+    {
+        Ok(LoginOutcome::Authenticated(mut token_pair)) if auth_mode.cookie => Ok((
+            add_auth_cookies(jar, &mut token_pair),
+            Json(LoginOutcome::Authenticated(token_pair)),
+        )),
+        Ok(login_outcome) => Ok((jar, Json(login_outcome))),
+        Err(err) => Err(login_error_response(err, &client_ip)),
+    }
+}
+
+/// The login of the clients from before `/api/v1`, they read the access token as the whole
+/// body and know nothing of refresh tokens or the MFA step.
+async fn legacy_login_user(
+    State(service): State<LoginService>,
+    client_ip: ClientIp,
     Json(payload): Json<AuthInfo>,
-) -> Result<String, StatusCode> {
+) -> Result<String, Response> {
     match service
-        .authenticate_user(payload.identificacion, payload.contrasena)
+        .authenticate_user(payload.identificacion, payload.contrasena, &client_ip.0)
         .await
     {
-        Ok(token) => Ok(token),
-        Err(err) => {
+        Ok(LoginOutcome::Authenticated(token_pair)) => Ok(token_pair.access_token),
+        Ok(LoginOutcome::MfaPending { .. }) => Err(ApiError::unauthorized(
+            "mfa_required",
+            "The account has MFA enabled, log in through /api/v1/auth/login",
+        )
+        .into_response()),
+        Err(err) => Err(login_error_response(err, &client_ip)),
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MfaLoginInfo {
+    mfa_token: String,
+    code: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login/mfa",
+    tag = "login",
+    params(AuthMode),
+    request_body = MfaLoginInfo,
+    responses(
+        (status = 200, description = "The tokens of the new session", body = TokenPair),
+        (status = 401, description = "`invalid_credentials`", body = ErrorBody),
+        (status = 429, description = "`login_locked`, too many failed logins", body = ErrorBody,
+            headers(("Retry-After" = i64, description = "Seconds until the login is allowed again"))),
+    )
+)]
+async fn login_user_mfa(
+    State(service): State<LoginService>,
+    client_ip: ClientIp,
+    Query(auth_mode): Query<AuthMode>,
+    jar: CookieJar,
+    Json(payload): Json<MfaLoginInfo>,
+) -> Result<(CookieJar, Json<TokenPair>), Response> {
+    match service
+        .complete_mfa_login(&payload.mfa_token, &payload.code, &client_ip.0)
+        .await
+    {
+        Ok(mut token_pair) if auth_mode.cookie => {
+            Ok((add_auth_cookies(jar, &mut token_pair), Json(token_pair)))
+        }
+        Ok(token_pair) => Ok((jar, Json(token_pair))),
+        Err(err) => Err(login_error_response(err, &client_ip)),
+    }
+}
+
+/// Every failed login gets the same answer, so it does not tell which users exist.
+fn login_error_response(err: UserServiceError, client_ip: &ClientIp) -> Response {
+    match err {
+        UserServiceError::LoginLocked { retry_after } => {
+            error!("Login from {client_ip} rejected, retry in {retry_after} seconds");
+            err.into_response()
+        }
+        err => {
             error!("Error authenticating user: {err}");
-            Err(StatusCode::UNAUTHORIZED)
+            ApiError::unauthorized("invalid_credentials", "The credentials are not valid")
+                .into_response()
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/{user_id}/unlock",
+    tag = "login",
+    params(("user_id" = String, Path)),
+    responses(
+        (status = 200, description = "The failed logins of the user were forgotten"),
+        (status = 404, description = "`user_not_found`", body = ErrorBody),
+    )
+)]
+async fn unlock_user(
+    State(service): State<LoginService>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, UserServiceError> {
+    service.unlock_user(&user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/login_history",
+    tag = "login",
+    responses((status = 200, description = "Login attempts of the caller", body = Vec<LoginHistoryEntry>))
+)]
+async fn get_login_history(
+    State(service): State<LoginService>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<LoginHistoryEntry>>, UserServiceError> {
+    Ok(Json(
+        service.get_login_history(&auth_context.user_id).await?,
+    ))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PasswordChange {
+    contrasena_actual: String,
+    contrasena_nueva: String,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/me/password",
+    tag = "password",
+    request_body = PasswordChange,
+    responses(
+        (status = 200, description = "The password was changed and the other sessions revoked"),
+        (status = 401, description = "`authentication_failed`, the current password is wrong", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid fields are in `errors`", body = ErrorBody),
+    )
+)]
+async fn change_password(
+    State(service): State<PasswordService>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(payload): Json<PasswordChange>,
+) -> Result<StatusCode, UserServiceError> {
+    service
+        .change_password(
+            &auth_context.user_id,
+            &auth_context.session_id,
+            &payload.contrasena_actual,
+            &payload.contrasena_nueva,
+        )
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PasswordForgot {
+    identificacion: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/password/forgot",
+    tag = "password",
+    request_body = PasswordForgot,
+    responses((status = 202, description = "A reset token is sent when the user exists"))
+)]
+async fn forgot_password(
+    State(service): State<PasswordService>,
+    Json(payload): Json<PasswordForgot>,
+) -> Result<StatusCode, UserServiceError> {
+    service
+        .request_password_reset(payload.identificacion)
+        .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PasswordReset {
+    token: String,
+    contrasena_nueva: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/password/reset",
+    tag = "password",
+    request_body = PasswordReset,
+    responses(
+        (status = 200, description = "The password was changed and every session revoked"),
+        (status = 401, description = "`invalid_password_reset_token`", body = ErrorBody),
+        (status = 422, description = "`validation_failed`, the invalid fields are in `errors`", body = ErrorBody),
+    )
+)]
+async fn reset_password(
+    State(service): State<PasswordService>,
+    Json(payload): Json<PasswordReset>,
+) -> Result<StatusCode, UserServiceError> {
+    service
+        .reset_password(&payload.token, &payload.contrasena_nueva)
+        .await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::RETRY_AFTER, StatusCode};
+    use chrono::Utc;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::api_error::REQUEST_ID_HEADER;
+    use crate::test_harness::{TestApp, TestUser, TEST_PASSWORD};
+    use crate::user_service::token_provider::hash_secret;
+
+    fn user_updating(user: &TestUser, nombre: &str) -> Value {
+        json!({
+            "nombre": nombre,
+            "correo": user.correo,
+            "telefono": user.telefono,
+            "identificacion": user.identificacion,
+            "nombre_tipo_identificacion": "CC"
+        })
+    }
+
+    #[tokio::test]
+    async fn test_create_user() {
+        let app = TestApp::new().await;
+
+        let new_user = json!({
+            "nombre": "Esteban",
+            "contrasena": TEST_PASSWORD,
+            "correo": "estebanmff@gmail.com",
+            "telefono": 3185920708u64,
+            "identificacion": "1014739191",
+            "nombre_tipo_identificacion": "CC"
+        });
+        assert_eq!(
+            app.post("/api/v1/users", None, new_user.clone())
+                .await
+                .status,
+            StatusCode::CREATED
+        );
+
+        let mut duplicate = new_user.clone();
+        duplicate["telefono"] = json!(3185920709u64);
+        duplicate["identificacion"] = json!("1014739192");
+        let response = app.post("/api/v1/users", None, duplicate).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["code"], "duplicate");
+        assert_eq!(response.body["field"], "correo");
+
+        let mut duplicate = new_user.clone();
+        duplicate["correo"] = json!("otro@gmail.com");
+        duplicate["telefono"] = json!(3185920709u64);
+        let response = app.post("/api/v1/users", None, duplicate).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["field"], "identificacion");
+
+        let mut duplicate = new_user.clone();
+        duplicate["correo"] = json!("otro@gmail.com");
+        duplicate["telefono"] = json!("+57 318 592 0708");
+        duplicate["identificacion"] = json!("1014739192");
+        let response = app.post("/api/v1/users", None, duplicate).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(response.body["field"], "telefono");
+
+        let mut invalid = new_user;
+        invalid["correo"] = json!("esteban@gmail");
+        invalid["contrasena"] = json!("corta");
+        invalid["nombre_tipo_identificacion"] = json!("nit");
+        let response = app.post("/api/v1/users", None, invalid).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["code"], "validation_failed");
+        let fields: Vec<&str> = response.body["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|error| error["field"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            ["contrasena", "correo", "nombre_tipo_identificacion"]
+        );
+
+        assert_eq!(
+            app.post("/api/v1/users", None, json!({ "nombre": "Esteban" }))
+                .await
+                .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let login = app.log_in("estebanmff@gmail.com", TEST_PASSWORD).await;
+        assert_eq!(login.status, StatusCode::OK);
+        assert_eq!(login.body["status"], "authenticated");
+
+        // The phone is stored in E.164 and found however it is written
+        let token = login.body["access_token"].as_str().unwrap();
+        let response = app.get("/api/v1/users/me", Some(token)).await;
+        assert_eq!(response.body["telefono"], "+573185920708");
+        for telefono in ["318 592 0708", "+57 318-592-0708"] {
+            assert_eq!(
+                app.log_in(telefono, TEST_PASSWORD).await.status,
+                StatusCode::OK,
+                "{telefono}"
+            );
         }
     }
+
+    #[tokio::test]
+    async fn test_unreadable_user_payloads_report_the_field() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let mut new_user = json!({
+            "nombre": "Esteban",
+            "correo": "estebanmff@gmail.com",
+            "telefono": 3185920708u64,
+            "identificacion": "1014739191",
+            "nombre_tipo_identificacion": "CC"
+        });
+        let response = app.post("/api/v1/users", None, new_user.clone()).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["code"], "validation_failed");
+        assert_eq!(
+            response.body["errors"],
+            json!([{ "field": "contrasena", "message": "is required" }])
+        );
+
+        new_user["contrasena"] = json!(TEST_PASSWORD);
+        new_user["telefono"] = json!(true);
+        let response = app.post("/user", None, new_user).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["errors"][0]["field"], "telefono");
+
+        let mut updating = user_updating(&user, "Usuario");
+        updating["nombre"] = json!(5);
+        let response = app
+            .put("/api/v1/users/me", Some(&user.token), updating)
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.body["errors"],
+            json!([{ "field": "nombre", "message": "must be a string" }])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_own_user_routes() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        for uri in [
+            "/api/v1/auth/check",
+            "/api/v1/users/me",
+            "/api/v1/users/me/role",
+        ] {
+            assert_eq!(app.get(uri, None).await.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                app.get(uri, Some("not-a-token")).await.status,
+                StatusCode::UNAUTHORIZED
+            );
+        }
+
+        assert_eq!(
+            app.get("/api/v1/auth/check", Some(&user.token))
+                .await
+                .status,
+            StatusCode::OK
+        );
+
+        let response = app.get("/api/v1/users/me/role", Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, json!("Usuario"));
+
+        let response = app
+            .put(
+                "/api/v1/users/me",
+                Some(&user.token),
+                user_updating(&user, "Renombrado"),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            app.put("/api/v1/users/me", None, user_updating(&user, "Renombrado"))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.put(
+                "/api/v1/users/me",
+                Some(&user.token),
+                json!({ "nombre": "Renombrado" })
+            )
+            .await
+            .status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let response = app
+            .put(
+                "/api/v1/users/me",
+                Some(&user.token),
+                user_updating(&user, ""),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            response.body["errors"],
+            json!([{ "field": "nombre", "message": "can not be empty" }])
+        );
+
+        let response = app.get("/api/v1/users/me", Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["nombre"], "Renombrado");
+        assert_eq!(response.body["id_persona"], json!(user.id_persona));
+    }
+
+    #[tokio::test]
+    async fn test_admin_user_routes() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let uri = format!("/api/v1/users/{}", user.id_persona);
+        for (token, status) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (Some(&trainer.token), StatusCode::FORBIDDEN),
+            (Some(&admin.token), StatusCode::OK),
+        ] {
+            let response = app
+                .put(
+                    &uri,
+                    token.map(String::as_str),
+                    user_updating(&user, "Editado"),
+                )
+                .await;
+            assert_eq!(response.status, status, "PUT {uri}");
+        }
+
+        let uri = format!("/api/v1/users/{}/role/Entrenador", user.id_persona);
+        for (token, status) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (Some(&trainer.token), StatusCode::FORBIDDEN),
+            (Some(&admin.token), StatusCode::OK),
+        ] {
+            let response = app.put(&uri, token.map(String::as_str), json!({})).await;
+            assert_eq!(response.status, status, "PUT {uri}");
+        }
+        assert_eq!(
+            app.put(
+                &format!("/api/v1/users/{}/role/Rector", user.id_persona),
+                Some(&admin.token),
+                json!({})
+            )
+            .await
+            .status,
+            StatusCode::BAD_REQUEST
+        );
+
+        let response = app
+            .get(
+                &format!("/api/v1/users/{}", user.id_persona),
+                Some(&admin.token),
+            )
+            .await;
+        assert_eq!(response.body["nombre"], "Editado");
+        assert_eq!(response.body["nombre_rol"], "Entrenador");
+    }
+
+    #[tokio::test]
+    async fn test_staff_user_routes() {
+        let app = TestApp::new().await;
+        let trainer = app.create_user("entrenador", UserRol::Entrenador).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let search = "/api/v1/users/search/usuario/Email";
+        let by_identification = format!("/api/v1/users/{}", user.correo);
+        for uri in [search, by_identification.as_str(), "/api/v1/users"] {
+            assert_eq!(app.get(uri, None).await.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(
+                app.get(uri, Some(&user.token)).await.status,
+                StatusCode::FORBIDDEN,
+                "GET {uri}"
+            );
+        }
+
+        let response = app.get(search, Some(&trainer.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["total"], 1);
+        assert_eq!(response.body["items"][0]["matricula_valida"], json!(false));
+        assert_eq!(
+            app.get("/api/v1/users/search/usuario/Cedula", Some(&trainer.token))
+                .await
+                .status,
+            StatusCode::BAD_REQUEST
+        );
+
+        let response = app.get(&by_identification, Some(&trainer.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["id_persona"], json!(user.id_persona));
+        let response = app
+            .get("/api/v1/users/nadie@unisabana.edu.co", Some(&trainer.token))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(response.body["code"], "user_not_found");
+        assert_eq!(
+            response.body["request_id"],
+            response.headers[REQUEST_ID_HEADER].to_str().unwrap()
+        );
+
+        let response = app.get("/api/v1/users", Some(&trainer.token)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["total"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_log_in_routes() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        // A wrong password and an unknown user get the same answer
+        for (identificacion, contrasena) in [
+            (user.correo.as_str(), "otra-clave"),
+            ("nadie@unisabana.edu.co", TEST_PASSWORD),
+        ] {
+            let response = app.log_in(identificacion, contrasena).await;
+            assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+            assert_eq!(response.body["code"], "invalid_credentials");
+        }
+        assert_eq!(
+            app.post("/api/v1/auth/login", None, json!({})).await.status,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            app.post(
+                "/api/v1/auth/login/mfa",
+                None,
+                json!({ "mfa_token": "not-a-token", "code": "123456" })
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let response = app
+            .get("/api/v1/users/me/login_history", Some(&user.token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body.as_array().unwrap().len(), 2);
+        assert_eq!(
+            app.get("/api/v1/users/me/login_history", None).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_in_with_identification_document() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        for identificacion in [
+            user.identificacion.clone(),
+            format!("CC {}", user.identificacion),
+            format!("cc-{}", user.identificacion),
+        ] {
+            let response = app.log_in(&identificacion, TEST_PASSWORD).await;
+            assert_eq!(response.status, StatusCode::OK, "{identificacion}");
+        }
+
+        // Another user with the same number in a document of another type
+        let other = app.create_user("otro", UserRol::Usuario).await;
+        let update = json!({
+            "nombre": "otro",
+            "correo": other.correo,
+            "telefono": other.telefono,
+            "identificacion": user.identificacion,
+            "nombre_tipo_identificacion": "TI"
+        });
+        assert_eq!(
+            app.put("/api/v1/users/me", Some(&other.token), update)
+                .await
+                .status,
+            StatusCode::OK
+        );
+
+        assert_eq!(
+            app.log_in(&user.identificacion, TEST_PASSWORD).await.status,
+            StatusCode::UNAUTHORIZED
+        );
+        for (identificacion, id_persona) in [
+            (format!("CC {}", user.identificacion), &user.id_persona),
+            (format!("TI {}", user.identificacion), &other.id_persona),
+        ] {
+            let login = app.log_in(&identificacion, TEST_PASSWORD).await;
+            assert_eq!(login.status, StatusCode::OK, "{identificacion}");
+
+            let token = login.body["access_token"].as_str().unwrap();
+            let response = app.get("/api/v1/users/me", Some(token)).await;
+            assert_eq!(response.body["id_persona"], json!(id_persona));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lockout_and_unlock() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let mut response = app.log_in(&user.correo, "otra-clave").await;
+        while response.status == StatusCode::UNAUTHORIZED {
+            response = app.log_in(&user.correo, "otra-clave").await;
+        }
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.body["code"], "login_locked");
+        assert!(response.headers.contains_key(RETRY_AFTER));
+
+        let uri = format!("/api/v1/users/{}/unlock", user.id_persona);
+        assert_eq!(
+            app.post(&uri, Some(&user.token), json!({})).await.status,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            app.post(&uri, Some(&admin.token), json!({})).await.status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.post(
+                &format!("/api/v1/users/{}/unlock", uuid::Uuid::new_v4()),
+                Some(&admin.token),
+                json!({})
+            )
+            .await
+            .status,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            app.log_in(&user.correo, TEST_PASSWORD).await.status,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_password_routes() {
+        let app = TestApp::new().await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+        let other_session = app.log_in(&user.correo, TEST_PASSWORD).await;
+
+        let change = |actual: &str| json!({ "contrasena_actual": actual, "contrasena_nueva": "otra-clave-segura" });
+        assert_eq!(
+            app.put("/api/v1/users/me/password", None, change(TEST_PASSWORD))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.put(
+                "/api/v1/users/me/password",
+                Some(&user.token),
+                change("mala-clave")
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        let weak = json!({ "contrasena_actual": TEST_PASSWORD, "contrasena_nueva": "123" });
+        let response = app
+            .put("/api/v1/users/me/password", Some(&user.token), weak)
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response.body["errors"][0]["field"], "contrasena_nueva");
+        assert_eq!(
+            app.put(
+                "/api/v1/users/me/password",
+                Some(&user.token),
+                change(TEST_PASSWORD)
+            )
+            .await
+            .status,
+            StatusCode::OK
+        );
+        assert_eq!(
+            app.log_in(&user.correo, "otra-clave-segura").await.status,
+            StatusCode::OK
+        );
+
+        // Only the session that changed the password survives
+        let refresh = |refresh_token: &str| json!({ "refresh_token": refresh_token });
+        let response = app
+            .post(
+                "/api/v1/token/refresh",
+                None,
+                refresh(other_session.body["refresh_token"].as_str().unwrap()),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        let response = app
+            .post("/api/v1/token/refresh", None, refresh(&user.refresh_token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        for identificacion in [user.correo.as_str(), "nadie@unisabana.edu.co"] {
+            let response = app
+                .post(
+                    "/api/v1/auth/password/forgot",
+                    None,
+                    json!({ "identificacion": identificacion }),
+                )
+                .await;
+            assert_eq!(response.status, StatusCode::ACCEPTED, "{identificacion}");
+        }
+
+        let reset = |token: &str| json!({ "token": token, "contrasena_nueva": TEST_PASSWORD });
+        assert_eq!(
+            app.post("/api/v1/auth/password/reset", None, reset("not-a-token"))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+
+        app.repositories
+            .user
+            .create_password_reset(
+                &user.id_persona,
+                &hash_secret("reset-token"),
+                Utc::now().timestamp() + 60,
+            )
+            .await
+            .unwrap();
+        app.repositories
+            .user
+            .create_password_reset(
+                &user.id_persona,
+                &hash_secret("older-reset-token"),
+                Utc::now().timestamp() + 60,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            app.post("/api/v1/auth/password/reset", None, reset("reset-token"))
+                .await
+                .status,
+            StatusCode::OK
+        );
+        // The reset also withdraws the other tokens of the user
+        assert_eq!(
+            app.post(
+                "/api/v1/auth/password/reset",
+                None,
+                reset("older-reset-token")
+            )
+            .await
+            .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.post("/api/v1/auth/password/reset", None, reset("reset-token"))
+                .await
+                .status,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            app.log_in(&user.correo, TEST_PASSWORD).await.status,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_are_deprecated_aliases() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let user = app.create_user("usuario", UserRol::Usuario).await;
+
+        let response = app
+            .put(
+                &format!("/user/role/Entrenador/{}", user.id_persona),
+                Some(&admin.token),
+                json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.headers.contains_key("deprecation"));
+
+        let response = app.get("/user/admin", Some(&admin.token)).await;
+        assert_eq!(response.body, json!("Admin"));
+        assert!(response.headers.contains_key("sunset"));
+
+        let response = app
+            .post(
+                "/log_in",
+                None,
+                json!({ "identificacion": user.correo, "contrasena": TEST_PASSWORD }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.headers.contains_key("deprecation"));
+        // The old clients take the whole body as the access token
+        let response = app.get("/user", Some(&response.text)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body["correo"], json!(user.correo));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_routes_answer_the_phone_as_a_number() {
+        let app = TestApp::new().await;
+        let admin = app.create_user("admin", UserRol::Admin).await;
+        let phone_number: u64 = admin.telefono.trim_start_matches('+').parse().unwrap();
+
+        let response = app.get("/api/v1/users/me", Some(&admin.token)).await;
+        assert_eq!(response.body["telefono"], json!(admin.telefono));
+
+        let response = app.get("/user", Some(&admin.token)).await;
+        assert_eq!(response.body["telefono"], json!(phone_number));
+
+        let response = app
+            .get(&format!("/user/{}", admin.correo), Some(&admin.token))
+            .await;
+        assert_eq!(response.body["telefono"], json!(phone_number));
+
+        let response = app.get("/user/all", Some(&admin.token)).await;
+        assert_eq!(response.body[0]["telefono"], json!(phone_number));
+
+        let response = app
+            .get("/user/search/admin/UserName/10", Some(&admin.token))
+            .await;
+        assert_eq!(response.body[0]["telefono"], json!(phone_number));
+
+        // Sent back as it was read, the number is the same phone
+        let response = app
+            .put(
+                "/user",
+                Some(&admin.token),
+                json!({
+                    "nombre": "admin",
+                    "correo": admin.correo,
+                    "telefono": phone_number,
+                    "identificacion": admin.identificacion,
+                    "nombre_tipo_identificacion": "CC",
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = app.get("/api/v1/users/me", Some(&admin.token)).await;
+        assert_eq!(response.body["telefono"], json!(admin.telefono));
+    }
 }
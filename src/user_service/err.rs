@@ -1,6 +1,19 @@
+use axum::{
+    http::{header::RETRY_AFTER, StatusCode},
+    response::{IntoResponse, Response},
+};
 use thiserror::Error;
 
-use super::{repository::err::UserRepositoryError, token_provider::TokenProviderError};
+use crate::{
+    api_error::ApiError,
+    mfa_service::err::MfaServiceError,
+    session_service::{err::SessionServiceError, repository::err::SessionRepositoryError},
+};
+
+use super::{
+    notifier::NotifierError, repository::err::UserRepositoryError,
+    token_provider::TokenProviderError, validation::ValidationErrors,
+};
 use std::result;
 
 pub type Result<T> = result::Result<T, UserServiceError>;
@@ -17,6 +30,77 @@ pub enum UserServiceError {
     AuthenticationFailed(String),
     #[error("Token provider error: {0}")]
     TokenProviderError(#[from] TokenProviderError),
+    #[error("Session service error: {0}")]
+    SessionServiceError(#[from] SessionServiceError),
     #[error("User not found: {0}")]
     UserNotFoundError(String),
+    #[error("The password reset token is invalid, expired or already used")]
+    InvalidPasswordResetToken,
+    #[error("Too many failed logins, retry in {retry_after} seconds")]
+    LoginLocked { retry_after: i64 },
+    #[error("MFA service error: {0}")]
+    MfaServiceError(#[from] MfaServiceError),
+    #[error("The mfa token is invalid or expired")]
+    InvalidMfaToken,
+    #[error("Invalid input: {0}")]
+    InvalidInput(#[from] ValidationErrors),
+    #[error("Notifier error: {0}")]
+    NotifierError(#[from] NotifierError),
+    #[error("Transaction error: {0}")]
+    TransactionError(#[from] libsql::Error),
+}
+
+impl IntoResponse for UserServiceError {
+    fn into_response(self) -> Response {
+        match &self {
+            UserServiceError::UserRepoError(UserRepositoryError::UserNotFound)
+            | UserServiceError::UserNotFoundError(_) => {
+                ApiError::not_found("user_not_found", &self)
+            }
+            UserServiceError::UserRepoError(UserRepositoryError::Duplicate { field }) => {
+                ApiError::duplicate(field)
+            }
+            UserServiceError::UserRepoError(UserRepositoryError::ForeignKeyViolation) => {
+                ApiError::invalid_reference()
+            }
+            UserServiceError::InvalidInput(errors) => ApiError::validation(errors.fields()),
+            UserServiceError::AuthenticationFailed(_) => {
+                ApiError::unauthorized("authentication_failed", &self)
+            }
+            UserServiceError::InvalidPasswordResetToken => {
+                ApiError::unauthorized("invalid_password_reset_token", &self)
+            }
+            UserServiceError::InvalidMfaToken => ApiError::unauthorized("invalid_mfa_token", &self),
+            UserServiceError::MfaServiceError(MfaServiceError::InvalidCode) => {
+                ApiError::unauthorized("invalid_mfa_code", &self)
+            }
+            UserServiceError::MfaServiceError(MfaServiceError::NotEnrolled) => {
+                ApiError::not_found("mfa_not_enrolled", &self)
+            }
+            UserServiceError::MfaServiceError(MfaServiceError::AlreadyEnabled) => {
+                ApiError::conflict("mfa_already_enabled", &self)
+            }
+            UserServiceError::SessionServiceError(
+                SessionServiceError::InvalidRefreshToken
+                | SessionServiceError::SessionRevoked
+                | SessionServiceError::SessionExpired
+                | SessionServiceError::RefreshTokenReuse(_)
+                | SessionServiceError::SessionNotFound
+                | SessionServiceError::SessionRepositoryError(
+                    SessionRepositoryError::SessionNotFound,
+                ),
+            ) => ApiError::unauthorized("invalid_session", &self),
+            UserServiceError::LoginLocked { retry_after } => {
+                let retry_after = retry_after.to_string();
+
+                return (
+                    [(RETRY_AFTER, retry_after)],
+                    ApiError::new(StatusCode::TOO_MANY_REQUESTS, "login_locked", &self),
+                )
+                    .into_response();
+            }
+            _ => ApiError::internal(&self),
+        }
+        .into_response()
+    }
 }
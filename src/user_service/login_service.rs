@@ -0,0 +1,418 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use bcrypt::verify;
+use chrono::Utc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::mfa_service::domain::MfaStatus;
+use crate::mfa_service::use_cases::MfaService;
+use crate::session_service::domain::TokenPair;
+use crate::session_service::use_cases::SessionService;
+use crate::unique_identifier_service::usecases::UniqueIdentifier;
+
+use super::domain::{LoginHistoryEntry, LoginOutcome, UserRol};
+use super::err::{Result, UserServiceError};
+use super::repository::{err::UserRepositoryError, UserRepository};
+use super::token_provider::TokenProvider;
+
+const LOGIN_HISTORY_LIMIT: u32 = 50;
+
+/// How many failed logins are tolerated before the caller has to wait, the wait doubles
+/// with every failure until it reaches the lockout.
+#[derive(Debug, Clone, Copy)]
+pub struct BackoffPolicy {
+    free_attempts: u32,
+    lockout_attempts: u32,
+    lockout_seconds: i64,
+}
+
+/// Failed logins of one account, persisted in `persona`.
+pub const ACCOUNT_BACKOFF: BackoffPolicy = BackoffPolicy {
+    free_attempts: 3,
+    lockout_attempts: 10,
+    lockout_seconds: 15 * 60,
+};
+
+/// Failed logins from one address, looser because several users can share it.
+pub const IP_BACKOFF: BackoffPolicy = BackoffPolicy {
+    free_attempts: 10,
+    lockout_attempts: 50,
+    lockout_seconds: 15 * 60,
+};
+
+impl BackoffPolicy {
+    pub fn blocked_until(&self, failed_attempts: u32, last_failure: i64) -> Option<i64> {
+        if failed_attempts < self.free_attempts {
+            return None;
+        }
+
+        if failed_attempts >= self.lockout_attempts {
+            return Some(last_failure + self.lockout_seconds);
+        }
+
+        let exponent = (failed_attempts - self.free_attempts).min(30);
+        let wait = 2_i64.pow(exponent).min(self.lockout_seconds);
+
+        Some(last_failure + wait)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FailedLogins {
+    attempts: u32,
+    blocked_until: Option<i64>,
+    last_failure: i64,
+}
+
+/// In memory failed login tracking per address.
+#[derive(Clone, Default)]
+pub struct IpThrottle {
+    failures: Arc<Mutex<HashMap<String, FailedLogins>>>,
+}
+
+impl IpThrottle {
+    const MAX_TRACKED: usize = 10_000;
+
+    fn blocked_until(&self, ip: &str, now: i64) -> Option<i64> {
+        let failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+
+        failures
+            .get(ip)
+            .and_then(|failed| failed.blocked_until)
+            .filter(|blocked_until| *blocked_until > now)
+    }
+
+    fn record_failure(&self, ip: &str, now: i64) {
+        let mut failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+
+        if failures.len() >= Self::MAX_TRACKED {
+            failures.retain(|_, failed| now - failed.last_failure < IP_BACKOFF.lockout_seconds);
+        }
+
+        let failed = failures.entry(ip.to_string()).or_insert(FailedLogins {
+            attempts: 0,
+            blocked_until: None,
+            last_failure: now,
+        });
+
+        failed.attempts += 1;
+        failed.last_failure = now;
+        failed.blocked_until = IP_BACKOFF.blocked_until(failed.attempts, now);
+    }
+
+    fn record_success(&self, ip: &str) {
+        let mut failures = self.failures.lock().unwrap_or_else(|err| err.into_inner());
+        failures.remove(ip);
+    }
+}
+
+#[derive(Clone)]
+pub struct LoginService {
+    user_repository: Arc<dyn UserRepository>,
+    unique_identifiers: Arc<dyn UniqueIdentifier>,
+    session_service: SessionService,
+    mfa_service: MfaService,
+    token_provider: TokenProvider,
+    ip_throttle: IpThrottle,
+}
+
+impl LoginService {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        unique_identifiers: Arc<dyn UniqueIdentifier>,
+        session_service: SessionService,
+        mfa_service: MfaService,
+        token_provider: TokenProvider,
+    ) -> Self {
+        Self {
+            user_repository,
+            unique_identifiers,
+            session_service,
+            mfa_service,
+            token_provider,
+            ip_throttle: IpThrottle::default(),
+        }
+    }
+
+    pub async fn authenticate_user(
+        &self,
+        identifier: String,
+        password: String,
+        client_ip: &str,
+    ) -> Result<LoginOutcome> {
+        let now = Utc::now().timestamp();
+
+        self.check_ip_backoff(client_ip, now)?;
+
+        let user_id = match self.unique_identifiers.identify(identifier.clone()).await {
+            Some(user_id) => user_id,
+            None => return Err(self.unknown_user(&identifier, client_ip, now)),
+        };
+
+        // The cache of the identifiers can still point at a user that was deleted
+        match self.check_account_lock(&user_id, client_ip, now).await {
+            Err(UserServiceError::UserRepoError(UserRepositoryError::UserNotFound)) => {
+                return Err(self.unknown_user(&identifier, client_ip, now))
+            }
+            result => result?,
+        }
+
+        let stored_password = self.user_repository.get_user_password(&user_id).await?;
+        let is_authenticated = verify(&password, &stored_password)
+            .map_err(|err| UserServiceError::PasswordVerificationError(err.to_string()))?;
+
+        if !is_authenticated {
+            self.record_failed_login(&user_id, client_ip, now).await?;
+
+            return Err(UserServiceError::AuthenticationFailed(
+                "In the password verification, the user is not authenticated".to_string(),
+            ));
+        }
+
+        let user_rol = self.user_repository.user_rol(&user_id).await?;
+
+        // The failed login counter is only reset once the second factor passes too
+        let mfa_verified = match self.mfa_service.status(&user_id, &user_rol).await? {
+            MfaStatus::Enabled => {
+                return Ok(LoginOutcome::MfaPending {
+                    mfa_token: self.token_provider.generate_mfa_token(user_id)?,
+                })
+            }
+            MfaStatus::EnrollmentRequired => false,
+            MfaStatus::NotRequired => true,
+        };
+
+        let token_pair = self
+            .start_session(user_id, user_rol, client_ip, now, mfa_verified)
+            .await?;
+
+        Ok(LoginOutcome::Authenticated(token_pair))
+    }
+
+    /// Second step of the login of the users with MFA, the code can also be a recovery code.
+    pub async fn complete_mfa_login(
+        &self,
+        mfa_token: &str,
+        code: &str,
+        client_ip: &str,
+    ) -> Result<TokenPair> {
+        let now = Utc::now().timestamp();
+
+        self.check_ip_backoff(client_ip, now)?;
+
+        let user_id = self
+            .token_provider
+            .verify_mfa_token(mfa_token)
+            .map_err(|_| UserServiceError::InvalidMfaToken)?
+            .claims
+            .sub;
+
+        self.check_account_lock(&user_id, client_ip, now).await?;
+
+        if !self.mfa_service.verify_login_code(&user_id, code).await? {
+            self.record_failed_login(&user_id, client_ip, now).await?;
+
+            return Err(UserServiceError::AuthenticationFailed(
+                "The MFA code is not valid".to_string(),
+            ));
+        }
+
+        let user_rol = self.user_repository.user_rol(&user_id).await?;
+
+        self.start_session(user_id, user_rol, client_ip, now, true)
+            .await
+    }
+
+    pub async fn unlock_user(&self, user_id: &str) -> Result<()> {
+        self.user_repository.reset_failed_logins(user_id).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_login_history(&self, user_id: &str) -> Result<Vec<LoginHistoryEntry>> {
+        Ok(self
+            .user_repository
+            .get_login_history(user_id, LOGIN_HISTORY_LIMIT)
+            .await?)
+    }
+
+    /// Counts against the address like a wrong password, so guessing identifiers is throttled
+    /// the same way as guessing passwords.
+    fn unknown_user(&self, identifier: &str, client_ip: &str, now: i64) -> UserServiceError {
+        self.ip_throttle.record_failure(client_ip, now);
+
+        UserServiceError::AuthenticationFailed(format!("Cannot identify user with: {identifier}"))
+    }
+
+    fn check_ip_backoff(&self, client_ip: &str, now: i64) -> Result<()> {
+        if let Some(blocked_until) = self.ip_throttle.blocked_until(client_ip, now) {
+            warn!("Login from {client_ip} rejected by the backoff");
+            return Err(UserServiceError::LoginLocked {
+                retry_after: blocked_until - now,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn check_account_lock(&self, user_id: &str, client_ip: &str, now: i64) -> Result<()> {
+        let locked_until = self.user_repository.get_locked_until(user_id).await?;
+
+        if let Some(locked_until) = locked_until.filter(|locked_until| *locked_until > now) {
+            warn!("Login to the locked account {user_id} from {client_ip}");
+            self.ip_throttle.record_failure(client_ip, now);
+            self.record_login(user_id, client_ip, false, now).await;
+            return Err(UserServiceError::LoginLocked {
+                retry_after: locked_until - now,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn record_failed_login(&self, user_id: &str, client_ip: &str, now: i64) -> Result<()> {
+        self.ip_throttle.record_failure(client_ip, now);
+        self.record_login(user_id, client_ip, false, now).await;
+
+        let failed_attempts = self
+            .user_repository
+            .increment_failed_logins(user_id)
+            .await?;
+
+        if let Some(locked_until) = ACCOUNT_BACKOFF.blocked_until(failed_attempts, now) {
+            self.user_repository
+                .lock_user(user_id, locked_until)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn start_session(
+        &self,
+        user_id: String,
+        user_rol: UserRol,
+        client_ip: &str,
+        now: i64,
+        mfa_verified: bool,
+    ) -> Result<TokenPair> {
+        self.ip_throttle.record_success(client_ip);
+        self.user_repository.reset_failed_logins(&user_id).await?;
+        self.record_login(&user_id, client_ip, true, now).await;
+
+        Ok(self
+            .session_service
+            .start_session(user_id, user_rol, mfa_verified)
+            .await?)
+    }
+
+    /// The history is informative, failing to write it must not block the login.
+    async fn record_login(&self, user_id: &str, client_ip: &str, success: bool, now: i64) {
+        let entry = LoginHistoryEntry {
+            id_login: Uuid::new_v4().to_string(),
+            id_persona: user_id.to_string(),
+            ip_address: client_ip.to_string(),
+            success,
+            created_at: now,
+        };
+
+        if let Err(err) = self.user_repository.add_login_history(entry).await {
+            error!("Error recording the login history of {user_id}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::mfa_service::repository::MockMfaRepository;
+    use crate::session_service::repository::MockSessionRepository;
+    use crate::user_service::repository::MockUserRepository;
+
+    const USER_ID: &str = "5f405541-d1df-454a-b2fc-56004ba380cc";
+
+    struct FixedIdentifier;
+
+    #[async_trait]
+    impl UniqueIdentifier for FixedIdentifier {
+        async fn identify(&self, _identification_token: String) -> Option<String> {
+            Some(USER_ID.to_string())
+        }
+
+        fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_backoff_policy() {
+        assert_eq!(ACCOUNT_BACKOFF.blocked_until(2, 100), None);
+        assert_eq!(ACCOUNT_BACKOFF.blocked_until(3, 100), Some(101));
+        assert_eq!(ACCOUNT_BACKOFF.blocked_until(5, 100), Some(104));
+        assert_eq!(ACCOUNT_BACKOFF.blocked_until(10, 100), Some(100 + 15 * 60));
+    }
+
+    fn login_service(user_repo: MockUserRepository) -> LoginService {
+        let user_repo = Arc::new(user_repo);
+        let token_provider =
+            TokenProvider::new("gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=".to_string());
+
+        LoginService::new(
+            user_repo.clone(),
+            Arc::new(FixedIdentifier),
+            SessionService::new(
+                Arc::new(MockSessionRepository::new()),
+                token_provider.clone(),
+            ),
+            MfaService::new(Arc::new(MockMfaRepository::new()), user_repo),
+            token_provider,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_locked_account_skips_password_check() {
+        let mut user_repo = MockUserRepository::new();
+        user_repo
+            .expect_get_locked_until()
+            .returning(|_| Ok(Some(Utc::now().timestamp() + 60)));
+        user_repo.expect_get_user_password().never();
+        user_repo
+            .expect_add_login_history()
+            .withf(|entry| !entry.success && entry.ip_address == "10.0.0.1")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let result = login_service(user_repo)
+            .authenticate_user("3001234567".to_string(), "password".to_string(), "10.0.0.1")
+            .await;
+
+        assert!(matches!(result, Err(UserServiceError::LoginLocked { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_identifier_of_a_deleted_user_fails_like_a_wrong_password() {
+        let mut user_repo = MockUserRepository::new();
+        user_repo
+            .expect_get_locked_until()
+            .returning(|_| Err(UserRepositoryError::UserNotFound));
+        user_repo.expect_get_user_password().never();
+
+        let login_service = login_service(user_repo);
+        let result = login_service
+            .authenticate_user("3001234567".to_string(), "password".to_string(), "10.0.0.1")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(UserServiceError::AuthenticationFailed(_))
+        ));
+        let failures = login_service.ip_throttle.failures.lock().unwrap();
+        assert_eq!(failures["10.0.0.1"].attempts, 1);
+    }
+}
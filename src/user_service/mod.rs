@@ -1,6 +1,10 @@
 pub mod domain;
 pub mod endpoints;
 pub mod err;
+pub mod login_service;
+pub mod notifier;
+pub mod password_service;
 pub mod repository;
 pub mod token_provider;
 pub mod use_cases;
+pub mod validation;
@@ -0,0 +1,68 @@
+use std::{path::PathBuf, result};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use mockall::automock;
+use thiserror::Error;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt};
+use tracing::debug;
+
+use super::domain::UserInfo;
+
+pub type Result<T> = result::Result<T, NotifierError>;
+
+#[derive(Error, Debug)]
+pub enum NotifierError {
+    #[error("Error writing the notification: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+/// Delivers messages to the users out of band, e.g. the password reset tokens.
+#[automock]
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send_password_reset(&self, user: &UserInfo, reset_token: &str) -> Result<()>;
+}
+
+/// Stand-in until there is an email provider, appends the notifications to the outbox file
+/// when one is configured. Without it only the fact that a token was sent is logged, the
+/// token itself would let anyone who reads the logs take over the account.
+pub struct LogNotifier {
+    outbox_file: Option<PathBuf>,
+}
+
+impl LogNotifier {
+    pub fn new(outbox_file: Option<String>) -> Self {
+        Self {
+            outbox_file: outbox_file.map(PathBuf::from),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send_password_reset(&self, user: &UserInfo, reset_token: &str) -> Result<()> {
+        let Some(outbox_file) = &self.outbox_file else {
+            debug!(
+                "Password reset token for {} ({}): [redacted]",
+                user.id_persona, user.correo
+            );
+            return Ok(());
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(outbox_file)
+            .await?;
+
+        let line = format!(
+            "{} to: {} password reset token: {reset_token}\n",
+            Utc::now().to_rfc3339(),
+            user.correo
+        );
+        file.write_all(line.as_bytes()).await?;
+
+        Ok(())
+    }
+}
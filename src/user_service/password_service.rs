@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use bcrypt::verify;
+use chrono::{Duration, Utc};
+use tracing::warn;
+
+use crate::database::UnitOfWork;
+use crate::session_service::use_cases::SessionService;
+use crate::unique_identifier_service::usecases::UniqueIdentifier;
+
+use super::err::{Result, UserServiceError};
+use super::notifier::Notifier;
+use super::repository::UserRepository;
+use super::token_provider::{generate_secret, hash_secret};
+use super::use_cases::UserService;
+use super::validation::validate_new_password;
+
+pub const PASSWORD_RESET_DURATION_MINUTES: i64 = 30;
+
+#[derive(Clone)]
+pub struct PasswordService {
+    user_repository: Arc<dyn UserRepository>,
+    unique_identifiers: Arc<dyn UniqueIdentifier>,
+    session_service: SessionService,
+    notifier: Arc<dyn Notifier>,
+    unit_of_work: UnitOfWork,
+}
+
+impl PasswordService {
+    pub fn new(
+        user_repository: Arc<dyn UserRepository>,
+        unique_identifiers: Arc<dyn UniqueIdentifier>,
+        session_service: SessionService,
+        notifier: Arc<dyn Notifier>,
+        unit_of_work: UnitOfWork,
+    ) -> Self {
+        Self {
+            user_repository,
+            unique_identifiers,
+            session_service,
+            notifier,
+            unit_of_work,
+        }
+    }
+
+    /// Sets the new password and revokes every other session of the user, the one used to
+    /// change it stays open.
+    pub async fn change_password(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        validate_new_password(new_password)?;
+
+        let stored_password = self.user_repository.get_user_password(user_id).await?;
+        let is_authenticated = verify(current_password, &stored_password)
+            .map_err(|err| UserServiceError::PasswordVerificationError(err.to_string()))?;
+
+        if !is_authenticated {
+            return Err(UserServiceError::AuthenticationFailed(
+                "The current password does not match".to_string(),
+            ));
+        }
+
+        let hashed_password = UserService::hash_password(new_password)?;
+        self.user_repository
+            .update_user_password(user_id, &hashed_password)
+            .await?;
+
+        self.session_service
+            .revoke_other_sessions(user_id, session_id)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sends a reset token to the user, an unknown identifier is not an error so the
+    /// endpoint can not be used to find out which users exist.
+    pub async fn request_password_reset(&self, identifier: String) -> Result<()> {
+        let Some(user_id) = self.unique_identifiers.identify(identifier.clone()).await else {
+            warn!("Password reset requested for an unknown user: {identifier}");
+            return Ok(());
+        };
+
+        let user = self.user_repository.get_user_by_id(&user_id).await?;
+        let reset_token = generate_secret();
+        let expires_at = Utc::now().timestamp()
+            + Duration::minutes(PASSWORD_RESET_DURATION_MINUTES).num_seconds();
+
+        self.user_repository
+            .create_password_reset(&user_id, &hash_secret(&reset_token), expires_at)
+            .await?;
+
+        self.notifier
+            .send_password_reset(&user, &reset_token)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Consumes the reset token and sets the new password, the other reset tokens of the
+    /// user and the sessions opened with the old password are revoked.
+    pub async fn reset_password(&self, reset_token: &str, new_password: &str) -> Result<()> {
+        // Checked first, a rejected password does not use up the token
+        validate_new_password(new_password)?;
+        let hashed_password = UserService::hash_password(new_password)?;
+
+        self.unit_of_work
+            .run(async {
+                let user_id = self
+                    .user_repository
+                    .consume_password_reset(&hash_secret(reset_token), Utc::now().timestamp())
+                    .await?
+                    .ok_or(UserServiceError::InvalidPasswordResetToken)?;
+
+                self.user_repository
+                    .invalidate_password_resets(&user_id)
+                    .await?;
+                self.user_repository
+                    .update_user_password(&user_id, &hashed_password)
+                    .await?;
+
+                self.session_service.revoke_user_sessions(&user_id).await?;
+
+                Ok(())
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use mockall::predicate::eq;
+
+    use super::*;
+    use crate::session_service::repository::MockSessionRepository;
+    use crate::user_service::notifier::MockNotifier;
+    use crate::user_service::repository::MockUserRepository;
+    use crate::user_service::token_provider::TokenProvider;
+
+    const USER_ID: &str = "5f405541-d1df-454a-b2fc-56004ba380cc";
+
+    struct NoIdentifier;
+
+    #[async_trait]
+    impl UniqueIdentifier for NoIdentifier {
+        async fn identify(&self, _identification_token: String) -> Option<String> {
+            None
+        }
+
+        fn next(&self) -> Option<Arc<dyn UniqueIdentifier>> {
+            None
+        }
+    }
+
+    fn password_service(
+        user_repo: MockUserRepository,
+        session_repo: MockSessionRepository,
+    ) -> PasswordService {
+        PasswordService::new(
+            Arc::new(user_repo),
+            Arc::new(NoIdentifier),
+            SessionService::new(
+                Arc::new(session_repo),
+                TokenProvider::new("gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=".to_string()),
+            ),
+            Arc::new(MockNotifier::new()),
+            UnitOfWork::disabled(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_revokes_sessions() {
+        let mut user_repo = MockUserRepository::new();
+        user_repo
+            .expect_consume_password_reset()
+            .with(eq(hash_secret("reset-token")), mockall::predicate::always())
+            .returning(|_, _| Ok(Some(USER_ID.to_string())));
+        user_repo
+            .expect_invalidate_password_resets()
+            .with(eq(USER_ID))
+            .times(1)
+            .returning(|_| Ok(()));
+        user_repo
+            .expect_update_user_password()
+            .withf(|user_id, _| user_id == USER_ID)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut session_repo = MockSessionRepository::new();
+        session_repo
+            .expect_revoke_user_sessions()
+            .with(eq(USER_ID))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        password_service(user_repo, session_repo)
+            .reset_password("reset-token", "new password")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_with_used_token() {
+        let mut user_repo = MockUserRepository::new();
+        user_repo
+            .expect_consume_password_reset()
+            .returning(|_, _| Ok(None));
+        user_repo.expect_update_user_password().never();
+
+        let result = password_service(user_repo, MockSessionRepository::new())
+            .reset_password("reset-token", "new password")
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(UserServiceError::InvalidPasswordResetToken)
+        ));
+    }
+}
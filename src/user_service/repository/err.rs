@@ -1,16 +1,22 @@
 use std::result;
 use thiserror::Error;
 
+use crate::database::from_libsql_error;
+
 pub type Result<T> = result::Result<T, UserRepositoryError>;
 
 #[derive(Error, Debug)]
 pub enum UserRepositoryError {
     #[error("Internal database error: {0}")]
-    InternalDbError(#[from] Box<dyn std::error::Error>),
+    InternalDbError(#[from] Box<dyn std::error::Error + Send + Sync>),
     #[error("User not found")]
     UserNotFound,
     #[error("Database connection error: {0}")]
-    ConnectionError(#[from] libsql::Error),
+    ConnectionError(libsql::Error),
+    #[error("Another row already has this {field}")]
+    Duplicate { field: String },
+    #[error("The row references another one that does not exist")]
+    ForeignKeyViolation,
     #[error("Error deserializing into a struct form the database: {0}")]
     DeserializationError(#[from] serde::de::value::Error),
     #[error("chrono date operation error: {0}")]
@@ -18,3 +24,5 @@ pub enum UserRepositoryError {
     #[error("serde json error: {0}")]
     SerdeJsonError(#[from] serde_json::Error),
 }
+
+from_libsql_error!(UserRepositoryError, UserRepositoryError::ConnectionError);
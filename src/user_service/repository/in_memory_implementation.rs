@@ -0,0 +1,430 @@
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::in_memory::{InMemoryStore, PasswordResetRow, PersonaRow, Tables};
+use crate::pagination::{paginate, ListParams, Page};
+use crate::user_service::domain::{
+    LoginHistoryEntry, SearchSelection, UserCreationInfo, UserInfo, UserRol, UserSelectionInfo,
+    UserUpdating,
+};
+
+use super::err::{Result, UserRepositoryError};
+use super::UserRepository;
+
+/// Days a tuition keeps the user enabled, the same window as the search query of the
+/// libsql repository.
+const TUITION_VALID_DAYS: i64 = 30;
+
+#[derive(Clone)]
+pub struct InMemoryUserRepository {
+    store: InMemoryStore,
+}
+
+impl InMemoryUserRepository {
+    pub fn new(store: InMemoryStore) -> Self {
+        Self { store }
+    }
+
+    fn with_persona<T>(&self, user_id: &str, f: impl FnOnce(&mut PersonaRow) -> T) -> Result<T> {
+        self.store
+            .tables()
+            .persona
+            .get_mut(user_id)
+            .map(f)
+            .ok_or(UserRepositoryError::UserNotFound)
+    }
+}
+
+fn has_valid_tuition(tables: &Tables, user_id: &str) -> bool {
+    let today = Utc::now().date_naive();
+
+    tables
+        .matricula
+        .iter()
+        .filter(|tuition| tuition.id_persona == user_id)
+        .map(|tuition| tuition.fecha_inscripccion)
+        .max()
+        .is_some_and(|last_tuition| (today - last_tuition).num_days() <= TUITION_VALID_DAYS)
+}
+
+/// The unique column of `persona` that another user already holds, like the unique
+/// indexes of the libsql schema.
+fn duplicate_field(
+    tables: &Tables,
+    user_id: &str,
+    correo: &str,
+    telefono: &str,
+    nombre_tipo_identificacion: &str,
+    identificacion: &str,
+) -> Option<&'static str> {
+    let others = || {
+        tables
+            .persona
+            .values()
+            .map(|persona| &persona.user)
+            .filter(|user| user.id_persona != user_id)
+    };
+
+    if others().any(|user| user.correo == correo) {
+        Some("correo")
+    } else if others().any(|user| user.telefono == telefono) {
+        Some("telefono")
+    } else if others().any(|user| {
+        user.nombre_tipo_identificacion == nombre_tipo_identificacion
+            && user.identificacion == identificacion
+    }) {
+        Some("identificacion")
+    } else {
+        None
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn create_user(&self, user_creation_info: UserCreationInfo) -> Result<()> {
+        let id_persona = uuid::Uuid::new_v4().to_string();
+        let mut tables = self.store.tables();
+
+        if let Some(field) = duplicate_field(
+            &tables,
+            &id_persona,
+            &user_creation_info.correo,
+            &user_creation_info.telefono,
+            &user_creation_info.nombre_tipo_identificacion,
+            &user_creation_info.identificacion,
+        ) {
+            return Err(UserRepositoryError::Duplicate {
+                field: field.to_string(),
+            });
+        }
+
+        tables.persona.insert(
+            id_persona.clone(),
+            PersonaRow {
+                user: UserInfo {
+                    id_persona,
+                    nombre: user_creation_info.nombre,
+                    correo: user_creation_info.correo,
+                    telefono: user_creation_info.telefono,
+                    identificacion: user_creation_info.identificacion,
+                    nombre_tipo_identificacion: user_creation_info.nombre_tipo_identificacion,
+                    nombre_rol: UserRol::Usuario,
+                },
+                contrasena: user_creation_info.contrasena,
+                failed_login_attempts: 0,
+                locked_until: None,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn get_user_password(&self, user_id: &str) -> Result<String> {
+        self.with_persona(user_id, |persona| persona.contrasena.clone())
+    }
+
+    async fn list_users(&self, params: &ListParams) -> Result<Page<UserInfo>> {
+        let users = self
+            .store
+            .tables()
+            .persona
+            .values()
+            .map(|persona| persona.user.clone())
+            .collect();
+
+        Ok(paginate(users, params))
+    }
+
+    async fn get_user_by_id(&self, user_id: &str) -> Result<UserInfo> {
+        self.with_persona(user_id, |persona| persona.user.clone())
+    }
+
+    async fn user_rol(&self, user_id: &str) -> Result<UserRol> {
+        self.with_persona(user_id, |persona| persona.user.nombre_rol.clone())
+    }
+
+    async fn modify_user(&self, updated_user_info: UserUpdating, user_id: &str) -> Result<()> {
+        if let Some(field) = duplicate_field(
+            &self.store.tables(),
+            user_id,
+            &updated_user_info.correo,
+            &updated_user_info.telefono,
+            &updated_user_info.nombre_tipo_identificacion,
+            &updated_user_info.identificacion,
+        ) {
+            return Err(UserRepositoryError::Duplicate {
+                field: field.to_string(),
+            });
+        }
+
+        // An UPDATE that matches no row is not an error either
+        let _ = self.with_persona(user_id, |persona| {
+            persona.user.nombre = updated_user_info.nombre;
+            persona.user.correo = updated_user_info.correo;
+            persona.user.telefono = updated_user_info.telefono;
+            persona.user.identificacion = updated_user_info.identificacion;
+            persona.user.nombre_tipo_identificacion = updated_user_info.nombre_tipo_identificacion;
+        });
+
+        Ok(())
+    }
+
+    async fn search_users_by_search_selection(
+        &self,
+        search: &str,
+        search_parameter: SearchSelection,
+        params: &ListParams,
+    ) -> Result<Page<UserSelectionInfo>> {
+        let tables = self.store.tables();
+        let search = search.to_lowercase();
+
+        let users = tables
+            .persona
+            .values()
+            .map(|persona| &persona.user)
+            .filter(|user| {
+                let column = match search_parameter {
+                    SearchSelection::Email => user.correo.clone(),
+                    SearchSelection::PhoneNumber => user.telefono.clone(),
+                    SearchSelection::UserName => user.nombre.clone(),
+                };
+
+                column.to_lowercase().contains(&search)
+            })
+            .map(|user| UserSelectionInfo {
+                id_persona: user.id_persona.clone(),
+                nombre: user.nombre.clone(),
+                correo: user.correo.clone(),
+                telefono: user.telefono.clone(),
+                identificacion: user.identificacion.clone(),
+                nombre_tipo_identificacion: user.nombre_tipo_identificacion.clone(),
+                nombre_rol: user.nombre_rol.clone(),
+                matricula_valida: has_valid_tuition(&tables, &user.id_persona),
+            })
+            .collect();
+
+        Ok(paginate(users, params))
+    }
+
+    async fn update_user_role(&self, user_role: UserRol, user_id: &str) -> Result<()> {
+        let _ = self.with_persona(user_id, |persona| persona.user.nombre_rol = user_role);
+
+        Ok(())
+    }
+
+    async fn update_user_password(&self, user_id: &str, hashed_password: &str) -> Result<()> {
+        self.with_persona(user_id, |persona| {
+            persona.contrasena = hashed_password.to_string()
+        })
+    }
+
+    async fn create_password_reset(
+        &self,
+        user_id: &str,
+        token_hash: &str,
+        expires_at: i64,
+    ) -> Result<()> {
+        self.store.tables().password_reset.insert(
+            token_hash.to_string(),
+            PasswordResetRow {
+                id_persona: user_id.to_string(),
+                expires_at,
+                used: false,
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn consume_password_reset(&self, token_hash: &str, now: i64) -> Result<Option<String>> {
+        let mut tables = self.store.tables();
+
+        match tables.password_reset.get_mut(token_hash) {
+            Some(reset) if !reset.used && reset.expires_at > now => {
+                reset.used = true;
+                Ok(Some(reset.id_persona.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn invalidate_password_resets(&self, user_id: &str) -> Result<()> {
+        self.store
+            .tables()
+            .password_reset
+            .values_mut()
+            .filter(|reset| reset.id_persona == user_id)
+            .for_each(|reset| reset.used = true);
+
+        Ok(())
+    }
+
+    async fn get_locked_until(&self, user_id: &str) -> Result<Option<i64>> {
+        self.with_persona(user_id, |persona| persona.locked_until)
+    }
+
+    async fn increment_failed_logins(&self, user_id: &str) -> Result<u32> {
+        self.with_persona(user_id, |persona| {
+            persona.failed_login_attempts += 1;
+            persona.failed_login_attempts
+        })
+    }
+
+    async fn lock_user(&self, user_id: &str, locked_until: i64) -> Result<()> {
+        let _ = self.with_persona(user_id, |persona| persona.locked_until = Some(locked_until));
+
+        Ok(())
+    }
+
+    async fn reset_failed_logins(&self, user_id: &str) -> Result<()> {
+        self.with_persona(user_id, |persona| {
+            persona.failed_login_attempts = 0;
+            persona.locked_until = None;
+        })
+    }
+
+    async fn add_login_history(&self, entry: LoginHistoryEntry) -> Result<()> {
+        self.store.tables().login_history.push(entry);
+
+        Ok(())
+    }
+
+    async fn get_login_history(&self, user_id: &str, limit: u32) -> Result<Vec<LoginHistoryEntry>> {
+        let mut history: Vec<LoginHistoryEntry> = self
+            .store
+            .tables()
+            .login_history
+            .iter()
+            .filter(|entry| entry.id_persona == user_id)
+            .cloned()
+            .collect();
+
+        history.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+        history.truncate(limit as usize);
+
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::in_memory::MatriculaRow;
+
+    fn user(nombre: &str, telefono: u64) -> UserCreationInfo {
+        UserCreationInfo {
+            nombre: nombre.to_string(),
+            contrasena: "hash".to_string(),
+            correo: format!("{nombre}@unisabana.edu.co"),
+            telefono: format!("+57{telefono}"),
+            identificacion: format!("10{telefono}"),
+            nombre_tipo_identificacion: "CC".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_marks_tuitions_older_than_30_days_as_invalid() {
+        let store = InMemoryStore::new();
+        let user_repository = InMemoryUserRepository::new(store.clone());
+
+        user_repository
+            .create_user(user("valida", 3001234567))
+            .await
+            .unwrap();
+        user_repository
+            .create_user(user("vencida", 3001234568))
+            .await
+            .unwrap();
+        user_repository
+            .create_user(user("ninguna", 3001234569))
+            .await
+            .unwrap();
+
+        let today = Utc::now().date_naive();
+        {
+            let mut tables = store.tables();
+            let ids: Vec<(String, String)> = tables
+                .persona
+                .values()
+                .map(|p| (p.user.nombre.clone(), p.user.id_persona.clone()))
+                .collect();
+
+            for (nombre, id_persona) in ids {
+                let fechas = match nombre.as_str() {
+                    "valida" => vec![today - Duration::days(60), today - Duration::days(30)],
+                    "vencida" => vec![today - Duration::days(31)],
+                    _ => vec![],
+                };
+
+                for fecha_inscripccion in fechas {
+                    let id_matricula = tables.matricula.len() as i64 + 1;
+                    tables.matricula.push(MatriculaRow {
+                        id_matricula,
+                        id_persona: id_persona.clone(),
+                        monto_usd: 20.0,
+                        fecha_inscripccion,
+                    });
+                }
+            }
+        }
+
+        let users = user_repository
+            .search_users_by_search_selection(
+                "unisabana",
+                SearchSelection::Email,
+                &ListParams::new::<UserSelectionInfo>(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(users.total, 3);
+        for user in users.items {
+            assert_eq!(
+                user.matricula_valida,
+                user.nombre == "valida",
+                "{}",
+                user.nombre
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_user_rejects_duplicate_fields() {
+        let user_repository = InMemoryUserRepository::new(InMemoryStore::new());
+        user_repository
+            .create_user(user("ana", 3001234567))
+            .await
+            .unwrap();
+
+        let duplicates = [
+            ("correo", user("ana", 3009999999)),
+            ("telefono", user("beto", 3001234567)),
+            (
+                "identificacion",
+                UserCreationInfo {
+                    identificacion: "103001234567".to_string(),
+                    ..user("carla", 3008888888)
+                },
+            ),
+        ];
+
+        for (expected, duplicate) in duplicates {
+            match user_repository.create_user(duplicate).await {
+                Err(UserRepositoryError::Duplicate { field }) => assert_eq!(field, expected),
+                other => panic!("expected a duplicate {expected}, got {other:?}"),
+            }
+        }
+
+        // The same number under another type of document is another person
+        user_repository
+            .create_user(UserCreationInfo {
+                identificacion: "103001234567".to_string(),
+                nombre_tipo_identificacion: "TI".to_string(),
+                ..user("dario", 3007777777)
+            })
+            .await
+            .unwrap();
+    }
+}
@@ -1,3 +1,6 @@
+use crate::database::{DatabaseHandle, PooledConnection};
+use crate::pagination::{count_rows, ListParams, Page};
+use crate::user_service::domain::LoginHistoryEntry;
 use crate::user_service::domain::SearchSelection;
 use crate::user_service::domain::UserCreationInfo;
 use crate::user_service::domain::UserInfo;
@@ -5,11 +8,8 @@ use crate::user_service::domain::UserRol;
 use crate::user_service::domain::UserSelectionInfo;
 use crate::user_service::domain::UserUpdating;
 use async_trait::async_trait;
-use libsql::{de, params, Connection, Database};
-use serde::Deserialize;
+use libsql::{de, params};
 use serde_json::json;
-use std::collections::HashMap;
-use std::result;
 use std::sync::Arc;
 use tracing::info;
 
@@ -18,21 +18,16 @@ use super::UserRepository;
 
 #[derive(Clone)]
 pub struct LibSqlUserRepository {
-    db: Arc<Database>,
+    db: Arc<DatabaseHandle>,
 }
 
 impl LibSqlUserRepository {
-    pub async fn new(url: &str, token: &str) -> result::Result<Arc<dyn UserRepository>, String> {
-        let db = libsql::Builder::new_remote(url.to_string(), token.to_string())
-            .build()
-            .await
-            .map_err(|err| format!("Error creating new remote database for libsql: {err}"))?;
-
-        Ok(Arc::new(Self { db: Arc::new(db) }))
+    pub fn new(db: Arc<DatabaseHandle>) -> Self {
+        Self { db }
     }
 
-    async fn get_connection(&self) -> Result<Connection> {
-        Ok(self.db.connect()?)
+    async fn get_connection(&self) -> Result<PooledConnection> {
+        Ok(self.db.connect().await?)
     }
 }
 
@@ -61,10 +56,10 @@ impl UserRepository for LibSqlUserRepository {
         Ok(())
     }
 
-    async fn get_user_password(&self, user_id: &String) -> Result<String> {
+    async fn get_user_password(&self, user_id: &str) -> Result<String> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT contrasena FROM persona WHERE id_persona = ?1 LIMIT 1",
                 params![user_id.to_string()],
             )
@@ -78,12 +73,25 @@ impl UserRepository for LibSqlUserRepository {
         }
     }
 
-    async fn get_users(&self) -> Result<Vec<UserInfo>> {
+    async fn list_users(&self, params: &ListParams) -> Result<Page<UserInfo>> {
         let conn = self.get_connection().await?;
+        let conditions = params.sql_conditions("");
+
+        let total = count_rows(
+            &conn,
+            &format!("SELECT COUNT(*) FROM persona WHERE {conditions}"),
+            params.filter_values(),
+        )
+        .await?;
+
         let mut rows = conn
-            .query(
-                "SELECT id_persona, nombre, correo, telefono, identificacion, nombre_tipo_identificacion, nombre_rol FROM persona",
-                libsql::params![],
+            .read(
+                &format!(
+                    "SELECT id_persona, nombre, correo, telefono, identificacion, nombre_tipo_identificacion, nombre_rol
+                    FROM persona WHERE {conditions} {}",
+                    params.sql_page("")
+                ),
+                params.filter_values(),
             )
             .await?;
 
@@ -93,15 +101,15 @@ impl UserRepository for LibSqlUserRepository {
             users.push(user);
         }
 
-        Ok(users)
+        Ok(Page::new(users, total, params))
     }
 
-    async fn get_user_by_id(&self, user_id: &String) -> Result<UserInfo> {
+    async fn get_user_by_id(&self, user_id: &str) -> Result<UserInfo> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT id_persona, nombre, correo, telefono, identificacion, nombre_tipo_identificacion, nombre_rol FROM persona WHERE id_persona = ?1",
-                libsql::params![user_id.clone()],
+                libsql::params![user_id],
             )
             .await?;
 
@@ -115,10 +123,10 @@ impl UserRepository for LibSqlUserRepository {
     async fn search_users_by_search_selection(
         &self,
         search: &str,
-        limit: u8,
         search_parameter: SearchSelection,
-    ) -> Result<Vec<UserSelectionInfo>> {
-        info!("Executing with search: |{search}|, limit: |{limit}| and seach parameter: |{search_parameter:?}|");
+        params: &ListParams,
+    ) -> Result<Page<UserSelectionInfo>> {
+        info!("Executing with search: |{search}|, seach parameter: |{search_parameter:?}| and {params:?}");
 
         let conn = self.get_connection().await?;
 
@@ -137,17 +145,26 @@ impl UserRepository for LibSqlUserRepository {
                 END AS matricula_valida
                 FROM persona p
                 LEFT JOIN matricula m ON p.id_persona = m.id_persona
-                WHERE p.{} LIKE ?1
+                WHERE p.{column} LIKE ? AND {conditions}
                 GROUP BY p.id_persona
-                LIMIT ?2",
-            column
+                {page}",
+            conditions = params.sql_conditions("p."),
+            page = params.sql_page("p."),
         );
+        let mut values = vec![libsql::Value::Text(format!("%{search}%"))];
+        values.extend(params.filter_values());
+
+        let total = count_rows(
+            &conn,
+            &format!(
+                "SELECT COUNT(*) FROM persona p WHERE p.{column} LIKE ? AND {}",
+                params.sql_conditions("p.")
+            ),
+            values.clone(),
+        )
+        .await?;
 
-        let limit_i64 = limit as i64;
-
-        let mut rows = conn
-            .query(&query, params![format!("%{}%", search), limit_i64])
-            .await?;
+        let mut rows = conn.read(&query, values).await?;
 
         let mut users = Vec::new();
 
@@ -157,7 +174,7 @@ impl UserRepository for LibSqlUserRepository {
             users.push(temp_user);
         }
 
-        Ok(users)
+        Ok(Page::new(users, total, params))
     }
 
     async fn modify_user(&self, updated_user_info: UserUpdating, user_id: &str) -> Result<()> {
@@ -180,7 +197,7 @@ impl UserRepository for LibSqlUserRepository {
     async fn user_rol(&self, user_id: &str) -> Result<UserRol> {
         let conn = self.get_connection().await?;
         let mut rows = conn
-            .query(
+            .read(
                 "SELECT nombre_rol FROM persona WHERE id_persona = ?1 LIMIT 1",
                 params![user_id],
             )
@@ -206,4 +223,174 @@ impl UserRepository for LibSqlUserRepository {
 
         Ok(())
     }
+
+    async fn update_user_password(&self, user_id: &str, hashed_password: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        let updated = conn
+            .execute(
+                "UPDATE persona SET contrasena = ?1 WHERE id_persona = ?2",
+                params![hashed_password, user_id],
+            )
+            .await?;
+
+        if updated == 0 {
+            return Err(UserRepositoryError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn create_password_reset(
+        &self,
+        user_id: &str,
+        token_hash: &str,
+        expires_at: i64,
+    ) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "INSERT INTO password_reset (token_hash, id_persona, expires_at, used)
+            VALUES (?1, ?2, ?3, FALSE)",
+            params![token_hash, user_id, expires_at],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn consume_password_reset(&self, token_hash: &str, now: i64) -> Result<Option<String>> {
+        let conn = self.get_connection().await?;
+
+        // Single statement, so two concurrent resets with the same token can not both win
+        let mut rows = conn
+            .query(
+                "UPDATE password_reset SET used = TRUE
+                WHERE token_hash = ?1 AND used = FALSE AND expires_at > ?2
+                RETURNING id_persona",
+                params![token_hash, now],
+            )
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get::<String>(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn invalidate_password_resets(&self, user_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "UPDATE password_reset SET used = TRUE WHERE id_persona = ?1 AND used = FALSE",
+            params![user_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_locked_until(&self, user_id: &str) -> Result<Option<i64>> {
+        let conn = self.get_connection().await?;
+        let mut rows = conn
+            .read(
+                "SELECT locked_until FROM persona WHERE id_persona = ?1 LIMIT 1",
+                params![user_id],
+            )
+            .await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(row.get::<Option<i64>>(0)?)
+        } else {
+            Err(UserRepositoryError::UserNotFound)
+        }
+    }
+
+    async fn increment_failed_logins(&self, user_id: &str) -> Result<u32> {
+        let conn = self.get_connection().await?;
+        let mut rows = conn
+            .query(
+                "UPDATE persona SET failed_login_attempts = failed_login_attempts + 1
+                WHERE id_persona = ?1
+                RETURNING failed_login_attempts",
+                params![user_id],
+            )
+            .await?;
+
+        if let Some(row) = rows.next().await? {
+            Ok(row.get::<u32>(0)?)
+        } else {
+            Err(UserRepositoryError::UserNotFound)
+        }
+    }
+
+    async fn lock_user(&self, user_id: &str, locked_until: i64) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "UPDATE persona SET locked_until = ?1 WHERE id_persona = ?2",
+            params![locked_until, user_id],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn reset_failed_logins(&self, user_id: &str) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        let updated = conn
+            .execute(
+                "UPDATE persona SET failed_login_attempts = 0, locked_until = NULL
+                WHERE id_persona = ?1",
+                params![user_id],
+            )
+            .await?;
+
+        if updated == 0 {
+            return Err(UserRepositoryError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn add_login_history(&self, entry: LoginHistoryEntry) -> Result<()> {
+        let conn = self.get_connection().await?;
+
+        conn.execute(
+            "INSERT INTO login_history (id_login, id_persona, ip_address, success, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.id_login,
+                entry.id_persona,
+                entry.ip_address,
+                entry.success,
+                entry.created_at
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_login_history(&self, user_id: &str, limit: u32) -> Result<Vec<LoginHistoryEntry>> {
+        let conn = self.get_connection().await?;
+        let mut rows = conn
+            .read(
+                "SELECT id_login, id_persona, ip_address, success, created_at FROM login_history
+                WHERE id_persona = ?1
+                ORDER BY created_at DESC
+                LIMIT ?2",
+                params![user_id, limit],
+            )
+            .await?;
+
+        let mut history = Vec::new();
+
+        while let Some(row) = rows.next().await? {
+            history.push(de::from_row::<LoginHistoryEntry>(&row)?);
+        }
+
+        Ok(history)
+    }
 }
@@ -2,6 +2,7 @@ use super::domain::SearchSelection;
 use super::domain::UserCreationInfo;
 
 pub mod err;
+use super::domain::LoginHistoryEntry;
 use super::domain::UserInfo;
 use super::domain::UserRol;
 use super::domain::UserSelectionInfo;
@@ -10,6 +11,10 @@ use async_trait::async_trait;
 use err::Result;
 use mockall::automock;
 
+use crate::pagination::{ListParams, Page};
+
+#[cfg(any(test, feature = "in-memory"))]
+pub mod in_memory_implementation;
 pub mod libsql_implementation;
 
 #[automock]
@@ -18,7 +23,7 @@ pub trait UserRepository: Send + Sync {
     async fn create_user(&self, user_creation_info: UserCreationInfo) -> Result<()>;
     async fn get_user_password(&self, user_id: &str) -> Result<String>;
 
-    async fn get_users(&self) -> Result<Vec<UserInfo>>;
+    async fn list_users(&self, params: &ListParams) -> Result<Page<UserInfo>>;
     async fn get_user_by_id(&self, user_id: &str) -> Result<UserInfo>;
 
     async fn user_rol(&self, user_id: &str) -> Result<UserRol>;
@@ -28,9 +33,41 @@ pub trait UserRepository: Send + Sync {
     async fn search_users_by_search_selection(
         &self,
         search: &str,
-        limit: u8,
         search_parameter: SearchSelection,
-    ) -> Result<Vec<UserSelectionInfo>>;
+        params: &ListParams,
+    ) -> Result<Page<UserSelectionInfo>>;
 
     async fn update_user_role(&self, user_role: UserRol, user_id: &str) -> Result<()>;
+
+    async fn update_user_password(&self, user_id: &str, hashed_password: &str) -> Result<()>;
+
+    async fn create_password_reset(
+        &self,
+        user_id: &str,
+        token_hash: &str,
+        expires_at: i64,
+    ) -> Result<()>;
+
+    /// Marks the reset token as used and returns the user it belongs to, only when the
+    /// token was still unused and not expired at `now`.
+    async fn consume_password_reset(&self, token_hash: &str, now: i64) -> Result<Option<String>>;
+
+    /// Marks every unused reset token of the user as used, the ones requested before a
+    /// reset can not change the new password.
+    async fn invalidate_password_resets(&self, user_id: &str) -> Result<()>;
+
+    /// Unix timestamp until which the user can not log in, if the account is locked.
+    async fn get_locked_until(&self, user_id: &str) -> Result<Option<i64>>;
+
+    /// Adds one to the failed login counter and returns the new value.
+    async fn increment_failed_logins(&self, user_id: &str) -> Result<u32>;
+
+    async fn lock_user(&self, user_id: &str, locked_until: i64) -> Result<()>;
+
+    /// Clears the failed login counter and the lock of the user.
+    async fn reset_failed_logins(&self, user_id: &str) -> Result<()>;
+
+    async fn add_login_history(&self, entry: LoginHistoryEntry) -> Result<()>;
+
+    async fn get_login_history(&self, user_id: &str, limit: u32) -> Result<Vec<LoginHistoryEntry>>;
 }
@@ -1,13 +1,45 @@
 use chrono::{Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, TokenData, Validation};
+use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
-use std::result;
+use sha2::{Digest, Sha256};
+use std::{fmt::Display, result};
 use thiserror::Error;
 
+use super::domain::UserRol;
+
+pub const REFRESH_TOKEN_DURATION_DAYS: i64 = 30;
+
+const TOKEN_ISSUER: &str = "sabana_club_backend";
+const TOKEN_AUDIENCE: &str = "sabana_club";
+/// Audience of the mfa pending tokens, so they are never accepted as access tokens
+const MFA_TOKEN_AUDIENCE: &str = "sabana_club_mfa";
+const MFA_TOKEN_DURATION_MINUTES: i64 = 5;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    pub iat: usize,
+    /// Id of the session that issued the token, used to revoke it
+    pub jti: String,
+    pub nombre_rol: UserRol,
+    /// The session passed the MFA policy of its role, role restricted routes require it
+    #[serde(default)]
+    pub mfa: bool,
+    pub iss: String,
+    pub aud: String,
+}
+
+/// Claims of the token handed out after the password check when the second factor is
+/// still missing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaClaims {
+    pub sub: String,
+    pub exp: usize,
+    pub iat: usize,
+    pub iss: String,
+    pub aud: String,
 }
 
 #[derive(Clone)]
@@ -20,8 +52,15 @@ impl TokenProvider {
         Self { token_key }
     }
 
-    pub fn generate_token(&self, client_id: String) -> Result<String> {
-        let expiration = Utc::now()
+    pub fn generate_token(
+        &self,
+        client_id: String,
+        nombre_rol: UserRol,
+        session_id: String,
+        mfa: bool,
+    ) -> Result<String> {
+        let now = Utc::now();
+        let expiration = now
             .checked_add_signed(Duration::hours(1))
             .expect("valid timestamp")
             .timestamp() as usize;
@@ -29,6 +68,12 @@ impl TokenProvider {
         let claims = Claims {
             sub: client_id,
             exp: expiration,
+            iat: now.timestamp() as usize,
+            jti: session_id,
+            nombre_rol,
+            mfa,
+            iss: TOKEN_ISSUER.to_string(),
+            aud: TOKEN_AUDIENCE.to_string(),
         };
 
         let token = encode(
@@ -43,6 +88,48 @@ impl TokenProvider {
     pub fn verify_token(&self, token: &str) -> Result<TokenData<Claims>> {
         let mut validation = Validation::new(jsonwebtoken::Algorithm::HS256);
         validation.leeway = 60;
+        validation.set_issuer(&[TOKEN_ISSUER]);
+        validation.set_audience(&[TOKEN_AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+
+        let token_data = decode(
+            token,
+            &DecodingKey::from_secret(self.token_key.as_ref()),
+            &validation,
+        )?;
+
+        Ok(token_data)
+    }
+
+    pub fn generate_mfa_token(&self, user_id: String) -> Result<String> {
+        let now = Utc::now();
+        let expiration = now
+            .checked_add_signed(Duration::minutes(MFA_TOKEN_DURATION_MINUTES))
+            .expect("valid timestamp")
+            .timestamp() as usize;
+
+        let claims = MfaClaims {
+            sub: user_id,
+            exp: expiration,
+            iat: now.timestamp() as usize,
+            iss: TOKEN_ISSUER.to_string(),
+            aud: MFA_TOKEN_AUDIENCE.to_string(),
+        };
+
+        let token = encode(
+            &Header::new(jsonwebtoken::Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.token_key.as_ref()),
+        )?;
+
+        Ok(token)
+    }
+
+    pub fn verify_mfa_token(&self, token: &str) -> Result<TokenData<MfaClaims>> {
+        let mut validation = Validation::new(jsonwebtoken::Algorithm::HS256);
+        validation.set_issuer(&[TOKEN_ISSUER]);
+        validation.set_audience(&[MFA_TOKEN_AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
 
         let token_data = decode(
             token,
@@ -54,6 +141,60 @@ impl TokenProvider {
     }
 }
 
+/// Opaque refresh token with the shape `<session_id>.<secret>`, only the hash of the
+/// secret is stored, so a leaked sessions table can not be used to refresh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshToken {
+    pub session_id: String,
+    secret: String,
+}
+
+impl RefreshToken {
+    pub fn new(session_id: String) -> Self {
+        Self {
+            session_id,
+            secret: generate_secret(),
+        }
+    }
+
+    pub fn parse(token: &str) -> Option<Self> {
+        let (session_id, secret) = token.split_once('.')?;
+
+        if session_id.is_empty() || secret.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            session_id: session_id.to_string(),
+            secret: secret.to_string(),
+        })
+    }
+
+    pub fn hash(&self) -> String {
+        hash_secret(&self.secret)
+    }
+}
+
+impl Display for RefreshToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.session_id, self.secret)
+    }
+}
+
+/// Random alphanumeric secret for opaque tokens.
+pub fn generate_secret() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(48)
+        .map(char::from)
+        .collect()
+}
+
+/// Hex sha256 of an opaque token secret, the form in which it is stored.
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
 type Result<T> = result::Result<T, TokenProviderError>;
 
 #[derive(Error, Debug)]
@@ -68,9 +209,45 @@ fn test_token_provider_validity() {
         TokenProvider::new("gxQy0CBeYonc3UByo72Q24B7K8EizgRo0NfzxMdwEoQ=".to_string());
 
     let token_result = token_provider
-        .generate_token("esteban".to_string())
+        .generate_token(
+            "esteban".to_string(),
+            UserRol::Entrenador,
+            "session".to_string(),
+            true,
+        )
         .unwrap();
     let claims = token_provider.verify_token(&token_result).unwrap();
 
     assert_eq!(claims.claims.sub, "esteban");
+    assert_eq!(claims.claims.nombre_rol, UserRol::Entrenador);
+    assert_eq!(claims.claims.jti, "session");
+
+    let mfa_token = token_provider
+        .generate_mfa_token("esteban".to_string())
+        .unwrap();
+
+    assert!(token_provider.verify_token(&mfa_token).is_err());
+    assert!(token_provider.verify_token(&token_result).is_ok());
+    assert_eq!(
+        token_provider
+            .verify_mfa_token(&mfa_token)
+            .unwrap()
+            .claims
+            .sub,
+        "esteban"
+    );
+}
+
+#[test]
+fn test_refresh_token_round_trip() {
+    let refresh_token = RefreshToken::new("5f405541-d1df-454a-b2fc-56004ba380cc".to_string());
+
+    let parsed = RefreshToken::parse(&refresh_token.to_string()).unwrap();
+
+    assert_eq!(parsed, refresh_token);
+    assert_eq!(parsed.hash(), refresh_token.hash());
+    assert_ne!(
+        RefreshToken::new(refresh_token.session_id.clone()).hash(),
+        refresh_token.hash()
+    );
 }
@@ -1,7 +1,11 @@
 use std::sync::Arc;
 
-use bcrypt::{hash, verify, DEFAULT_COST};
+use bcrypt::{hash, DEFAULT_COST};
 
+use crate::database::after_commit;
+use crate::pagination::{ListParams, Page};
+use crate::phone::{strip_separators, PhoneNormalizer};
+use crate::session_service::use_cases::SessionService;
 use crate::unique_identifier_service::usecases::UniqueIdentifier;
 
 use super::domain::{
@@ -9,33 +13,54 @@ use super::domain::{
 };
 use super::err::{Result, UserServiceError};
 use super::repository::UserRepository;
-use super::token_provider::TokenProvider;
+use super::validation::{validate_user_creation, validate_user_updating};
 
 #[derive(Clone)]
 pub struct UserService {
     user_repository: Arc<dyn UserRepository>,
     unique_identifiers: Arc<dyn UniqueIdentifier>,
-    token_provider: TokenProvider,
+    session_service: SessionService,
+    phone_normalizer: PhoneNormalizer,
 }
 
 impl UserService {
     pub fn new(
         user_repository: Arc<dyn UserRepository>,
         unique_identifiers: Arc<dyn UniqueIdentifier>,
-        token_provider: TokenProvider,
+        session_service: SessionService,
+        phone_normalizer: PhoneNormalizer,
     ) -> Self {
         Self {
             user_repository,
             unique_identifiers,
-            token_provider,
+            session_service,
+            phone_normalizer,
         }
     }
 
+    /// The phone in E.164, left as it came when it is not a phone so the validation
+    /// reports it.
+    fn normalize_phone(&self, telefono: String) -> String {
+        self.phone_normalizer
+            .normalize(&telefono)
+            .unwrap_or(telefono)
+    }
+
+    /// Clears the resolved identifiers once the change is committed, a request run as a
+    /// unit of work could otherwise have them resolved again from the old rows.
+    fn identifiers_changed(&self) {
+        let unique_identifiers = self.unique_identifiers.clone();
+        after_commit(move || unique_identifiers.identifiers_changed());
+    }
+
     pub async fn update_user_rol(&self, user_role: UserRol, user_id: &str) -> Result<()> {
         self.user_repository
             .update_user_role(user_role, user_id)
             .await?;
 
+        // Tokens carry the role, the old ones can not keep the previous permissions
+        self.session_service.revoke_user_sessions(user_id).await?;
+
         Ok(())
     }
 
@@ -48,21 +73,36 @@ impl UserService {
     pub async fn search_user_by_search_selection(
         &self,
         search: &str,
-        limit: u8,
         search_selection: SearchSelection,
-    ) -> Result<Vec<UserSelectionInfo>> {
+        params: &ListParams,
+    ) -> Result<Page<UserSelectionInfo>> {
+        // Stored phones have no separators, "318 592" has to find "+573185920708"
+        let search = match search_selection {
+            SearchSelection::PhoneNumber => strip_separators(search),
+            _ => search.to_string(),
+        };
+
         let users_selection_info = self
             .user_repository
-            .search_users_by_search_selection(search, limit, search_selection)
+            .search_users_by_search_selection(&search, search_selection, params)
             .await?;
 
         Ok(users_selection_info)
     }
 
     pub async fn update_user(&self, user_update_info: UserUpdating, user_id: &str) -> Result<()> {
+        let user_update_info = UserUpdating {
+            nombre_tipo_identificacion: user_update_info.nombre_tipo_identificacion.to_uppercase(),
+            identificacion: user_update_info.identificacion.to_uppercase(),
+            telefono: self.normalize_phone(user_update_info.telefono),
+            ..user_update_info
+        };
+        validate_user_updating(&user_update_info)?;
+
         self.user_repository
             .modify_user(user_update_info, user_id)
             .await?;
+        self.identifiers_changed();
 
         Ok(())
     }
@@ -71,30 +111,25 @@ impl UserService {
         &self,
         user_creation_info: UserCreationInfo,
     ) -> Result<()> {
-        let hashed_password = Self::hash_password(&user_creation_info.contrasena)?;
-
-        let hashed_user_info = UserCreationInfo {
-            contrasena: hashed_password,
+        let user_creation_info = UserCreationInfo {
             nombre_tipo_identificacion: user_creation_info
                 .nombre_tipo_identificacion
                 .to_uppercase(),
+            // The letters of foreign documents are looked up in uppercase
+            identificacion: user_creation_info.identificacion.to_uppercase(),
+            telefono: self.normalize_phone(user_creation_info.telefono),
             ..user_creation_info
         };
+        validate_user_creation(&user_creation_info)?;
 
-        self.user_repository.create_user(hashed_user_info).await?;
-        Ok(())
-    }
-
-    pub async fn create_user(&self, user_creation_info: UserCreationInfo) -> Result<()> {
         let hashed_user_info = UserCreationInfo {
-            contrasena: user_creation_info.contrasena,
-            nombre_tipo_identificacion: user_creation_info
-                .nombre_tipo_identificacion
-                .to_uppercase(),
+            contrasena: Self::hash_password(&user_creation_info.contrasena)?,
             ..user_creation_info
         };
 
         self.user_repository.create_user(hashed_user_info).await?;
+        self.identifiers_changed();
+
         Ok(())
     }
 
@@ -102,35 +137,11 @@ impl UserService {
         let hashed_password = hash(password, DEFAULT_COST)
             .map_err(|err| UserServiceError::PasswordHashError(err.to_string()))?;
 
-        return Ok(hashed_password);
-    }
-
-    pub async fn authenticate_user(&self, identifier: String, password: String) -> Result<String> {
-        let user_id = match self.unique_identifiers.identify(identifier.clone()).await {
-            Some(user_id) => user_id,
-            None => {
-                return Err(UserServiceError::AuthenticationFailed(format!(
-                    "Cannot identify user with: {identifier}"
-                )))
-            }
-        };
-
-        let stored_password = self.user_repository.get_user_password(&user_id).await?;
-        let is_authenticated = verify(&password, &stored_password)
-            .map_err(|err| UserServiceError::PasswordVerificationError(err.to_string()))?;
-
-        if !is_authenticated {
-            return Err(UserServiceError::AuthenticationFailed(
-                "In the password verification, the user is not authenticated".to_string(),
-            ));
-        }
-
-        let token = self.token_provider.generate_token(user_id)?;
-        Ok(token)
+        Ok(hashed_password)
     }
 
-    pub async fn get_users(&self) -> Result<Vec<UserInfo>> {
-        Ok(self.user_repository.get_users().await?)
+    pub async fn list_users(&self, params: &ListParams) -> Result<Page<UserInfo>> {
+        Ok(self.user_repository.list_users(params).await?)
     }
 
     pub async fn get_user_by_identification(&self, identification: String) -> Result<UserInfo> {
@@ -0,0 +1,300 @@
+//! Rules for the user payloads, checked before anything is stored. Every broken rule is
+//! reported with the field it belongs to so the client can show it next to the input.
+
+use std::fmt::Display;
+
+use crate::{api_error::FieldError, phone::is_e164};
+
+use super::domain::{UserCreationInfo, UserUpdating};
+
+/// Identification documents accepted by the club.
+pub const TIPOS_IDENTIFICACION: [&str; 4] = ["CC", "TI", "CE", "PASAPORTE"];
+
+const MAX_NOMBRE_LENGTH: usize = 100;
+const MAX_CORREO_LENGTH: usize = 254;
+const MAX_IDENTIFICACION_LENGTH: usize = 20;
+const MIN_CONTRASENA_LENGTH: usize = 10;
+/// bcrypt ignores whatever comes after the 72nd byte
+const MAX_CONTRASENA_BYTES: usize = 72;
+
+/// The broken rules of a payload, never empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    pub fn fields(&self) -> &[FieldError] {
+        &self.0
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors: Vec<String> = self
+            .0
+            .iter()
+            .map(|error| format!("{} {}", error.field, error.message))
+            .collect();
+
+        write!(f, "{}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+#[derive(Default)]
+struct Validator(Vec<FieldError>);
+
+impl Validator {
+    fn check(mut self, field: &str, rule: std::result::Result<(), &str>) -> Self {
+        if let Err(message) = rule {
+            self.0.push(FieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        }
+
+        self
+    }
+
+    fn finish(self) -> Result<(), ValidationErrors> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationErrors(self.0))
+        }
+    }
+}
+
+/// Expects the type of identification in uppercase and the phone normalized.
+pub fn validate_user_creation(user: &UserCreationInfo) -> Result<(), ValidationErrors> {
+    Validator::default()
+        .check("nombre", check_nombre(&user.nombre))
+        .check("contrasena", check_contrasena(&user.contrasena))
+        .check("correo", check_correo(&user.correo))
+        .check("telefono", check_telefono(&user.telefono))
+        .check(
+            "nombre_tipo_identificacion",
+            check_tipo_identificacion(&user.nombre_tipo_identificacion),
+        )
+        .check(
+            "identificacion",
+            check_identificacion(&user.nombre_tipo_identificacion, &user.identificacion),
+        )
+        .finish()
+}
+
+/// Expects the type of identification in uppercase and the phone normalized.
+pub fn validate_user_updating(user: &UserUpdating) -> Result<(), ValidationErrors> {
+    Validator::default()
+        .check("nombre", check_nombre(&user.nombre))
+        .check("correo", check_correo(&user.correo))
+        .check("telefono", check_telefono(&user.telefono))
+        .check(
+            "nombre_tipo_identificacion",
+            check_tipo_identificacion(&user.nombre_tipo_identificacion),
+        )
+        .check(
+            "identificacion",
+            check_identificacion(&user.nombre_tipo_identificacion, &user.identificacion),
+        )
+        .finish()
+}
+
+/// The password policy for a password that replaces the current one.
+pub fn validate_new_password(contrasena_nueva: &str) -> Result<(), ValidationErrors> {
+    Validator::default()
+        .check("contrasena_nueva", check_contrasena(contrasena_nueva))
+        .finish()
+}
+
+fn check_nombre(nombre: &str) -> std::result::Result<(), &'static str> {
+    let length = nombre.trim().chars().count();
+
+    if length == 0 {
+        Err("can not be empty")
+    } else if length > MAX_NOMBRE_LENGTH {
+        Err("can not have more than 100 characters")
+    } else {
+        Ok(())
+    }
+}
+
+fn check_correo(correo: &str) -> std::result::Result<(), &'static str> {
+    let Some((local, domain)) = correo.split_once('@') else {
+        return Err("is not a valid email address");
+    };
+
+    let valid_local = !local.is_empty()
+        && local.len() <= 64
+        && local.split('.').all(|part| !part.is_empty())
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_domain = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+
+    if correo.len() <= MAX_CORREO_LENGTH && valid_local && valid_domain {
+        Ok(())
+    } else {
+        Err("is not a valid email address")
+    }
+}
+
+fn check_telefono(telefono: &str) -> std::result::Result<(), &'static str> {
+    if is_e164(telefono) {
+        Ok(())
+    } else {
+        Err("is not a valid phone number")
+    }
+}
+
+fn check_tipo_identificacion(tipo: &str) -> std::result::Result<(), &'static str> {
+    if TIPOS_IDENTIFICACION.contains(&tipo) {
+        Ok(())
+    } else {
+        Err("must be one of CC, TI, CE or PASAPORTE")
+    }
+}
+
+/// Colombian documents are only digits, foreign ones can have letters.
+fn check_identificacion(tipo: &str, identificacion: &str) -> std::result::Result<(), &'static str> {
+    let numeric = matches!(tipo, "CC" | "TI");
+
+    if identificacion.is_empty() {
+        Err("can not be empty")
+    } else if identificacion.len() > MAX_IDENTIFICACION_LENGTH {
+        Err("can not have more than 20 characters")
+    } else if numeric && !identificacion.chars().all(|c| c.is_ascii_digit()) {
+        Err("can only have digits for this type of identification")
+    } else if !identificacion.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Err("can only have letters and digits")
+    } else {
+        Ok(())
+    }
+}
+
+fn check_contrasena(contrasena: &str) -> std::result::Result<(), &'static str> {
+    let classes = [
+        contrasena.chars().any(|c| c.is_lowercase()),
+        contrasena.chars().any(|c| c.is_uppercase()),
+        contrasena.chars().any(|c| c.is_numeric()),
+        contrasena.chars().any(|c| !c.is_alphanumeric()),
+    ];
+
+    if contrasena.chars().count() < MIN_CONTRASENA_LENGTH {
+        Err("must have at least 10 characters")
+    } else if contrasena.len() > MAX_CONTRASENA_BYTES {
+        Err("can not have more than 72 bytes")
+    } else if classes.into_iter().filter(|class| *class).count() < 2 {
+        Err("must mix at least two of lowercase letters, uppercase letters, digits and symbols")
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_creation() -> UserCreationInfo {
+        UserCreationInfo {
+            nombre: "Esteban".to_string(),
+            contrasena: "una-clave-segura".to_string(),
+            correo: "estebanmff@gmail.com".to_string(),
+            telefono: "+573185920708".to_string(),
+            identificacion: "1014739191".to_string(),
+            nombre_tipo_identificacion: "CC".to_string(),
+        }
+    }
+
+    fn invalid_fields(user: &UserCreationInfo) -> Vec<String> {
+        validate_user_creation(user)
+            .err()
+            .map(|errors| errors.fields().iter().map(|e| e.field.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_valid_user_passes() {
+        assert_eq!(validate_user_creation(&user_creation()), Ok(()));
+        assert_eq!(
+            validate_user_creation(&UserCreationInfo {
+                identificacion: "AB123456".to_string(),
+                nombre_tipo_identificacion: "PASAPORTE".to_string(),
+                ..user_creation()
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_every_broken_rule_is_reported() {
+        let user = UserCreationInfo {
+            nombre: "   ".to_string(),
+            contrasena: "corta".to_string(),
+            correo: "esteban@".to_string(),
+            telefono: "123".to_string(),
+            identificacion: "10-14".to_string(),
+            nombre_tipo_identificacion: "NIT".to_string(),
+        };
+
+        assert_eq!(
+            invalid_fields(&user),
+            [
+                "nombre",
+                "contrasena",
+                "correo",
+                "telefono",
+                "nombre_tipo_identificacion",
+                "identificacion"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_email_syntax() {
+        for correo in ["a@unisabana.edu.co", "nombre.apellido+club@gmail.com"] {
+            assert_eq!(check_correo(correo), Ok(()), "{correo}");
+        }
+
+        for correo in [
+            "",
+            "sin-arroba",
+            "@gmail.com",
+            "a@gmail",
+            "a@@gmail.com",
+            "a b@gmail.com",
+            "a..b@gmail.com",
+            "a@-gmail.com",
+            "a@gmail..com",
+        ] {
+            assert!(check_correo(correo).is_err(), "{correo}");
+        }
+    }
+
+    #[test]
+    fn test_identification_depends_on_the_type() {
+        assert!(check_identificacion("CC", "AB123").is_err());
+        assert!(check_identificacion("TI", "1234567").is_ok());
+        assert!(check_identificacion("CE", "AB123").is_ok());
+        assert!(check_identificacion("PASAPORTE", "AB 123").is_err());
+    }
+
+    #[test]
+    fn test_password_policy() {
+        assert!(check_contrasena("una-clave-segura").is_ok());
+        assert!(check_contrasena("Clave12345").is_ok());
+
+        for contrasena in ["Corta1!", "solominusculas", "1234567890", &"a1".repeat(40)] {
+            assert!(check_contrasena(contrasena).is_err(), "{contrasena}");
+        }
+    }
+}